@@ -0,0 +1,162 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BUILTIN_AGENTS: &str = include_str!("agents.toml");
+
+/// How an agent expects its slash-command files to be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandFormat {
+    /// `<name>.md` with Claude's placeholders
+    Md,
+    /// Gemini's `<name>.toml`
+    Toml,
+    /// Copilot's `<name>.prompt.md` with `${input:...}` placeholders
+    Prompt,
+}
+
+impl CommandFormat {
+    /// File name suffix after the command name, without the leading dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            CommandFormat::Md => "md",
+            CommandFormat::Toml => "toml",
+            CommandFormat::Prompt => crate::converter::copilot::PROMPT_EXTENSION,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AgentDefinition {
+    pub name: String,
+    pub folder: String,
+    #[serde(default)]
+    pub install_url: Option<String>,
+    #[serde(default)]
+    pub requires_cli: bool,
+    #[serde(default)]
+    pub cli: Option<String>,
+    #[serde(default = "default_version_args")]
+    pub version_args: Vec<String>,
+    /// Oldest CLI version `bl check` accepts; templates may raise it further.
+    #[serde(default)]
+    pub min_version: Option<String>,
+    pub skills_dir: String,
+    pub commands_dir: String,
+    pub command_format: CommandFormat,
+    pub context_file: String,
+    /// Project paths the agent may write credentials to.
+    #[serde(default)]
+    pub credential_paths: Vec<String>,
+}
+
+fn default_version_args() -> Vec<String> {
+    vec!["--version".to_string()]
+}
+
+/// Agent definitions merged from the built-in table and any override files.
+pub struct AgentRegistry {
+    agents: BTreeMap<String, AgentDefinition>,
+}
+
+impl AgentRegistry {
+    /// Load the built-in agents, then apply the organisation file named by
+    /// `BL1NK_AGENTS_FILE` and finally the user file, so later files win.
+    pub fn load() -> Result<Self> {
+        let mut merged = parse_agents_table(BUILTIN_AGENTS, Path::new("<built-in>"))?;
+
+        for path in override_files() {
+            if !path.exists() {
+                continue;
+            }
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read agent overrides from {}", path.display()))?;
+            merge_agents_table(&mut merged, parse_agents_table(&content, &path)?);
+        }
+
+        let mut agents = BTreeMap::new();
+        for (key, table) in merged {
+            let definition: AgentDefinition = toml::Value::Table(table)
+                .try_into()
+                .with_context(|| format!("Invalid definition for agent '{}'", key))?;
+            agents.insert(key, definition);
+        }
+
+        Ok(Self { agents })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&AgentDefinition> {
+        self.agents.get(key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.agents.contains_key(key)
+    }
+
+    pub fn keys(&self) -> Vec<&str> {
+        self.agents.keys().map(String::as_str).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &AgentDefinition)> {
+        self.agents.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Global skills directory for `agent`; unknown agents fall back to `~/.<agent>/skills`.
+    pub fn skills_dir(&self, home: &Path, agent: &str) -> PathBuf {
+        match self.agents.get(agent) {
+            Some(def) => home.join(&def.skills_dir),
+            None => home.join(format!(".{}/skills", agent)),
+        }
+    }
+}
+
+impl AgentDefinition {
+    /// Binary probed by `init` and `check`; IDE-based agents have none.
+    pub fn cli_binary(&self) -> Option<&str> {
+        self.cli.as_deref()
+    }
+}
+
+fn override_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Some(org) = env::var_os("BL1NK_AGENTS_FILE") {
+        files.extend(env::split_paths(&org));
+    }
+    if let Some(config) = dirs::config_dir() {
+        files.push(config.join("bl1nk").join("agents.toml"));
+    }
+    files
+}
+
+fn parse_agents_table(content: &str, source: &Path) -> Result<BTreeMap<String, toml::Table>> {
+    let mut root: toml::Table = content
+        .parse()
+        .with_context(|| format!("Invalid agent file {}", source.display()))?;
+
+    let Some(agents) = root.remove("agents") else {
+        return Ok(BTreeMap::new());
+    };
+    let toml::Value::Table(agents) = agents else {
+        bail!("'agents' in {} must be a table", source.display());
+    };
+
+    let mut result = BTreeMap::new();
+    for (key, value) in agents {
+        let toml::Value::Table(table) = value else {
+            bail!("Agent '{}' in {} must be a table", key, source.display());
+        };
+        result.insert(key, table);
+    }
+    Ok(result)
+}
+
+/// Field-level merge so an override file only needs the keys it changes.
+fn merge_agents_table(base: &mut BTreeMap<String, toml::Table>, overrides: BTreeMap<String, toml::Table>) {
+    for (key, table) in overrides {
+        base.entry(key).or_default().extend(table);
+    }
+}
@@ -0,0 +1,433 @@
+use super::claude::resolve_author;
+use super::ir::{Author, Skill};
+use super::{convert_skill, detect_formats, ConversionResult, Format};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tokio::fs;
+
+/// Written to the output root after every batch.
+pub const REPORT_FILE: &str = "conversion-report.json";
+/// Index of the extensions in a Gemini batch; Gemini CLI has no marketplace format.
+pub const GEMINI_INDEX_FILE: &str = "gemini-extensions.json";
+
+// ============================================================================
+// Models
+// ============================================================================
+
+/// A skill directory found below the batch root.
+#[derive(Debug, Clone)]
+pub struct SkillSource {
+    pub path: PathBuf,
+    /// Location below the batch root, mirrored under the output root
+    pub relative: PathBuf,
+    /// The detected format, or why none could be picked
+    pub format: std::result::Result<Format, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkillReport {
+    pub name: String,
+    pub source: String,
+    pub output: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<&'static str>,
+    #[serde(flatten)]
+    pub result: ConversionResult,
+    #[serde(skip)]
+    skill: Option<Skill>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    pub to: &'static str,
+    pub source: String,
+    pub output: String,
+    pub succeeded: usize,
+    pub failed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<String>,
+    pub warnings: Vec<String>,
+    pub skills: Vec<SkillReport>,
+}
+
+/// `.claude-plugin/marketplace.json` listing every converted skill.
+#[derive(Debug, Clone, Serialize)]
+struct MarketplaceIndex {
+    name: String,
+    owner: Author,
+    plugins: Vec<MarketplaceEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MarketplaceEntry {
+    name: String,
+    source: String,
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    /// `false` lets the entry stand in for a missing `plugin.json`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    strict: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skills: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct GeminiIndex {
+    extensions: Vec<GeminiIndexEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct GeminiIndexEntry {
+    name: String,
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    path: String,
+}
+
+// ============================================================================
+// Discovery
+// ============================================================================
+
+/// Find the skill directories below `root`. With `from`, only directories in
+/// that format count; otherwise the format is detected per directory. Hidden
+/// directories, `skip` and the inside of a found skill are not searched.
+pub fn find_skills(root: &Path, from: Option<Format>, skip: &Path) -> Result<Vec<SkillSource>> {
+    let mut skills = Vec::new();
+    let mut walker = walkdir::WalkDir::new(root).sort_by_file_name().into_iter();
+
+    while let Some(entry) = walker.next() {
+        let entry = entry?;
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        let path = entry.path();
+        let hidden = entry.depth() > 0 && entry.file_name().to_string_lossy().starts_with('.');
+        if hidden || (entry.depth() > 0 && path == skip) {
+            walker.skip_current_dir();
+            continue;
+        }
+
+        let formats = detect_formats(path);
+        let format = match from {
+            Some(from) if formats.contains(&from) => Ok(from),
+            Some(_) => continue,
+            None => match formats.as_slice() {
+                [] => continue,
+                [format] => Ok(*format),
+                formats => Err(format!(
+                    "matches several formats ({}); pass --from",
+                    formats.iter().map(|f| f.as_str()).collect::<Vec<_>>().join(", ")
+                )),
+            },
+        };
+        skills.push(SkillSource {
+            path: path.to_path_buf(),
+            relative: path.strip_prefix(root).unwrap_or(path).to_path_buf(),
+            format,
+        });
+        walker.skip_current_dir();
+    }
+
+    Ok(skills)
+}
+
+// ============================================================================
+// Conversion
+// ============================================================================
+
+/// Convert every skill to `to` below `output` on up to `jobs` threads, then
+/// write the target's index and the report.
+pub fn convert_all(skills: &[SkillSource], to: Format, source: &Path, output: &Path, jobs: usize) -> Result<BatchReport> {
+    let next = AtomicUsize::new(0);
+    let reports: Mutex<Vec<Option<SkillReport>>> = Mutex::new(vec![None; skills.len()]);
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, skills.len().max(1)) {
+            scope.spawn(|| {
+                // Each worker drives its own conversions; they share no state
+                let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build();
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(skill) = skills.get(i) else {
+                        break;
+                    };
+                    let report = match &runtime {
+                        Ok(runtime) => runtime.block_on(convert_one(skill, to, output)),
+                        Err(e) => failed_report(skill, output, None, format!("Failed to start worker: {}", e)),
+                    };
+                    reports.lock().unwrap()[i] = Some(report);
+                }
+            });
+        }
+    });
+
+    let skills: Vec<SkillReport> = reports.into_inner().unwrap().into_iter().flatten().collect();
+    let mut report = BatchReport {
+        to: to.as_str(),
+        source: source.display().to_string(),
+        output: output.display().to_string(),
+        succeeded: 0,
+        failed: 0,
+        index: None,
+        warnings: Vec::new(),
+        skills,
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    runtime.block_on(async {
+        match write_index(&mut report, output).await {
+            Ok(path) => report.index = path,
+            Err(e) => report.warnings.push(format!("Failed to write the index: {}", e)),
+        }
+        report.succeeded = report.skills.iter().filter(|s| s.result.success).count();
+        report.failed = report.skills.len() - report.succeeded;
+
+        fs::create_dir_all(output).await?;
+        fs::write(output.join(REPORT_FILE), serde_json::to_string_pretty(&report)?).await?;
+        Ok::<_, anyhow::Error>(())
+    })?;
+
+    Ok(report)
+}
+
+async fn convert_one(skill: &SkillSource, to: Format, output: &Path) -> SkillReport {
+    let from = match &skill.format {
+        Ok(from) => *from,
+        Err(reason) => return failed_report(skill, output, None, reason.clone()),
+    };
+    if from == to {
+        return failed_report(skill, output, Some(from), format!("already in {} format", to.as_str()));
+    }
+
+    let target = output.join(&skill.relative);
+    match convert_skill(from, to, &skill.path, &target).await {
+        Ok((result, converted)) => SkillReport {
+            name: converted.as_ref().map(|s| s.name.clone()).unwrap_or_else(|| dir_name(skill)),
+            source: skill.path.display().to_string(),
+            output: target.display().to_string(),
+            from: Some(from.as_str()),
+            result,
+            skill: converted,
+        },
+        Err(e) => failed_report(skill, output, Some(from), format!("{:#}", e)),
+    }
+}
+
+fn failed_report(skill: &SkillSource, output: &Path, from: Option<Format>, error: String) -> SkillReport {
+    SkillReport {
+        name: dir_name(skill),
+        source: skill.path.display().to_string(),
+        output: output.join(&skill.relative).display().to_string(),
+        from: from.map(Format::as_str),
+        result: ConversionResult {
+            success: false,
+            errors: vec![error],
+            ..Default::default()
+        },
+        skill: None,
+    }
+}
+
+fn dir_name(skill: &SkillSource) -> String {
+    skill
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| skill.path.display().to_string())
+}
+
+// ============================================================================
+// Index
+// ============================================================================
+
+/// Write the index the target format uses to list several skills, if it has one.
+async fn write_index(report: &mut BatchReport, output: &Path) -> Result<Option<String>> {
+    let to = Format::parse(report.to).unwrap_or(Format::Gemini);
+    if !matches!(to, Format::Claude | Format::ClaudePlugin | Format::Gemini) {
+        report
+            .warnings
+            .push(format!("{} has no index of several skills; none written", to.as_str()));
+        return Ok(None);
+    }
+
+    // Two entries with one name would shadow each other
+    let mut listed: BTreeMap<String, String> = BTreeMap::new();
+    let mut entries = Vec::new();
+    for skill_report in &mut report.skills {
+        let Some(skill) = skill_report.skill.as_ref().filter(|_| skill_report.result.success) else {
+            continue;
+        };
+        if let Some(first) = listed.get(&skill.name) {
+            skill_report.result.warnings.push(format!(
+                "Name {} is already used by {}; left out of the index",
+                skill.name, first
+            ));
+            continue;
+        }
+        listed.insert(skill.name.clone(), skill_report.source.clone());
+        let relative = Path::new(&skill_report.output)
+            .strip_prefix(output)
+            .unwrap_or(Path::new("."))
+            .to_string_lossy()
+            .replace('\\', "/");
+        let source = if relative.is_empty() { "./".to_string() } else { format!("./{}", relative) };
+        entries.push((skill, source));
+    }
+
+    let path = match to {
+        Format::Gemini => {
+            let index = GeminiIndex {
+                extensions: entries
+                    .iter()
+                    .map(|(skill, source)| GeminiIndexEntry {
+                        name: skill.name.clone(),
+                        description: skill.description.clone(),
+                        version: skill.version.clone(),
+                        path: source.clone(),
+                    })
+                    .collect(),
+            };
+            fs::create_dir_all(output).await?;
+            let path = output.join(GEMINI_INDEX_FILE);
+            fs::write(&path, serde_json::to_string_pretty(&index)?).await?;
+            path
+        }
+        _ => {
+            let name = output
+                .canonicalize()
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+                .unwrap_or_else(|| "skills".to_string());
+            let owner = entries
+                .iter()
+                .find_map(|(skill, _)| skill.author.clone())
+                .or_else(|| entries.first().and_then(|(skill, _)| resolve_author(skill)))
+                .unwrap_or_else(|| Author {
+                    name: name.clone(),
+                    email: None,
+                });
+            let bare_skills = to == Format::Claude;
+            let index = MarketplaceIndex {
+                name: format!("{}-marketplace", name),
+                owner,
+                plugins: entries
+                    .iter()
+                    .map(|(skill, source)| MarketplaceEntry {
+                        name: skill.name.clone(),
+                        source: source.clone(),
+                        description: skill.description.clone(),
+                        version: skill.version.clone(),
+                        // A bare SKILL.md directory has no plugin.json of its own
+                        strict: bare_skills.then_some(false),
+                        skills: bare_skills.then(|| vec!["./".to_string()]),
+                    })
+                    .collect(),
+            };
+            let dir = output.join(".claude-plugin");
+            fs::create_dir_all(&dir).await?;
+            let path = dir.join("marketplace.json");
+            fs::write(&path, serde_json::to_string_pretty(&index)?).await?;
+            path
+        }
+    };
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write_skill(dir: &Path, name: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(
+            dir.join("SKILL.md"),
+            format!("---\nname: {}\ndescription: The {} skill\n---\nDo {}.\n", name, name, name),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_find_skills() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write_skill(&root.join("alpha"), "alpha");
+        write_skill(&root.join("group").join("beta"), "beta");
+        write_skill(&root.join(".hidden"), "hidden");
+        write_skill(&root.join("dist").join("gamma"), "gamma");
+        fs::create_dir_all(root.join("both")).unwrap();
+        fs::write(root.join("both").join("SKILL.md"), "").unwrap();
+        fs::write(root.join("both").join("gemini-extension.json"), "{}").unwrap();
+
+        let skills = find_skills(root, None, &root.join("dist")).unwrap();
+        let relative: Vec<_> = skills.iter().map(|s| s.relative.to_string_lossy().to_string()).collect();
+        assert_eq!(relative, vec!["alpha", "both", "group/beta"]);
+        assert!(skills[1].format.is_err());
+
+        let skills = find_skills(root, Some(Format::Gemini), &root.join("dist")).unwrap();
+        assert_eq!(skills.len(), 1);
+        assert_eq!(skills[0].format, Ok(Format::Gemini));
+    }
+
+    #[test]
+    fn test_convert_all_to_gemini() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("skills");
+        let output = temp_dir.path().join("dist");
+        write_skill(&root.join("alpha"), "alpha");
+        write_skill(&root.join("beta"), "beta");
+        write_skill(&root.join("copy"), "alpha");
+        fs::create_dir_all(root.join("broken")).unwrap();
+        fs::write(root.join("broken").join("SKILL.md"), "no frontmatter").unwrap();
+
+        let skills = find_skills(&root, None, &output).unwrap();
+        let report = convert_all(&skills, Format::Gemini, &root, &output, 2).unwrap();
+
+        let names: Vec<_> = report.skills.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["alpha", "beta", "broken", "alpha"]);
+        assert_eq!((report.succeeded, report.failed), (3, 1));
+        assert!(output.join("beta").join("gemini-extension.json").exists());
+        assert!(report.skills[3].result.warnings.iter().any(|w| w.contains("left out of the index")));
+
+        let index: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output.join(GEMINI_INDEX_FILE)).unwrap()).unwrap();
+        assert_eq!(index["extensions"].as_array().unwrap().len(), 2);
+        assert_eq!(index["extensions"][1]["path"], "./beta");
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output.join(REPORT_FILE)).unwrap()).unwrap();
+        assert_eq!(saved["failed"], 1);
+        assert_eq!(saved["skills"][2]["success"], false);
+    }
+
+    #[test]
+    fn test_convert_all_writes_marketplace() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("skills");
+        let output = temp_dir.path().join("dist");
+        write_skill(&root.join("alpha"), "alpha");
+
+        let skills = find_skills(&root, None, &output).unwrap();
+        let report = convert_all(&skills, Format::ClaudePlugin, &root, &output, 4).unwrap();
+        assert_eq!(report.failed, 0, "{:?}", report.skills);
+
+        let marketplace: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(output.join(".claude-plugin").join("marketplace.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(marketplace["name"], "dist-marketplace");
+        assert_eq!(marketplace["plugins"][0]["source"], "./alpha");
+        assert!(marketplace["plugins"][0].get("strict").is_none());
+    }
+}
@@ -0,0 +1,661 @@
+use super::ir::{Author, McpServer, Setting, Skill, SkillCommand, Subagent, ToolPermissions};
+use super::{
+    bundle_server_files, collect_assets, command_name, find_command_files, relative_server_paths,
+    report_unmapped_mcp_options, rewrite_server_paths, settings, split_frontmatter, write_assets, write_command_file,
+    ConversionResult, McpServerConfig, MCP_CONFIG_OPTIONS,
+};
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::fs;
+
+pub const TARGET: &str = "Claude Code";
+/// How plugin manifests spell the installed plugin's directory.
+pub const PLUGIN_ROOT: &str = "${CLAUDE_PLUGIN_ROOT}/";
+
+// ============================================================================
+// Models
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Frontmatter {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Either a YAML list or a comma-separated string
+    #[serde(default, rename = "allowed-tools", skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subagents: Option<Vec<FrontmatterSubagent>>,
+    /// Keys this converter does not interpret, such as `metadata` or `license`
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// Frontmatter of a `.claude/commands/*.md` file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandFrontmatter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, rename = "allowed-tools", skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Value>,
+    #[serde(default, rename = "argument-hint", skip_serializing_if = "Option::is_none")]
+    pub argument_hint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontmatterSubagent {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Marketplace {
+    pub name: String,
+    pub owner: MarketplaceOwner,
+    pub metadata: MarketplaceMetadata,
+    pub plugins: Vec<Plugin>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketplaceOwner {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketplaceMetadata {
+    pub description: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginRepository {
+    #[serde(rename = "type")]
+    pub repo_type: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Plugin {
+    pub name: String,
+    pub description: String,
+    pub source: String,
+    pub strict: bool,
+    pub author: String,
+    pub repository: PluginRepository,
+    pub license: String,
+    pub keywords: Vec<String>,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub skills: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "mcpServers")]
+    pub mcp_servers: Option<BTreeMap<String, McpServerConfig>>,
+}
+
+/// The parts of a `marketplace.json` the reader relies on; real-world files
+/// leave out most of the fields the writer emits.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct MarketplaceSource {
+    owner: Option<MarketplaceOwner>,
+    metadata: Option<MarketplaceSourceMetadata>,
+    plugins: Option<Vec<PluginSource>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct MarketplaceSourceMetadata {
+    version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct PluginSource {
+    description: Option<String>,
+    #[serde(rename = "mcpServers")]
+    mcp_servers: Option<BTreeMap<String, McpServerConfig>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationInsight {
+    #[serde(rename = "type")]
+    pub insight_type: String,
+    pub command: String,
+    pub message: String,
+}
+
+// ============================================================================
+// Reader
+// ============================================================================
+
+/// Read a Claude skill directory: `SKILL.md`, `.claude/commands/*.md` and the
+/// optional `.claude-plugin/marketplace.json`.
+pub async fn read(source: &Path, _result: &mut ConversionResult) -> Result<Skill> {
+    let content = fs::read_to_string(source.join("SKILL.md")).await?;
+    let (frontmatter, body) = parse_skill_md(&content)?;
+
+    let mut skill = Skill {
+        name: frontmatter.name.clone(),
+        description: frontmatter.description.clone().unwrap_or_default(),
+        instructions: body.to_string(),
+        tools: tool_permissions(&frontmatter),
+        extra: frontmatter.extra.clone(),
+        ..Default::default()
+    };
+
+    if let Some(subagents) = &frontmatter.subagents {
+        skill.subagents = subagents
+            .iter()
+            .map(|agent| Subagent {
+                name: agent.name.clone(),
+                description: agent.description.clone(),
+                prompt: None,
+            })
+            .collect();
+    }
+
+    skill.commands = read_markdown_commands(&source.join(".claude").join("commands")).await?;
+
+    // Extract from marketplace.json if it exists
+    let marketplace_path = source.join(".claude-plugin").join("marketplace.json");
+    if let Ok(marketplace_content) = fs::read_to_string(&marketplace_path).await {
+        if let Ok(marketplace) = serde_json::from_str::<MarketplaceSource>(&marketplace_content) {
+            skill.version = marketplace.metadata.and_then(|m| m.version);
+            skill.author = marketplace.owner.map(|owner| Author {
+                name: owner.name,
+                email: owner.email,
+            });
+            if let Some(plugin) = marketplace.plugins.as_ref().and_then(|p| p.first()) {
+                if skill.description.is_empty() {
+                    skill.description = plugin.description.clone().unwrap_or_default();
+                }
+                if let Some(servers) = &plugin.mcp_servers {
+                    skill.mcp_servers = servers
+                        .iter()
+                        .map(|(name, cfg)| (name.clone(), relative_server_paths(&cfg.to_ir(), &[PLUGIN_ROOT])))
+                        .collect();
+                }
+            }
+        }
+    }
+
+    skill.settings = settings::infer_settings(&skill.mcp_servers);
+    skill.assets = collect_assets(source)?;
+    bundle_server_files(&mut skill, source)?;
+    Ok(skill)
+}
+
+pub fn parse_skill_md(content: &str) -> Result<(Frontmatter, &str)> {
+    let (yaml, body) = split_frontmatter(content).ok_or_else(|| anyhow!("SKILL.md missing YAML frontmatter"))?;
+    let frontmatter: Frontmatter = serde_yaml::from_str(yaml)?;
+    Ok((frontmatter, body))
+}
+
+pub fn tool_permissions(frontmatter: &Frontmatter) -> ToolPermissions {
+    ToolPermissions {
+        allowed: frontmatter.allowed_tools.as_ref().map(tool_list),
+    }
+}
+
+/// `allowed-tools` is either a YAML list or a comma-separated string.
+fn tool_list(tools: &Value) -> Vec<String> {
+    match tools {
+        Value::Array(arr) => arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect(),
+        Value::String(s) => s.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Read `*.md` command files with optional `description` frontmatter;
+/// subdirectories namespace the command (`feature/action.md` is `feature:action`).
+pub async fn read_markdown_commands(dir: &Path) -> Result<Vec<SkillCommand>> {
+    let mut commands = Vec::new();
+
+    for path in find_command_files(dir, "md")? {
+        let Some(name) = command_name(dir, &path, "md") else {
+            continue;
+        };
+        let content = fs::read_to_string(&path).await?;
+        commands.push(parse_markdown_command(name, &content));
+    }
+
+    commands.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(commands)
+}
+
+pub fn parse_markdown_command(name: String, content: &str) -> SkillCommand {
+    let Some((yaml, body)) = split_frontmatter(content) else {
+        return SkillCommand {
+            name,
+            prompt: content.to_string(),
+            ..Default::default()
+        };
+    };
+
+    let frontmatter: CommandFrontmatter = serde_yaml::from_str(yaml).unwrap_or_default();
+    SkillCommand {
+        name,
+        description: frontmatter.description,
+        prompt: body.trim().to_string(),
+        allowed_tools: frontmatter.allowed_tools.as_ref().map(tool_list),
+        argument_hint: frontmatter.argument_hint,
+        model: frontmatter.model,
+    }
+}
+
+pub fn render_markdown_command(command: &SkillCommand) -> String {
+    let frontmatter = CommandFrontmatter {
+        description: Some(
+            command
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("Run {}", command.name)),
+        ),
+        // Claude's own docs write the list as one comma-separated string
+        allowed_tools: command.allowed_tools.as_ref().map(|tools| Value::String(tools.join(", "))),
+        argument_hint: command.argument_hint.clone(),
+        model: command.model.clone(),
+    };
+    let yaml = serde_yaml::to_string(&frontmatter).unwrap_or_default();
+    format!("---\n{}---\n\n{}\n", yaml, command.prompt.trim())
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+/// Write `SKILL.md`, `.claude-plugin/marketplace.json` and `.claude/commands/`.
+pub async fn write(skill: &Skill, output: &Path, result: &mut ConversionResult) {
+    // Step 1: Generate SKILL.md
+    match write_skill_md(skill, output).await {
+        Ok(path) => result.files.push(path),
+        Err(e) => result.errors.push(format!("Failed to generate SKILL.md: {}", e)),
+    }
+
+    // Step 2: Generate .claude-plugin/marketplace.json
+    if resolve_author(skill).is_none() {
+        result.warnings.push(
+            "No author known for marketplace.json; set git user.name or edit the owner by hand".to_string(),
+        );
+    }
+    match write_marketplace_json(skill, output).await {
+        Ok(path) => result.files.push(path),
+        Err(e) => result.errors.push(format!("Failed to generate marketplace.json: {}", e)),
+    }
+    for (name, server) in &skill.mcp_servers {
+        report_unmapped_mcp_options(name, server, TARGET, MCP_CONFIG_OPTIONS, result);
+    }
+
+    // Step 3: Generate Custom Commands
+    match write_commands(skill, output).await {
+        Ok(paths) => result.files.extend(paths),
+        Err(e) => result.errors.push(format!("Failed to generate commands: {}", e)),
+    }
+
+    // Step 4: Copy bundled files
+    write_assets(&skill.assets, output, result).await;
+
+    // Step 5: Generate Migration Insights
+    if let Err(e) = write_migration_insights(skill, output).await {
+        result.warnings.push(format!("Failed to generate insights: {}", e));
+    }
+}
+
+pub fn skill_frontmatter(skill: &Skill) -> Frontmatter {
+    let subagents: Vec<FrontmatterSubagent> = skill
+        .subagents
+        .iter()
+        .map(|agent| FrontmatterSubagent {
+            name: agent.name.clone(),
+            description: agent.description.clone(),
+        })
+        .collect();
+
+    Frontmatter {
+        name: skill.name.clone(),
+        description: Some(skill.description.clone()),
+        allowed_tools: skill
+            .tools
+            .allowed
+            .as_ref()
+            .map(|tools| Value::Array(tools.iter().map(|t| Value::String(t.clone())).collect())),
+        subagents: if subagents.is_empty() { None } else { Some(subagents) },
+        extra: skill.extra.clone(),
+    }
+}
+
+async fn write_skill_md(skill: &Skill, output: &Path) -> Result<String> {
+    let yaml_frontmatter = serde_yaml::to_string(&skill_frontmatter(skill))?;
+
+    // Build SKILL.md content
+    let mut skill_content = format!("---\n{}---\n\n", yaml_frontmatter);
+    skill_content.push_str(&format!("# {} - Claude Code Skill\n\n", skill.name));
+    skill_content.push_str(&format!("{}\n\n", skill.description));
+
+    // Add environment variable configuration section if there are settings
+    if !skill.settings.is_empty() {
+        skill_content.push_str("## Configuration\n\n");
+        skill_content.push_str("This skill requires the following environment variables:\n\n");
+        for setting in &skill.settings {
+            skill_content.push_str(&configuration_line(setting));
+        }
+        skill_content.push_str("\nSet these in your environment or Claude Code configuration.\n\n");
+    }
+
+    if !skill.instructions.trim().is_empty() {
+        skill_content.push_str(skill.instructions.trim());
+        skill_content.push_str("\n\n");
+    } else {
+        skill_content.push_str(&format!(
+            "## Usage\n\nUse this skill when you need {}.\n\n",
+            skill.description.to_lowercase()
+        ));
+    }
+
+    // Add footer
+    skill_content.push_str("---\n\n");
+    skill_content.push_str("*This skill was converted from a Gemini CLI extension using [skill-porter](https://github.com/jduncan-rva/skill-porter)*\n");
+
+    let output_path = output.join("SKILL.md");
+    fs::write(&output_path, skill_content).await?;
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+pub fn configuration_line(setting: &Setting) -> String {
+    let mut line = format!("- `{}`: {}", setting.name, setting.description);
+    if let Some(default) = &setting.default {
+        line.push_str(&format!(" (default: {})", default));
+    }
+    if setting.required {
+        line.push_str(" **(required)**");
+    }
+    line.push('\n');
+    line
+}
+
+/// The skill's author, falling back to the git identity of whoever converts it.
+pub fn resolve_author(skill: &Skill) -> Option<Author> {
+    skill.author.clone().or_else(|| {
+        crate::tools::git_config("user.name").map(|name| Author {
+            name,
+            email: crate::tools::git_config("user.email"),
+        })
+    })
+}
+
+async fn write_marketplace_json(skill: &Skill, output: &Path) -> Result<String> {
+    let owner = resolve_author(skill).unwrap_or_else(|| Author {
+        name: skill.name.clone(),
+        email: None,
+    });
+    let mut plugin = Plugin {
+        name: skill.name.clone(),
+        description: skill.description.clone(),
+        source: ".".to_string(),
+        strict: false,
+        author: owner.name.clone(),
+        repository: PluginRepository {
+            repo_type: "git".to_string(),
+            url: format!("https://github.com/user/{}", skill.name),
+        },
+        license: "MIT".to_string(),
+        keywords: extract_keywords(&skill.description),
+        category: "general".to_string(),
+        tags: Vec::new(),
+        skills: vec![".".to_string()],
+        mcp_servers: None,
+    };
+
+    if !skill.mcp_servers.is_empty() {
+        plugin.mcp_servers = Some(
+            skill
+                .mcp_servers
+                .iter()
+                .map(|(name, server)| (name.clone(), server_config(skill, server, PLUGIN_ROOT)))
+                .collect(),
+        );
+    }
+
+    let marketplace = Marketplace {
+        name: format!("{}-marketplace", skill.name),
+        owner: MarketplaceOwner {
+            name: owner.name,
+            email: owner.email,
+        },
+        metadata: MarketplaceMetadata {
+            description: skill.description.clone(),
+            version: skill.version.clone().unwrap_or_else(|| "1.0.0".to_string()),
+        },
+        plugins: vec![plugin],
+    };
+
+    let claude_plugin_dir = output.join(".claude-plugin");
+    fs::create_dir_all(&claude_plugin_dir).await?;
+
+    let output_path = claude_plugin_dir.join("marketplace.json");
+    fs::write(&output_path, serde_json::to_string_pretty(&marketplace)?).await?;
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+/// A server entry for a plugin rooted at `root`, defaults spelled out.
+pub fn server_config(skill: &Skill, server: &McpServer, root: &str) -> McpServerConfig {
+    let server = rewrite_server_paths(server, &skill.assets, root);
+    let server = settings::map_values(&server, |value| settings::with_defaults(value, &skill.settings));
+    McpServerConfig::from_ir(&server)
+}
+
+async fn write_commands(skill: &Skill, output: &Path) -> Result<Vec<String>> {
+    let mut generated_files = Vec::new();
+    if skill.commands.is_empty() {
+        return Ok(generated_files);
+    }
+
+    let commands_dir = output.join(".claude").join("commands");
+    fs::create_dir_all(&commands_dir).await?;
+
+    for cmd in &skill.commands {
+        generated_files.push(write_command_file(&commands_dir, &cmd.name, "md", &render_markdown_command(cmd)).await?);
+    }
+
+    Ok(generated_files)
+}
+
+async fn write_migration_insights(skill: &Skill, output: &Path) -> Result<()> {
+    let persona_regex = Regex::new(r"You are a|Act as|Your role is")?;
+
+    // Heuristic checks
+    let insights: Vec<MigrationInsight> = skill
+        .commands
+        .iter()
+        .filter(|cmd| persona_regex.is_match(&cmd.prompt))
+        .map(|cmd| MigrationInsight {
+            insight_type: "PERSONA_DETECTED".to_string(),
+            command: cmd.name.clone(),
+            message: format!(
+                "Command `/{0}` appears to define a persona. Consider moving this logic to `SKILL.md` instructions so Claude can adopt it automatically without a slash command.",
+                cmd.name
+            ),
+        })
+        .collect();
+
+    // Generate Report Content
+    let mut content = String::from("# Migration Insights & Recommendations\n\n");
+    content.push_str("Generated during conversion from Gemini to Claude.\n\n");
+
+    if !insights.is_empty() {
+        content.push_str("## 💡 Optimization Opportunities\n\n");
+        content.push_str("While we successfully converted your commands to Claude Slash Commands, some might work better as native Skill instructions.\n\n");
+
+        for insight in &insights {
+            content.push_str(&format!("### `/{}`\n", insight.command));
+            content.push_str(&format!("{}\n\n", insight.message));
+        }
+
+        content.push_str("## How to Apply\n");
+        content.push_str("1. Open `SKILL.md`\n");
+        content.push_str("2. Paste the prompt instructions into the main description area.\n");
+        if let Some(first_insight) = insights.first() {
+            content.push_str(&format!(
+                "3. Delete `.claude/commands/{}.md` if you prefer automatic invocation.\n",
+                first_insight.command
+            ));
+        }
+    } else {
+        content.push_str("✅ No specific architectural changes recommended. The direct conversion should work well.\n");
+    }
+
+    let shared_dir = output.join("shared");
+    fs::create_dir_all(&shared_dir).await?;
+    fs::write(shared_dir.join("MIGRATION_INSIGHTS.md"), content).await?;
+
+    Ok(())
+}
+
+pub fn extract_keywords(description: &str) -> Vec<String> {
+    let common_words = ["the", "a", "an", "and", "or", "but", "for", "with", "to", "from", "in", "on"];
+
+    description
+        .to_lowercase()
+        .split_whitespace()
+        .filter(|word| word.len() > 3 && !common_words.contains(word))
+        .take(5)
+        .map(|s| s.to_string())
+        .collect()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_frontmatter_parsing() {
+        let yaml_str = r#"
+name: Test Skill
+description: A test skill
+allowed-tools:
+  - Read
+  - Write
+"#;
+
+        let frontmatter: Frontmatter = serde_yaml::from_str(yaml_str).unwrap();
+        assert_eq!(frontmatter.name, "Test Skill");
+        assert_eq!(frontmatter.description, Some("A test skill".to_string()));
+        assert_eq!(
+            tool_permissions(&frontmatter).allowed,
+            Some(vec!["Read".to_string(), "Write".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_extract_keywords() {
+        let description = "This is a powerful tool for managing databases and files";
+
+        let keywords = extract_keywords(description);
+
+        assert!(keywords.contains(&"powerful".to_string()));
+        assert!(keywords.contains(&"managing".to_string()));
+        assert!(!keywords.contains(&"is".to_string())); // Common word
+    }
+
+    #[test]
+    fn test_marketplace_owner_serialization() {
+        let owner = MarketplaceOwner {
+            name: "Test User".to_string(),
+            email: Some("test@example.com".to_string()),
+        };
+
+        let json = serde_json::to_string_pretty(&owner).unwrap();
+        assert!(json.contains("\"name\": \"Test User\""));
+        assert!(json.contains("\"email\": \"test@example.com\""));
+    }
+
+    #[test]
+    fn test_migration_insight_serialization() {
+        let insight = MigrationInsight {
+            insight_type: "PERSONA_DETECTED".to_string(),
+            command: "test_cmd".to_string(),
+            message: "Test message".to_string(),
+        };
+
+        let json = serde_json::to_string_pretty(&insight).unwrap();
+        assert!(json.contains("\"type\": \"PERSONA_DETECTED\""));
+        assert!(json.contains("\"command\": \"test_cmd\""));
+    }
+
+    #[tokio::test]
+    async fn test_read_skill_with_commands_and_marketplace() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path();
+        fs::create_dir_all(source.join(".claude/commands")).unwrap();
+        fs::create_dir_all(source.join(".claude-plugin")).unwrap();
+
+        fs::write(
+            source.join("SKILL.md"),
+            "---\nname: Test Skill\nsubagents:\n  - name: Agent1\n    description: First agent\n---\n# Content here\n",
+        )
+        .unwrap();
+        fs::write(
+            source.join(".claude/commands/review.md"),
+            "---\ndescription: Review code\n---\n\nReview $ARGUMENTS\n",
+        )
+        .unwrap();
+        let marketplace = json!({
+            "owner": { "name": "Acme" },
+            "metadata": { "version": "2.0.0" },
+            "plugins": [{
+                "description": "From marketplace",
+                "mcpServers": { "db": { "command": "node", "args": ["server.js"], "env": { "DB_HOST": "${DB_HOST}" } } }
+            }]
+        });
+        fs::write(source.join(".claude-plugin/marketplace.json"), marketplace.to_string()).unwrap();
+
+        let mut result = ConversionResult::default();
+        let skill = read(source, &mut result).await.unwrap();
+
+        assert_eq!(skill.version.as_deref(), Some("2.0.0"));
+        assert_eq!(skill.description, "From marketplace");
+        assert_eq!(skill.subagents[0].name, "Agent1");
+        assert_eq!(skill.commands[0].description.as_deref(), Some("Review code"));
+        assert_eq!(skill.mcp_servers["db"].args, vec!["server.js".to_string()]);
+        assert_eq!(skill.settings[0].name, "DB_HOST");
+        assert_eq!(skill.author.as_ref().map(|a| a.name.as_str()), Some("Acme"));
+    }
+
+    #[test]
+    fn test_frontmatter_keeps_unknown_keys() {
+        let content = "---\nname: demo\ndescription: Demo\nmetadata:\n  short-description: Short\n---\nBody\n";
+        let (frontmatter, _) = parse_skill_md(content).unwrap();
+        assert_eq!(frontmatter.extra["metadata"], json!({ "short-description": "Short" }));
+
+        let yaml = serde_yaml::to_string(&frontmatter).unwrap();
+        assert!(yaml.contains("short-description: Short"));
+    }
+
+    #[test]
+    fn test_command_frontmatter_round_trip() {
+        let content = "---\ndescription: Fix an issue\nallowed-tools: Bash(git diff:*), Read\nargument-hint: '[issue]'\nmodel: haiku\n---\n\nFix #$1\n";
+        let command = parse_markdown_command("fix".to_string(), content);
+        assert_eq!(
+            command.allowed_tools,
+            Some(vec!["Bash(git diff:*)".to_string(), "Read".to_string()])
+        );
+        assert_eq!(command.argument_hint.as_deref(), Some("[issue]"));
+        assert_eq!(command.model.as_deref(), Some("haiku"));
+        assert_eq!(command.prompt, "Fix #$1");
+
+        assert_eq!(parse_markdown_command("fix".to_string(), &render_markdown_command(&command)), command);
+    }
+}
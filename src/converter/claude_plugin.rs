@@ -0,0 +1,347 @@
+use super::claude::{
+    parse_skill_md, read_markdown_commands, render_markdown_command, server_config, skill_frontmatter,
+    tool_permissions, PLUGIN_ROOT, TARGET,
+};
+use super::ir::{Author, Skill, Subagent};
+use super::{
+    bundle_server_files, collect_assets, relative_server_paths, report_unmapped_mcp_options, settings,
+    split_frontmatter, write_assets, write_command_file, ConversionResult, McpServerConfig, MCP_CONFIG_OPTIONS,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::fs;
+
+// ============================================================================
+// Models
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<PluginAuthor>,
+    #[serde(default, rename = "mcpServers", skip_serializing_if = "Option::is_none")]
+    pub mcp_servers: Option<BTreeMap<String, McpServerConfig>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginAuthor {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+/// `.mcp.json` at the plugin root.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpFile {
+    #[serde(default, rename = "mcpServers")]
+    pub mcp_servers: BTreeMap<String, McpServerConfig>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct AgentFrontmatter {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+// ============================================================================
+// Reader
+// ============================================================================
+
+/// Read a Claude plugin: `.claude-plugin/plugin.json`, `.mcp.json`,
+/// `commands/*.md`, `agents/*.md` and the first skill under `skills/`.
+pub async fn read(source: &Path, result: &mut ConversionResult) -> Result<Skill> {
+    let manifest_content = fs::read_to_string(source.join(".claude-plugin").join("plugin.json")).await?;
+    let manifest: PluginManifest = serde_json::from_str(&manifest_content)?;
+
+    let mut skill = Skill {
+        name: manifest.name.clone(),
+        version: manifest.version.clone(),
+        description: manifest.description.clone().unwrap_or_default(),
+        author: manifest.author.clone().map(|author| Author {
+            name: author.name,
+            email: author.email,
+        }),
+        ..Default::default()
+    };
+
+    if let Some(servers) = &manifest.mcp_servers {
+        skill.mcp_servers = servers.iter().map(|(name, cfg)| (name.clone(), cfg.to_ir())).collect();
+    }
+    if let Ok(content) = fs::read_to_string(source.join(".mcp.json")).await {
+        let mcp: McpFile = serde_json::from_str(&content)?;
+        skill
+            .mcp_servers
+            .extend(mcp.mcp_servers.iter().map(|(name, cfg)| (name.clone(), cfg.to_ir())));
+    }
+
+    skill.commands = read_markdown_commands(&source.join("commands")).await?;
+    skill.subagents = read_agents(&source.join("agents")).await?;
+
+    // A plugin may bundle several skills; the IR holds one set of instructions
+    let skill_dirs = skill_dirs(&source.join("skills")).await?;
+    let mut skill_root = PLUGIN_ROOT.to_string();
+    if let Some(dir) = skill_dirs.first() {
+        let content = fs::read_to_string(dir.join("SKILL.md")).await?;
+        let (frontmatter, body) = parse_skill_md(&content)?;
+        skill.instructions = body.to_string();
+        skill.tools = tool_permissions(&frontmatter);
+        skill.extra = frontmatter.extra.clone();
+        if skill.description.is_empty() {
+            skill.description = frontmatter.description.unwrap_or_default();
+        }
+        skill.assets = collect_assets(dir)?;
+        let dir_name = dir.file_name().unwrap_or_default().to_string_lossy();
+        skill_root = format!("{}skills/{}/", PLUGIN_ROOT, dir_name);
+    }
+    for dir in skill_dirs.iter().skip(1) {
+        result.warnings.push(format!(
+            "Skipped skill {}: only the first skill in a plugin is converted",
+            dir.file_name().unwrap_or_default().to_string_lossy()
+        ));
+    }
+
+    // Files next to the skill are its own; others are bundled from the plugin root
+    skill.mcp_servers = skill
+        .mcp_servers
+        .iter()
+        .map(|(name, server)| (name.clone(), relative_server_paths(server, &[skill_root.as_str()])))
+        .collect();
+    if let Some(dir) = skill_dirs.first() {
+        bundle_server_files(&mut skill, dir)?;
+    }
+    skill.mcp_servers = skill
+        .mcp_servers
+        .iter()
+        .map(|(name, server)| (name.clone(), relative_server_paths(server, &[PLUGIN_ROOT])))
+        .collect();
+    bundle_server_files(&mut skill, source)?;
+
+    skill.settings = settings::infer_settings(&skill.mcp_servers);
+    Ok(skill)
+}
+
+async fn skill_dirs(dir: &Path) -> Result<Vec<std::path::PathBuf>> {
+    let mut dirs = Vec::new();
+    if let Ok(mut entries) = fs::read_dir(dir).await {
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.path().join("SKILL.md").is_file() {
+                dirs.push(entry.path());
+            }
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+async fn read_agents(dir: &Path) -> Result<Vec<Subagent>> {
+    let mut agents = Vec::new();
+
+    if let Ok(mut entries) = fs::read_dir(dir).await {
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "md") {
+                continue;
+            }
+            let content = fs::read_to_string(&path).await?;
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("unknown").to_string();
+            let (frontmatter, body) = match split_frontmatter(&content) {
+                Some((yaml, body)) => (serde_yaml::from_str::<AgentFrontmatter>(yaml).unwrap_or_default(), body),
+                None => (AgentFrontmatter::default(), content.as_str()),
+            };
+            agents.push(Subagent {
+                name: frontmatter.name.unwrap_or(stem),
+                description: frontmatter.description,
+                prompt: Some(body.trim().to_string()).filter(|p| !p.is_empty()),
+            });
+        }
+    }
+
+    agents.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(agents)
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+/// Write the plugin layout with the instructions as `skills/<name>/SKILL.md`.
+pub async fn write(skill: &Skill, output: &Path, result: &mut ConversionResult) {
+    // Step 1: Generate .claude-plugin/plugin.json and .mcp.json
+    match write_manifest(skill, output).await {
+        Ok(paths) => result.files.extend(paths),
+        Err(e) => result.errors.push(format!("Failed to generate plugin.json: {}", e)),
+    }
+    for (name, server) in &skill.mcp_servers {
+        report_unmapped_mcp_options(name, server, TARGET, MCP_CONFIG_OPTIONS, result);
+    }
+
+    // Step 2: Generate skills/<name>/SKILL.md
+    let skill_dir = output.join("skills").join(&skill.name);
+    match write_skill_md(skill, &skill_dir).await {
+        Ok(path) => result.files.push(path),
+        Err(e) => result.errors.push(format!("Failed to generate SKILL.md: {}", e)),
+    }
+
+    // Step 3: Generate commands/ and agents/
+    match write_commands_and_agents(skill, output).await {
+        Ok(paths) => result.files.extend(paths),
+        Err(e) => result.errors.push(format!("Failed to generate commands: {}", e)),
+    }
+
+    // Step 4: Copy bundled files next to the skill that references them
+    write_assets(&skill.assets, &skill_dir, result).await;
+}
+
+async fn write_manifest(skill: &Skill, output: &Path) -> Result<Vec<String>> {
+    let mut written = Vec::new();
+    let manifest = PluginManifest {
+        name: skill.name.clone(),
+        version: skill.version.clone(),
+        description: Some(skill.description.clone()),
+        author: skill.author.as_ref().map(|author| PluginAuthor {
+            name: author.name.clone(),
+            email: author.email.clone(),
+        }),
+        mcp_servers: None,
+    };
+
+    let plugin_dir = output.join(".claude-plugin");
+    fs::create_dir_all(&plugin_dir).await?;
+    let manifest_path = plugin_dir.join("plugin.json");
+    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?).await?;
+    written.push(manifest_path.to_string_lossy().to_string());
+
+    if !skill.mcp_servers.is_empty() {
+        // Bundled files sit next to the skill, not at the plugin root
+        let skill_root = format!("{}skills/{}/", PLUGIN_ROOT, skill.name);
+        let mcp = McpFile {
+            mcp_servers: skill
+                .mcp_servers
+                .iter()
+                .map(|(name, server)| (name.clone(), server_config(skill, server, &skill_root)))
+                .collect(),
+        };
+        let mcp_path = output.join(".mcp.json");
+        fs::write(&mcp_path, serde_json::to_string_pretty(&mcp)?).await?;
+        written.push(mcp_path.to_string_lossy().to_string());
+    }
+
+    Ok(written)
+}
+
+async fn write_skill_md(skill: &Skill, skill_dir: &Path) -> Result<String> {
+    // Subagents live in agents/ in a plugin, not in the skill frontmatter
+    let mut frontmatter = skill_frontmatter(skill);
+    frontmatter.subagents = None;
+
+    let content = format!(
+        "---\n{}---\n\n{}\n",
+        serde_yaml::to_string(&frontmatter)?,
+        skill.instructions.trim()
+    );
+
+    fs::create_dir_all(skill_dir).await?;
+    let path = skill_dir.join("SKILL.md");
+    fs::write(&path, content).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+async fn write_commands_and_agents(skill: &Skill, output: &Path) -> Result<Vec<String>> {
+    let mut written = Vec::new();
+
+    if !skill.commands.is_empty() {
+        let commands_dir = output.join("commands");
+        fs::create_dir_all(&commands_dir).await?;
+        for cmd in &skill.commands {
+            written.push(write_command_file(&commands_dir, &cmd.name, "md", &render_markdown_command(cmd)).await?);
+        }
+    }
+
+    if !skill.subagents.is_empty() {
+        let agents_dir = output.join("agents");
+        fs::create_dir_all(&agents_dir).await?;
+        for agent in &skill.subagents {
+            let frontmatter = AgentFrontmatter {
+                name: Some(agent.name.clone()),
+                description: agent.description.clone(),
+            };
+            let body = agent
+                .prompt
+                .clone()
+                .or_else(|| agent.description.clone())
+                .unwrap_or_default();
+            let path = agents_dir.join(format!("{}.md", agent.name));
+            fs::write(
+                &path,
+                format!("---\n{}---\n\n{}\n", serde_yaml::to_string(&frontmatter)?, body.trim()),
+            )
+            .await?;
+            written.push(path.to_string_lossy().to_string());
+        }
+    }
+
+    Ok(written)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::ir::{McpServer, SkillCommand};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_plugin_write_then_read() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut skill = Skill {
+            name: "demo".to_string(),
+            version: Some("0.2.0".to_string()),
+            description: "Demo plugin".to_string(),
+            instructions: "Do the thing.\n".to_string(),
+            commands: vec![SkillCommand {
+                name: "run".to_string(),
+                description: Some("Run it".to_string()),
+                prompt: "Run $ARGUMENTS".to_string(),
+                ..Default::default()
+            }],
+            subagents: vec![Subagent {
+                name: "reviewer".to_string(),
+                description: Some("Reviews code".to_string()),
+                prompt: Some("You review code.".to_string()),
+            }],
+            ..Default::default()
+        };
+        skill.mcp_servers.insert(
+            "db".to_string(),
+            McpServer {
+                command: Some("node".to_string()),
+                args: vec!["server.js".to_string()],
+                ..Default::default()
+            },
+        );
+
+        let mut result = ConversionResult::default();
+        write(&skill, temp_dir.path(), &mut result).await;
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let read_back = read(temp_dir.path(), &mut result).await.unwrap();
+        assert_eq!(read_back.name, skill.name);
+        assert_eq!(read_back.version, skill.version);
+        assert_eq!(read_back.instructions.trim(), "Do the thing.");
+        assert_eq!(read_back.commands, skill.commands);
+        assert_eq!(read_back.subagents, skill.subagents);
+        assert_eq!(read_back.mcp_servers, skill.mcp_servers);
+    }
+}
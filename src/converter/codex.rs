@@ -0,0 +1,227 @@
+use super::ir::Skill;
+use super::{
+    instructions_markdown, placeholders, render_markdown, report_unmapped_mcp_options, report_unmapped_settings,
+    report_unmapped_tools, write_assets, write_command_file, ConversionResult,
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::fs;
+
+const TARGET: &str = "Codex";
+
+// ============================================================================
+// Models
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+struct PromptFrontmatter {
+    description: String,
+    #[serde(rename = "argument-hint", skip_serializing_if = "Option::is_none")]
+    argument_hint: Option<String>,
+}
+
+/// The `[mcp_servers]` part of `.codex/config.toml`.
+#[derive(Debug, Clone, Default, Serialize)]
+struct CodexConfig {
+    mcp_servers: BTreeMap<String, CodexMcpServer>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CodexMcpServer {
+    command: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    args: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cwd: Option<String>,
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+/// Write `AGENTS.md`, `.codex/prompts/*.md` and `.codex/config.toml`.
+pub async fn write(skill: &Skill, output: &Path, result: &mut ConversionResult) {
+    // Step 1: Generate AGENTS.md
+    match write_agents_md(skill, output).await {
+        Ok(path) => result.files.push(path),
+        Err(e) => result.errors.push(format!("Failed to generate AGENTS.md: {}", e)),
+    }
+
+    // Step 2: Generate prompts from commands and subagents
+    match write_prompts(skill, output, result).await {
+        Ok(paths) => result.files.extend(paths),
+        Err(e) => result.errors.push(format!("Failed to generate prompts: {}", e)),
+    }
+
+    // Step 3: Generate .codex/config.toml
+    match write_config(skill, output, result).await {
+        Ok(Some(path)) => result.files.push(path),
+        Ok(None) => {}
+        Err(e) => result.errors.push(format!("Failed to generate config.toml: {}", e)),
+    }
+
+    // Step 4: Copy bundled files
+    write_assets(&skill.assets, output, result).await;
+
+    report_unmapped_settings(skill, TARGET, "AGENTS.md", result);
+    report_unmapped_tools(skill, TARGET, result);
+}
+
+async fn write_agents_md(skill: &Skill, output: &Path) -> Result<String> {
+    let path = output.join("AGENTS.md");
+    fs::write(&path, instructions_markdown(skill)).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+async fn write_prompts(skill: &Skill, output: &Path, result: &mut ConversionResult) -> Result<Vec<String>> {
+    let mut written = Vec::new();
+    if skill.commands.is_empty() && skill.subagents.is_empty() {
+        return Ok(written);
+    }
+
+    let prompts_dir = output.join(".codex").join("prompts");
+    fs::create_dir_all(&prompts_dir).await?;
+
+    // Codex prompts understand $ARGUMENTS and $1..$9 but no injections
+    for cmd in &skill.commands {
+        let frontmatter = PromptFrontmatter {
+            description: cmd.description.clone().unwrap_or_else(|| format!("Run {}", cmd.name)),
+            argument_hint: cmd.argument_hint.clone(),
+        };
+        let mut warnings = placeholders::unsupported_injections(&placeholders::lex_claude(&cmd.prompt), TARGET);
+        if cmd.allowed_tools.is_some() {
+            warnings.push(format!("allowed-tools has no {} equivalent", TARGET));
+        }
+        if cmd.model.is_some() {
+            warnings.push(format!("model has no {} equivalent", TARGET));
+        }
+        result
+            .warnings
+            .extend(warnings.into_iter().map(|w| format!("Command {}: {}", cmd.name, w)));
+        let content = render_markdown(&frontmatter, &cmd.prompt)?;
+        written.push(write_command_file(&prompts_dir, &cmd.name, "md", &content).await?);
+    }
+
+    // Codex has no subagents; each becomes a prompt that adopts the persona
+    for agent in &skill.subagents {
+        let frontmatter = PromptFrontmatter {
+            description: format!("Activate {} agent", agent.name),
+            argument_hint: None,
+        };
+        let persona = agent
+            .prompt
+            .as_deref()
+            .or(agent.description.as_deref())
+            .unwrap_or_default();
+        let body = format!(
+            "You are acting as the '{}' agent.\n{}\n\nUser Query: $ARGUMENTS",
+            agent.name, persona
+        );
+        let path = prompts_dir.join(format!("{}.md", agent.name));
+        fs::write(&path, render_markdown(&frontmatter, &body)?).await?;
+        written.push(path.to_string_lossy().to_string());
+        result.unmapped.push(format!(
+            "Subagent {}: {} has no subagents; written as the prompt /{}",
+            agent.name, TARGET, agent.name
+        ));
+    }
+
+    Ok(written)
+}
+
+async fn write_config(skill: &Skill, output: &Path, result: &mut ConversionResult) -> Result<Option<String>> {
+    let mut config = CodexConfig::default();
+    for (name, server) in &skill.mcp_servers {
+        let Some(command) = &server.command else {
+            result
+                .unmapped
+                .push(format!("MCP server {}: {} only runs servers by command", name, TARGET));
+            continue;
+        };
+        report_unmapped_mcp_options(name, server, TARGET, &["cwd"], result);
+        config.mcp_servers.insert(
+            name.clone(),
+            CodexMcpServer {
+                command: command.clone(),
+                args: server.args.clone(),
+                env: server.env.clone(),
+                cwd: server.cwd.clone(),
+            },
+        );
+    }
+    if config.mcp_servers.is_empty() {
+        return Ok(None);
+    }
+
+    let codex_dir = output.join(".codex");
+    fs::create_dir_all(&codex_dir).await?;
+    let path = codex_dir.join("config.toml");
+    fs::write(&path, toml::to_string_pretty(&config)?).await?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::ir::{McpServer, SkillCommand, Subagent, ToolPermissions};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_codex_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut skill = Skill {
+            name: "demo".to_string(),
+            description: "Demo skill".to_string(),
+            instructions: "Do the thing.\n".to_string(),
+            commands: vec![SkillCommand {
+                name: "run".to_string(),
+                description: Some("Run it".to_string()),
+                prompt: "Run $ARGUMENTS".to_string(),
+                ..Default::default()
+            }],
+            subagents: vec![Subagent {
+                name: "reviewer".to_string(),
+                description: Some("Reviews code".to_string()),
+                prompt: None,
+            }],
+            tools: ToolPermissions {
+                allowed: Some(vec!["Read".to_string()]),
+            },
+            ..Default::default()
+        };
+        skill.mcp_servers.insert(
+            "db".to_string(),
+            McpServer {
+                command: Some("node".to_string()),
+                args: vec!["server.js".to_string()],
+                ..Default::default()
+            },
+        );
+
+        let mut result = ConversionResult::default();
+        write(&skill, temp_dir.path(), &mut result).await;
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let agents_md = std::fs::read_to_string(temp_dir.path().join("AGENTS.md")).unwrap();
+        assert!(agents_md.starts_with("# demo\n\nDemo skill\n\nDo the thing."));
+
+        let prompt = std::fs::read_to_string(temp_dir.path().join(".codex/prompts/run.md")).unwrap();
+        assert_eq!(prompt, "---\ndescription: Run it\n---\n\nRun $ARGUMENTS\n");
+
+        let config = std::fs::read_to_string(temp_dir.path().join(".codex/config.toml")).unwrap();
+        assert!(config.contains("[mcp_servers.db]"));
+        assert!(config.contains("command = \"node\""));
+
+        assert_eq!(result.unmapped.len(), 2, "{:?}", result.unmapped);
+        assert!(result.unmapped[0].starts_with("Subagent reviewer"));
+        assert!(result.unmapped[1].starts_with("Tool permissions"));
+    }
+}
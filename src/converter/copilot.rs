@@ -0,0 +1,299 @@
+use super::ir::{McpTransport, Skill, SkillCommand};
+use super::{
+    claude, instructions_markdown, placeholders, render_markdown, settings, report_unmapped_mcp_options, report_unmapped_settings,
+    report_unmapped_tools, write_assets, write_command_file, ConversionResult,
+};
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::fs;
+
+const TARGET: &str = "Copilot";
+const INSTRUCTIONS_FILE: &str = ".github/copilot-instructions.md";
+/// Suffix Copilot looks for in `.github/prompts/`
+pub const PROMPT_EXTENSION: &str = "prompt.md";
+
+// ============================================================================
+// Models
+// ============================================================================
+
+/// Frontmatter of a `.prompt.md` file.
+#[derive(Debug, Clone, Serialize)]
+struct PromptFrontmatter {
+    description: String,
+    mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+}
+
+/// Frontmatter of a `.chatmode.md` file.
+#[derive(Debug, Clone, Serialize)]
+struct ChatModeFrontmatter {
+    description: String,
+}
+
+/// `.vscode/mcp.json`, which Copilot agent mode reads.
+#[derive(Debug, Clone, Default, Serialize)]
+struct McpFile {
+    servers: BTreeMap<String, VsCodeMcpServer>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct VsCodeMcpServer {
+    #[serde(rename = "type")]
+    server_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    args: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    headers: BTreeMap<String, String>,
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+/// Write `.github/copilot-instructions.md`, `.github/prompts/*.prompt.md`,
+/// `.github/chatmodes/*.chatmode.md` and `.vscode/mcp.json`.
+pub async fn write(skill: &Skill, output: &Path, result: &mut ConversionResult) {
+    // Step 1: Generate copilot-instructions.md
+    match write_instructions(skill, output).await {
+        Ok(path) => result.files.push(path),
+        Err(e) => result.errors.push(format!("Failed to generate copilot-instructions.md: {}", e)),
+    }
+
+    // Step 2: Generate prompt files
+    match write_prompts(skill, output, result).await {
+        Ok(paths) => result.files.extend(paths),
+        Err(e) => result.errors.push(format!("Failed to generate prompt files: {}", e)),
+    }
+
+    // Step 3: Generate chat modes from subagents
+    match write_chat_modes(skill, output).await {
+        Ok(paths) => result.files.extend(paths),
+        Err(e) => result.errors.push(format!("Failed to generate chat modes: {}", e)),
+    }
+
+    // Step 4: Generate .vscode/mcp.json
+    match write_mcp(skill, output, result).await {
+        Ok(Some(path)) => result.files.push(path),
+        Ok(None) => {}
+        Err(e) => result.errors.push(format!("Failed to generate mcp.json: {}", e)),
+    }
+
+    // Step 5: Copy bundled files
+    write_assets(&skill.assets, output, result).await;
+
+    report_unmapped_settings(skill, TARGET, INSTRUCTIONS_FILE, result);
+    report_unmapped_tools(skill, TARGET, result);
+}
+
+async fn write_instructions(skill: &Skill, output: &Path) -> Result<String> {
+    let path = output.join(INSTRUCTIONS_FILE);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(&path, instructions_markdown(skill)).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+async fn write_prompts(skill: &Skill, output: &Path, result: &mut ConversionResult) -> Result<Vec<String>> {
+    let mut written = Vec::new();
+    if skill.commands.is_empty() {
+        return Ok(written);
+    }
+
+    let prompts_dir = output.join(".github").join("prompts");
+    fs::create_dir_all(&prompts_dir).await?;
+
+    for cmd in &skill.commands {
+        let mut warnings = Vec::new();
+        let content = render_prompt(cmd, &mut warnings)?;
+        result
+            .warnings
+            .extend(warnings.into_iter().map(|w| format!("Command {}: {}", cmd.name, w)));
+        written.push(write_command_file(&prompts_dir, &cmd.name, PROMPT_EXTENSION, &content).await?);
+    }
+
+    Ok(written)
+}
+
+/// Render one command as a `.prompt.md` file.
+pub fn render_prompt(cmd: &SkillCommand, warnings: &mut Vec<String>) -> Result<String> {
+    let frontmatter = PromptFrontmatter {
+        description: cmd.description.clone().unwrap_or_else(|| format!("Run {}", cmd.name)),
+        mode: "agent".to_string(),
+        model: cmd.model.clone(),
+    };
+    let (body, body_warnings) = placeholders::claude_to_copilot(&cmd.prompt);
+    warnings.extend(body_warnings);
+    if cmd.allowed_tools.is_some() {
+        warnings.push("allowed-tools names Claude tools, which Copilot does not share".to_string());
+    }
+    if cmd.argument_hint.is_some() {
+        warnings.push("argument-hint has no Copilot equivalent".to_string());
+    }
+    render_markdown(&frontmatter, &body)
+}
+
+/// Read a `.prompt.md` file back into the IR, whose prompts use Claude's syntax.
+pub fn parse_prompt(name: String, content: &str, warnings: &mut Vec<String>) -> SkillCommand {
+    let mut command = claude::parse_markdown_command(name, content);
+    let (prompt, prompt_warnings) = placeholders::copilot_to_claude(&command.prompt);
+    command.prompt = prompt;
+    warnings.extend(prompt_warnings);
+    command
+}
+
+async fn write_chat_modes(skill: &Skill, output: &Path) -> Result<Vec<String>> {
+    let mut written = Vec::new();
+    if skill.subagents.is_empty() {
+        return Ok(written);
+    }
+
+    let modes_dir = output.join(".github").join("chatmodes");
+    fs::create_dir_all(&modes_dir).await?;
+
+    for agent in &skill.subagents {
+        let description = agent
+            .description
+            .clone()
+            .unwrap_or_else(|| format!("{} agent", agent.name));
+        let body = agent.prompt.clone().unwrap_or_else(|| description.clone());
+        let path = modes_dir.join(format!("{}.chatmode.md", agent.name));
+        fs::write(&path, render_markdown(&ChatModeFrontmatter { description }, &body)?).await?;
+        written.push(path.to_string_lossy().to_string());
+    }
+
+    Ok(written)
+}
+
+async fn write_mcp(skill: &Skill, output: &Path, result: &mut ConversionResult) -> Result<Option<String>> {
+    let env_regex = Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}")?;
+    let mut mcp = McpFile::default();
+    for (name, server) in &skill.mcp_servers {
+        report_unmapped_mcp_options(name, server, TARGET, &["headers"], result);
+        // ${VAR} -> ${env:VAR}; the default is only kept in the setting
+        let server = settings::map_values(server, |value| {
+            env_regex
+                .replace_all(&settings::strip_defaults(value), "$${env:$1}")
+                .to_string()
+        });
+        let server_type = match server.transport {
+            McpTransport::Stdio => "stdio",
+            McpTransport::Sse => "sse",
+            McpTransport::Http => "http",
+        };
+        mcp.servers.insert(
+            name.clone(),
+            VsCodeMcpServer {
+                server_type: server_type.to_string(),
+                command: server.command,
+                args: server.args,
+                env: server.env,
+                url: server.url,
+                headers: server.headers,
+            },
+        );
+    }
+    if mcp.servers.is_empty() {
+        return Ok(None);
+    }
+
+    let vscode_dir = output.join(".vscode");
+    fs::create_dir_all(&vscode_dir).await?;
+    let path = vscode_dir.join("mcp.json");
+    fs::write(&path, serde_json::to_string_pretty(&mcp)?).await?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::ir::{McpServer, Setting, SkillCommand};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_render_copilot_placeholders() {
+        let mut warnings = Vec::new();
+        let body = placeholders::render_copilot(
+            &placeholders::lex_claude("Compare $1 with $2 in @src/lib.rs: $ARGUMENTS"),
+            &mut warnings,
+        );
+        assert_eq!(body, "Compare ${input:arg1} with ${input:arg2} in #file:src/lib.rs: ${input:args}");
+        assert!(warnings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_copilot_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut skill = Skill {
+            name: "demo".to_string(),
+            description: "Demo skill".to_string(),
+            instructions: "Do the thing.\n".to_string(),
+            commands: vec![SkillCommand {
+                name: "run".to_string(),
+                description: Some("Run it".to_string()),
+                prompt: "Run $ARGUMENTS".to_string(),
+                ..Default::default()
+            }],
+            settings: vec![Setting {
+                name: "API_KEY".to_string(),
+                description: "API authentication key".to_string(),
+                default: None,
+                secret: true,
+                required: true,
+            }],
+            ..Default::default()
+        };
+        skill.mcp_servers.insert(
+            "api".to_string(),
+            McpServer {
+                command: Some("node".to_string()),
+                args: vec!["server.js".to_string()],
+                env: BTreeMap::from([("KEY".to_string(), "${API_KEY}".to_string())]),
+                ..Default::default()
+            },
+        );
+        skill.mcp_servers.insert(
+            "docs".to_string(),
+            McpServer {
+                url: Some("https://docs.example.com/sse".to_string()),
+                transport: McpTransport::Sse,
+                headers: BTreeMap::from([("Authorization".to_string(), "Bearer ${API_KEY}".to_string())]),
+                ..Default::default()
+            },
+        );
+
+        let mut result = ConversionResult::default();
+        write(&skill, temp_dir.path(), &mut result).await;
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let instructions = std::fs::read_to_string(temp_dir.path().join(INSTRUCTIONS_FILE)).unwrap();
+        assert!(instructions.contains("- `API_KEY`: API authentication key **(required)**"));
+
+        let prompt = std::fs::read_to_string(temp_dir.path().join(".github/prompts/run.prompt.md")).unwrap();
+        assert_eq!(prompt, "---\ndescription: Run it\nmode: agent\n---\n\nRun ${input:args}\n");
+
+        let mcp: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(temp_dir.path().join(".vscode/mcp.json")).unwrap()).unwrap();
+        assert_eq!(mcp["servers"]["api"]["type"], "stdio");
+        assert_eq!(mcp["servers"]["api"]["env"]["KEY"], "${env:API_KEY}");
+        assert_eq!(mcp["servers"]["docs"]["type"], "sse");
+        assert_eq!(mcp["servers"]["docs"]["headers"]["Authorization"], "Bearer ${env:API_KEY}");
+
+        assert_eq!(result.unmapped.len(), 1, "{:?}", result.unmapped);
+        assert!(result.unmapped[0].starts_with("Settings (API_KEY)"));
+    }
+}
@@ -0,0 +1,222 @@
+use super::ir::{Skill, ARGS_PLACEHOLDER};
+use super::placeholders::{self, Token};
+use super::settings;
+use super::{
+    instructions_markdown, render_markdown, report_unmapped_mcp_options, report_unmapped_settings,
+    report_unmapped_tools, write_assets, write_command_file, ConversionResult, McpServerConfig, MCP_CONFIG_OPTIONS,
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::fs;
+
+const TARGET: &str = "Cursor";
+
+// ============================================================================
+// Models
+// ============================================================================
+
+/// Frontmatter of a `.mdc` rule. A rule with a description and no globs is
+/// "agent requested": Cursor pulls it in when the description matches the task.
+#[derive(Debug, Clone, Serialize)]
+struct RuleFrontmatter {
+    description: String,
+    globs: String,
+    #[serde(rename = "alwaysApply")]
+    always_apply: bool,
+}
+
+/// `.cursor/mcp.json`.
+#[derive(Debug, Clone, Default, Serialize)]
+struct McpFile {
+    #[serde(rename = "mcpServers")]
+    mcp_servers: BTreeMap<String, McpServerConfig>,
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+/// Write `.cursor/rules/*.mdc`, `.cursor/commands/*.md` and `.cursor/mcp.json`.
+pub async fn write(skill: &Skill, output: &Path, result: &mut ConversionResult) {
+    // Step 1: Generate rules from the instructions and subagents
+    match write_rules(skill, output, result).await {
+        Ok(paths) => result.files.extend(paths),
+        Err(e) => result.errors.push(format!("Failed to generate rules: {}", e)),
+    }
+
+    // Step 2: Generate commands
+    match write_commands(skill, output, result).await {
+        Ok(paths) => result.files.extend(paths),
+        Err(e) => result.errors.push(format!("Failed to generate commands: {}", e)),
+    }
+
+    // Step 3: Generate .cursor/mcp.json
+    match write_mcp(skill, output).await {
+        Ok(Some(path)) => result.files.push(path),
+        Ok(None) => {}
+        Err(e) => result.errors.push(format!("Failed to generate mcp.json: {}", e)),
+    }
+    for (name, server) in &skill.mcp_servers {
+        report_unmapped_mcp_options(name, server, TARGET, MCP_CONFIG_OPTIONS, result);
+    }
+
+    // Step 4: Copy bundled files
+    write_assets(&skill.assets, output, result).await;
+
+    report_unmapped_settings(skill, TARGET, &format!(".cursor/rules/{}.mdc", skill.name), result);
+    report_unmapped_tools(skill, TARGET, result);
+}
+
+async fn write_rules(skill: &Skill, output: &Path, result: &mut ConversionResult) -> Result<Vec<String>> {
+    let mut written = Vec::new();
+    let rules_dir = output.join(".cursor").join("rules");
+    fs::create_dir_all(&rules_dir).await?;
+
+    let frontmatter = RuleFrontmatter {
+        description: skill.description.clone(),
+        globs: String::new(),
+        always_apply: false,
+    };
+    let path = rules_dir.join(format!("{}.mdc", skill.name));
+    fs::write(&path, render_markdown(&frontmatter, &instructions_markdown(skill))?).await?;
+    written.push(path.to_string_lossy().to_string());
+
+    // Subagents become agent-requested rules; Cursor cannot delegate to them
+    for agent in &skill.subagents {
+        let description = agent
+            .description
+            .clone()
+            .unwrap_or_else(|| format!("{} agent", agent.name));
+        let frontmatter = RuleFrontmatter {
+            description: description.clone(),
+            globs: String::new(),
+            always_apply: false,
+        };
+        let body = agent.prompt.clone().unwrap_or(description);
+        let path = rules_dir.join(format!("{}.mdc", agent.name));
+        fs::write(&path, render_markdown(&frontmatter, &body)?).await?;
+        written.push(path.to_string_lossy().to_string());
+        result.unmapped.push(format!(
+            "Subagent {}: {} has no subagents; written as an agent-requested rule",
+            agent.name, TARGET
+        ));
+    }
+
+    Ok(written)
+}
+
+async fn write_commands(skill: &Skill, output: &Path, result: &mut ConversionResult) -> Result<Vec<String>> {
+    let mut written = Vec::new();
+    if skill.commands.is_empty() {
+        return Ok(written);
+    }
+
+    let commands_dir = output.join(".cursor").join("commands");
+    fs::create_dir_all(&commands_dir).await?;
+
+    // Cursor commands are plain markdown: no description, no argument placeholders
+    for cmd in &skill.commands {
+        let tokens = placeholders::lex_claude(&cmd.prompt);
+        if tokens.iter().any(|t| matches!(t, Token::AllArgs | Token::Arg(_))) {
+            result.unmapped.push(format!(
+                "Command {}: {} commands take no arguments; {} and $1.. are left as written",
+                cmd.name, TARGET, ARGS_PLACEHOLDER
+            ));
+        }
+        if cmd.description.is_some() {
+            result.unmapped.push(format!(
+                "Command {}: {} commands have no description",
+                cmd.name, TARGET
+            ));
+        }
+        result.warnings.extend(
+            placeholders::unsupported_injections(&tokens, TARGET)
+                .into_iter()
+                .map(|w| format!("Command {}: {}", cmd.name, w)),
+        );
+        let content = format!("{}\n", cmd.prompt.trim());
+        written.push(write_command_file(&commands_dir, &cmd.name, "md", &content).await?);
+    }
+
+    Ok(written)
+}
+
+async fn write_mcp(skill: &Skill, output: &Path) -> Result<Option<String>> {
+    if skill.mcp_servers.is_empty() {
+        return Ok(None);
+    }
+
+    let mcp = McpFile {
+        mcp_servers: skill
+            .mcp_servers
+            .iter()
+            .map(|(name, server)| {
+                let server = settings::map_values(server, settings::strip_defaults);
+                // Cursor tells the transport from the endpoint itself
+                let config = McpServerConfig {
+                    server_type: None,
+                    ..McpServerConfig::from_ir(&server)
+                };
+                (name.clone(), config)
+            })
+            .collect(),
+    };
+    let cursor_dir = output.join(".cursor");
+    fs::create_dir_all(&cursor_dir).await?;
+    let path = cursor_dir.join("mcp.json");
+    fs::write(&path, serde_json::to_string_pretty(&mcp)?).await?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::ir::{SkillCommand, Subagent};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_cursor_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let skill = Skill {
+            name: "demo".to_string(),
+            description: "Demo skill".to_string(),
+            instructions: "Do the thing.\n".to_string(),
+            commands: vec![SkillCommand {
+                name: "run".to_string(),
+                description: None,
+                prompt: "Run $ARGUMENTS".to_string(),
+                ..Default::default()
+            }],
+            subagents: vec![Subagent {
+                name: "reviewer".to_string(),
+                description: Some("Reviews code".to_string()),
+                prompt: Some("You review code.".to_string()),
+            }],
+            ..Default::default()
+        };
+
+        let mut result = ConversionResult::default();
+        write(&skill, temp_dir.path(), &mut result).await;
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let rule = std::fs::read_to_string(temp_dir.path().join(".cursor/rules/demo.mdc")).unwrap();
+        assert!(rule.starts_with("---\ndescription: Demo skill\nglobs: ''\nalwaysApply: false\n---\n"));
+        assert!(rule.contains("Do the thing."));
+
+        let agent = std::fs::read_to_string(temp_dir.path().join(".cursor/rules/reviewer.mdc")).unwrap();
+        assert!(agent.ends_with("You review code.\n"));
+
+        let command = std::fs::read_to_string(temp_dir.path().join(".cursor/commands/run.md")).unwrap();
+        assert_eq!(command, "Run $ARGUMENTS\n");
+
+        assert_eq!(result.unmapped.len(), 2, "{:?}", result.unmapped);
+        assert!(result.unmapped[0].starts_with("Subagent reviewer"));
+        assert!(result.unmapped[1].starts_with("Command run"));
+    }
+}
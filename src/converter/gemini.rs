@@ -0,0 +1,725 @@
+use super::ir::{McpServer, McpTransport, Setting, Skill, SkillCommand, Subagent, ToolPermissions};
+use super::{
+    bundle_server_files, collect_assets, command_name, command_path, find_command_files, installed_root_prefixes,
+    placeholders, relative_server_paths, rewrite_asset_links, rewrite_server_paths, settings, write_assets,
+    ConversionResult,
+};
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::fs;
+
+const ARGS: &str = "{{args}}";
+const EXTENSION_PATH: &str = "${extensionPath}/";
+const SUBAGENT_MARKER: &str = "# Auto-generated from Claude Subagent";
+
+// ============================================================================
+// Models
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiManifest {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, rename = "contextFileName", skip_serializing_if = "Option::is_none")]
+    pub context_file_name: Option<String>,
+    #[serde(default, rename = "mcpServers", skip_serializing_if = "Option::is_none")]
+    pub mcp_servers: Option<BTreeMap<String, GeminiMcpServer>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<Vec<GeminiSetting>>,
+    #[serde(default, rename = "excludeTools", skip_serializing_if = "Option::is_none")]
+    pub exclude_tools: Option<Vec<String>>,
+}
+
+/// An MCP server entry; `url` is an SSE endpoint, `httpUrl` a streamable HTTP one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GeminiMcpServer {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, rename = "httpUrl", skip_serializing_if = "Option::is_none")]
+    pub http_url: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trust: Option<bool>,
+}
+
+impl GeminiMcpServer {
+    fn to_ir(&self) -> McpServer {
+        let (url, transport) = match (&self.http_url, &self.url) {
+            (Some(url), _) => (Some(url.clone()), McpTransport::Http),
+            (None, Some(url)) => (Some(url.clone()), McpTransport::Sse),
+            (None, None) => (None, McpTransport::Stdio),
+        };
+        McpServer {
+            command: self.command.clone(),
+            args: self.args.clone(),
+            env: self.env.clone(),
+            cwd: self.cwd.clone(),
+            url,
+            transport,
+            headers: self.headers.clone(),
+            timeout: self.timeout,
+            trust: self.trust,
+        }
+    }
+
+    fn from_ir(server: &McpServer) -> Self {
+        let (url, http_url) = match server.transport {
+            McpTransport::Stdio => (None, None),
+            McpTransport::Sse => (server.url.clone(), None),
+            McpTransport::Http => (None, server.url.clone()),
+        };
+        Self {
+            command: server.command.clone(),
+            args: server.args.clone(),
+            env: server.env.clone(),
+            cwd: server.cwd.clone(),
+            url,
+            http_url,
+            headers: server.headers.clone(),
+            timeout: server.timeout,
+            trust: server.trust,
+        }
+    }
+}
+
+/// A custom command file, `commands/<name>.toml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeminiCommand {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub prompt: String,
+}
+
+/// An install-time prompt; Gemini stores the answer in `envVar`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiSetting {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, rename = "envVar", skip_serializing_if = "Option::is_none")]
+    pub env_var: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensitive: Option<bool>,
+    /// Older manifests written by this tool said `secret` instead of `sensitive`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+}
+
+impl GeminiSetting {
+    fn to_ir(&self) -> Setting {
+        Setting {
+            name: self.env_var.clone().unwrap_or_else(|| self.name.clone()),
+            description: self.description.clone(),
+            default: self.default.clone(),
+            secret: self.sensitive.or(self.secret).unwrap_or(false),
+            required: self.required.unwrap_or(false),
+        }
+    }
+
+    fn from_ir(setting: &Setting) -> Self {
+        Self {
+            name: setting.name.clone(),
+            description: setting.description.clone(),
+            env_var: Some(setting.name.clone()),
+            sensitive: setting.secret.then_some(true),
+            secret: None,
+            default: setting.default.clone(),
+            required: setting.required.then_some(true),
+        }
+    }
+}
+
+// ============================================================================
+// Reader
+// ============================================================================
+
+/// Read a Gemini extension: `gemini-extension.json`, its context file and
+/// `commands/*.toml`.
+pub async fn read(source: &Path, result: &mut ConversionResult) -> Result<Skill> {
+    let manifest_content = fs::read_to_string(source.join("gemini-extension.json")).await?;
+    let manifest: GeminiManifest = serde_json::from_str(&manifest_content)?;
+
+    let context_file_name = manifest.context_file_name.as_deref().unwrap_or("GEMINI.md");
+    let content = fs::read_to_string(source.join(context_file_name)).await.unwrap_or_default();
+
+    let assets = collect_assets(source)?;
+    let instructions = strip_converter_boilerplate(&content)?;
+    let mut skill = Skill {
+        name: manifest.name.clone(),
+        version: manifest.version.clone(),
+        description: manifest.description.clone(),
+        // Links into the extension become relative to the skill root
+        instructions: rewrite_asset_links(&instructions, &assets, &[EXTENSION_PATH.to_string()], ""),
+        assets,
+        ..Default::default()
+    };
+
+    if let Some(declared) = &manifest.settings {
+        skill.settings = declared.iter().map(GeminiSetting::to_ir).collect();
+    }
+    if let Some(servers) = &manifest.mcp_servers {
+        let declared = &skill.settings;
+        skill.mcp_servers = servers
+            .iter()
+            .map(|(name, cfg)| {
+                // Paths into the extension become relative to the skill root
+                let server = relative_server_paths(&cfg.to_ir(), &[EXTENSION_PATH]);
+                let server = settings::map_values(&server, |value| settings::from_gemini_value(value, declared));
+                (name.clone(), server)
+            })
+            .collect();
+        bundle_server_files(&mut skill, source)?;
+    }
+    settings::add_missing_settings(&mut skill.settings, &skill.mcp_servers);
+    if let Some(exclude_tools) = &manifest.exclude_tools {
+        skill.tools = ToolPermissions::from_excluded(exclude_tools);
+    }
+
+    read_commands(&source.join("commands"), &mut skill, result).await?;
+    Ok(skill)
+}
+
+/// Remove the header, quick start and footer the writer adds to GEMINI.md.
+fn strip_converter_boilerplate(content: &str) -> Result<String> {
+    let gemini_header_regex = Regex::new(r"^#\s+.+?\s+-\s+Gemini CLI Extension\n\n")?;
+    let quick_start_regex = Regex::new(r"##\s+Quick Start[\s\S]+?After installation.+?\n\n")?;
+    let footer_regex = Regex::new(r"\n---\n\n\*This extension was converted.+?\*\n$")?;
+
+    let clean_content = gemini_header_regex.replace(content, "");
+    let clean_content = quick_start_regex.replace(&clean_content, "");
+    let clean_content = footer_regex.replace(&clean_content, "");
+    Ok(clean_content.to_string())
+}
+
+async fn read_commands(dir: &Path, skill: &mut Skill, result: &mut ConversionResult) -> Result<()> {
+    let persona_regex = Regex::new(r"^\s*You are acting as the '.+?' agent\.\n([\s\S]*?)\n*User Query: \{\{args\}\}\s*$")?;
+
+    for path in find_command_files(dir, "toml")? {
+        let Some(name) = command_name(dir, &path, "toml") else {
+            continue;
+        };
+        let content = fs::read_to_string(&path).await?;
+        let command: GeminiCommand = match toml::from_str(&content) {
+            Ok(command) => command,
+            Err(e) => {
+                result
+                    .warnings
+                    .push(format!("Skipped command {}: invalid TOML: {}", path.display(), e));
+                continue;
+            }
+        };
+        let prompt = command.prompt.trim();
+
+        // Subagents come back from the commands the writer generated for them
+        if content.contains(SUBAGENT_MARKER) {
+            let description = persona_regex
+                .captures(prompt)
+                .and_then(|caps| caps.get(1))
+                .map(|m| m.as_str().trim().to_string())
+                .filter(|d| !d.is_empty());
+            skill.subagents.push(Subagent {
+                name,
+                description,
+                prompt: None,
+            });
+            continue;
+        }
+
+        let (prompt, warnings) = placeholders::gemini_to_claude(prompt);
+        result
+            .warnings
+            .extend(warnings.into_iter().map(|w| format!("Command {}: {}", name, w)));
+        skill.commands.push(SkillCommand {
+            name,
+            description: command.description,
+            prompt,
+            ..Default::default()
+        });
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+/// Write `gemini-extension.json`, `GEMINI.md` and `commands/*.toml`.
+pub async fn write(skill: &Skill, output: &Path, result: &mut ConversionResult) {
+    // Step 1: Generate gemini-extension.json
+    match write_manifest(skill, output).await {
+        Ok(path) => result.files.push(path),
+        Err(e) => result.errors.push(format!("Failed to generate manifest: {}", e)),
+    }
+
+    // Step 2: Generate GEMINI.md
+    match write_context(skill, output).await {
+        Ok(path) => result.files.push(path),
+        Err(e) => result.errors.push(format!("Failed to generate context: {}", e)),
+    }
+
+    // Step 3: Generate Custom Commands
+    match write_commands(skill, output, result).await {
+        Ok(paths) => result.files.extend(paths),
+        Err(e) => result.errors.push(format!("Failed to generate commands: {}", e)),
+    }
+
+    // Step 4: Copy bundled files
+    write_assets(&skill.assets, output, result).await;
+
+    for agent in &skill.subagents {
+        result.unmapped.push(format!(
+            "Subagent {}: Gemini CLI has no subagents; written as the command /{}",
+            agent.name, agent.name
+        ));
+    }
+}
+
+pub fn build_manifest(skill: &Skill) -> GeminiManifest {
+    let mut manifest = GeminiManifest {
+        name: skill.name.clone(),
+        version: Some(skill.version.clone().unwrap_or_else(|| "1.0.0".to_string())),
+        description: skill.description.clone(),
+        context_file_name: Some("GEMINI.md".to_string()),
+        mcp_servers: None,
+        settings: None,
+        exclude_tools: None,
+    };
+
+    if !skill.mcp_servers.is_empty() {
+        manifest.mcp_servers = Some(
+            skill
+                .mcp_servers
+                .iter()
+                .map(|(name, server)| {
+                    let server = rewrite_server_paths(server, &skill.assets, EXTENSION_PATH);
+                    let server = settings::map_values(&server, settings::strip_defaults);
+                    (name.clone(), GeminiMcpServer::from_ir(&server))
+                })
+                .collect(),
+        );
+    }
+
+    // Convert allowed-tools to excludeTools
+    if skill.tools.allowed.is_some() {
+        manifest.exclude_tools = Some(skill.tools.excluded());
+    }
+
+    // Every placeholder gets a prompt, carrying the default its `:-` spelled out
+    let mut declared = skill.settings.clone();
+    settings::add_missing_settings(&mut declared, &skill.mcp_servers);
+    if !declared.is_empty() {
+        manifest.settings = Some(declared.iter().map(GeminiSetting::from_ir).collect());
+    }
+
+    manifest
+}
+
+async fn write_manifest(skill: &Skill, output: &Path) -> Result<String> {
+    let output_path = output.join("gemini-extension.json");
+    let json_content = serde_json::to_string_pretty(&build_manifest(skill))?;
+    fs::write(&output_path, json_content).await?;
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+async fn write_context(skill: &Skill, output: &Path) -> Result<String> {
+    let mut gemini_content = format!("# {} - Gemini CLI Extension\n\n", skill.name);
+    gemini_content.push_str(&skill.description);
+    gemini_content.push_str("\n\n## Quick Start\n\nAfter installation, you can use this extension by asking questions or giving commands naturally.\n\n");
+    // GEMINI.md is loaded into the user's project, so bundled files need the extension root
+    let mut prefixes = installed_root_prefixes(&skill.name);
+    prefixes.extend([EXTENSION_PATH.to_string(), "./".to_string()]);
+    gemini_content.push_str(&rewrite_asset_links(&skill.instructions, &skill.assets, &prefixes, EXTENSION_PATH));
+    gemini_content.push_str("\n\n---\n\n");
+    gemini_content.push_str("*This extension was converted from a Claude Code skill using [skill-porter](https://github.com/jduncan-rva/skill-porter)*\n");
+
+    let output_path = output.join("GEMINI.md");
+    fs::write(&output_path, gemini_content).await?;
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+async fn write_commands(skill: &Skill, output: &Path, result: &mut ConversionResult) -> Result<Vec<String>> {
+    let mut generated_files = Vec::new();
+    if skill.subagents.is_empty() && skill.commands.is_empty() {
+        return Ok(generated_files);
+    }
+
+    let commands_dir = output.join("commands");
+    fs::create_dir_all(&commands_dir).await?;
+
+    // Convert Subagents -> Commands
+    for agent in &skill.subagents {
+        let command = GeminiCommand {
+            description: Some(format!("Activate {} agent", agent.name)),
+            prompt: format!(
+                "You are acting as the '{}' agent.\n{}\n\nUser Query: {}\n",
+                agent.name,
+                agent.description.as_deref().unwrap_or(""),
+                ARGS
+            ),
+        };
+        let header = format!("# Agent Persona: {}\n{}\n", agent.name, SUBAGENT_MARKER);
+        if let Some(path) = write_command(&commands_dir, &agent.name, &header, &command, result).await? {
+            generated_files.push(path);
+        }
+    }
+
+    // Convert Commands -> Gemini Commands
+    for cmd in &skill.commands {
+        // Convert placeholder and injection syntax
+        let (prompt, mut warnings) = placeholders::claude_to_gemini(&cmd.prompt);
+        if cmd.allowed_tools.is_some() {
+            warnings.push("allowed-tools has no Gemini equivalent; Gemini asks before running tools".to_string());
+        }
+        if cmd.argument_hint.is_some() {
+            warnings.push("argument-hint has no Gemini equivalent".to_string());
+        }
+        if cmd.model.is_some() {
+            warnings.push("model has no Gemini equivalent; the session model is used".to_string());
+        }
+        result
+            .warnings
+            .extend(warnings.into_iter().map(|w| format!("Command {}: {}", cmd.name, w)));
+
+        let command = GeminiCommand {
+            description: Some(
+                cmd.description
+                    .clone()
+                    .unwrap_or_else(|| format!("Custom command: {}", cmd.name)),
+            ),
+            prompt: format!("{}\n", prompt.trim()),
+        };
+        if let Some(path) = write_command(&commands_dir, &cmd.name, "", &command, result).await? {
+            generated_files.push(path);
+        }
+    }
+
+    Ok(generated_files)
+}
+
+/// Serialize one command file; names and prompts the format cannot hold are
+/// reported and skipped.
+async fn write_command(
+    commands_dir: &Path,
+    name: &str,
+    header: &str,
+    command: &GeminiCommand,
+    result: &mut ConversionResult,
+) -> Result<Option<String>> {
+    let Some(path) = command_path(commands_dir, name, "toml") else {
+        result
+            .errors
+            .push(format!("Command {}: name cannot be mapped to a file under commands/", name));
+        return Ok(None);
+    };
+    let content = match encode_command(header, command) {
+        Ok(content) => content,
+        Err(reason) => {
+            result.errors.push(format!("Command {}: {}", name, reason));
+            return Ok(None);
+        }
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(&path, content).await?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Serialize one command file, or say why Gemini could not read it back.
+pub fn encode_command(header: &str, command: &GeminiCommand) -> std::result::Result<String, &'static str> {
+    if command.prompt.trim().is_empty() {
+        return Err("Gemini commands need a non-empty prompt");
+    }
+
+    let content = toml::to_string(command)
+        .map(|toml| format!("{}{}", header, toml))
+        .map_err(|_| "content cannot be encoded as TOML")?;
+    // Never emit a file the reader would parse differently
+    if toml::from_str::<GeminiCommand>(&content).ok().as_ref() != Some(command) {
+        return Err("content cannot be encoded as TOML");
+    }
+    Ok(content)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::ir::{Asset, KNOWN_TOOLS};
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_gemini_manifest_deserialization() {
+        let json_str = r#"{
+            "name": "Test Extension",
+            "version": "1.0.0",
+            "description": "Test description",
+            "contextFileName": "GEMINI.md",
+            "excludeTools": ["Edit", "Grep"]
+        }"#;
+
+        let manifest: GeminiManifest = serde_json::from_str(json_str).unwrap();
+        assert_eq!(manifest.name, "Test Extension");
+        assert_eq!(manifest.version, Some("1.0.0".to_string()));
+        assert_eq!(manifest.exclude_tools, Some(vec!["Edit".to_string(), "Grep".to_string()]));
+    }
+
+    #[test]
+    fn test_gemini_manifest_serialization() {
+        let skill = Skill {
+            name: "Test Extension".to_string(),
+            description: "Test description".to_string(),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string_pretty(&build_manifest(&skill)).unwrap();
+        assert!(json.contains("\"name\": \"Test Extension\""));
+        assert!(json.contains("\"version\": \"1.0.0\""));
+    }
+
+    #[test]
+    fn test_setting_with_secret() {
+        let setting = GeminiSetting::from_ir(&Setting {
+            name: "API_KEY".to_string(),
+            description: "API Key".to_string(),
+            default: None,
+            secret: true,
+            required: true,
+        });
+
+        let json = serde_json::to_string_pretty(&setting).unwrap();
+        assert!(json.contains("\"envVar\": \"API_KEY\""));
+        assert!(json.contains("\"sensitive\": true"));
+        assert!(json.contains("\"required\": true"));
+        assert!(!json.contains("\"secret\""));
+
+        // Manifests from older conversions said `secret`
+        let legacy: GeminiSetting = serde_json::from_str(r#"{"name": "API_KEY", "secret": true}"#).unwrap();
+        assert!(legacy.to_ir().secret);
+        let native: GeminiSetting =
+            serde_json::from_str(r#"{"name": "API key", "envVar": "API_KEY", "sensitive": true}"#).unwrap();
+        assert_eq!(native.to_ir().name, "API_KEY");
+    }
+
+    #[test]
+    fn test_convert_allowed_tools_to_exclude() {
+        let skill = Skill {
+            tools: ToolPermissions {
+                allowed: Some(vec!["Read".to_string(), "Write".to_string(), "Bash".to_string()]),
+            },
+            ..Default::default()
+        };
+
+        let excluded = build_manifest(&skill).exclude_tools.unwrap();
+        assert!(excluded.contains(&"Edit".to_string()));
+        assert!(!excluded.contains(&"Read".to_string()));
+    }
+
+    #[test]
+    fn test_large_allow_list_still_excludes() {
+        let allowed: Vec<String> = KNOWN_TOOLS.iter().filter(|t| **t != "Bash").map(|t| t.to_string()).collect();
+        let skill = Skill {
+            tools: ToolPermissions { allowed: Some(allowed) },
+            ..Default::default()
+        };
+
+        assert_eq!(build_manifest(&skill).exclude_tools, Some(vec!["Bash".to_string()]));
+    }
+
+    #[test]
+    fn test_transform_mcp_servers() {
+        let mut skill = Skill {
+            assets: ["server/index.js", "config/app.json"]
+                .iter()
+                .map(|path| Asset {
+                    path: path.to_string(),
+                    source: path.into(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+        skill.mcp_servers.insert(
+            "local".to_string(),
+            McpServer {
+                command: Some("node".to_string()),
+                args: ["./server/index.js", "run", "--port", "8080", "--config=config/app.json", "@scope/pkg"]
+                    .map(String::from)
+                    .to_vec(),
+                cwd: Some(".".to_string()),
+                timeout: Some(30000),
+                trust: Some(true),
+                ..Default::default()
+            },
+        );
+        skill.mcp_servers.insert(
+            "remote".to_string(),
+            McpServer {
+                url: Some("https://mcp.example.com/mcp".to_string()),
+                transport: McpTransport::Http,
+                headers: BTreeMap::from([("Authorization".to_string(), "Bearer ${API_TOKEN}".to_string())]),
+                ..Default::default()
+            },
+        );
+
+        let servers = build_manifest(&skill).mcp_servers.unwrap();
+        assert_eq!(
+            servers["local"].args,
+            vec![
+                "${extensionPath}/server/index.js",
+                "run",
+                "--port",
+                "8080",
+                "--config=${extensionPath}/config/app.json",
+                "@scope/pkg"
+            ]
+        );
+        assert_eq!(servers["local"].cwd.as_deref(), Some("${extensionPath}"));
+        assert_eq!(servers["local"].timeout, Some(30000));
+        assert_eq!(servers["remote"].http_url.as_deref(), Some("https://mcp.example.com/mcp"));
+        assert!(servers["remote"].url.is_none());
+
+        let json = serde_json::to_value(&servers).unwrap();
+        assert_eq!(json["remote"]["headers"]["Authorization"], "Bearer ${API_TOKEN}");
+        assert_eq!(json["local"]["trust"], true);
+
+        let read_back: BTreeMap<String, McpServer> = servers
+            .iter()
+            .map(|(name, cfg)| (name.clone(), relative_server_paths(&cfg.to_ir(), &[EXTENSION_PATH])))
+            .collect();
+        let mut expected = skill.mcp_servers.clone();
+        let local = expected.get_mut("local").unwrap();
+        local.args[0] = "server/index.js".to_string();
+        assert_eq!(read_back, expected);
+    }
+
+    #[tokio::test]
+    async fn test_read_extension_with_commands() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path();
+        fs::create_dir_all(source.join("commands")).unwrap();
+
+        let manifest = json!({
+            "name": "Test Extension",
+            "description": "Test",
+            "contextFileName": "GEMINI.md",
+            "mcpServers": {
+                "db": { "command": "node", "args": ["${extensionPath}/server.js", "serve"] },
+                "docs": { "url": "https://example.com/sse" }
+            },
+            "excludeTools": ["Edit"]
+        });
+        fs::write(source.join("gemini-extension.json"), manifest.to_string()).unwrap();
+        fs::write(
+            source.join("GEMINI.md"),
+            "# Test Extension - Gemini CLI Extension\n\nTest content\n",
+        )
+        .unwrap();
+        fs::write(source.join("server.js"), "").unwrap();
+        fs::write(
+            source.join("commands").join("test.toml"),
+            "description = \"Test Command\"\n\nprompt = \"\"\"\nUser Query: {{args}}\n\"\"\"\n",
+        )
+        .unwrap();
+
+        let mut result = ConversionResult::default();
+        let skill = read(source, &mut result).await.unwrap();
+
+        assert_eq!(skill.instructions, "Test content\n");
+        assert_eq!(skill.mcp_servers["db"].args, vec!["server.js", "serve"]);
+        assert_eq!(skill.mcp_servers["docs"].transport, McpTransport::Sse);
+        assert!(skill.assets.iter().any(|asset| asset.path == "server.js"));
+        assert!(!skill.tools.allowed.unwrap().contains(&"Edit".to_string()));
+        assert_eq!(skill.commands[0].description.as_deref(), Some("Test Command"));
+        assert!(skill.commands[0].prompt.contains("$ARGUMENTS"));
+    }
+
+    #[tokio::test]
+    async fn test_commands_survive_quotes_and_nesting() {
+        let temp_dir = TempDir::new().unwrap();
+        let skill = Skill {
+            name: "demo".to_string(),
+            commands: vec![
+                SkillCommand {
+                    name: "feature:action".to_string(),
+                    description: Some("Say \"hi\"".to_string()),
+                    prompt: "Print \"\"\" then 'quoted' text for $ARGUMENTS\\n".to_string(),
+                    ..Default::default()
+                },
+                SkillCommand {
+                    name: "../escape".to_string(),
+                    description: None,
+                    prompt: "Nope".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let mut result = ConversionResult::default();
+        write_commands(&skill, temp_dir.path(), &mut result).await.unwrap();
+        assert_eq!(result.errors.len(), 1, "{:?}", result.errors);
+        assert!(result.errors[0].starts_with("Command ../escape"));
+        assert!(temp_dir.path().join("commands/feature/action.toml").exists());
+
+        let mut read_back = Skill::default();
+        read_commands(&temp_dir.path().join("commands"), &mut read_back, &mut result)
+            .await
+            .unwrap();
+        assert_eq!(read_back.commands.len(), 1);
+        assert_eq!(read_back.commands[0].name, "feature:action");
+        assert_eq!(read_back.commands[0].description.as_deref(), Some("Say \"hi\""));
+        assert_eq!(read_back.commands[0].prompt, skill.commands[0].prompt);
+    }
+
+    #[tokio::test]
+    async fn test_read_commands_accepts_literal_strings_and_reports_invalid_toml() {
+        let temp_dir = TempDir::new().unwrap();
+        let commands_dir = temp_dir.path().join("commands");
+        fs::create_dir_all(&commands_dir).unwrap();
+        fs::write(
+            commands_dir.join("literal.toml"),
+            "description = 'Single quoted'\nprompt = '''\nRaw \\d+ for {{args}}\n'''\n",
+        )
+        .unwrap();
+        fs::write(commands_dir.join("broken.toml"), "prompt = \"unterminated\n").unwrap();
+
+        let mut skill = Skill::default();
+        let mut result = ConversionResult::default();
+        read_commands(&commands_dir, &mut skill, &mut result).await.unwrap();
+
+        assert_eq!(skill.commands.len(), 1);
+        assert_eq!(skill.commands[0].description.as_deref(), Some("Single quoted"));
+        assert_eq!(skill.commands[0].prompt, "Raw \\d+ for $ARGUMENTS");
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("broken.toml"));
+    }
+}
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+// ============================================================================
+// Intermediate Representation
+// ============================================================================
+//
+// Every format reader produces a `Skill` and every writer consumes one, so a
+// new format only needs a reader and a writer instead of a converter per pair.
+// Paths inside the IR are relative to the skill root and prompts use Claude's
+// command syntax (`$ARGUMENTS`, `$1`, !`cmd`, `@file`); readers and writers
+// translate at the edges through `placeholders`. MCP values use Claude's
+// `${VAR}` placeholders, mapped to settings by `settings`.
+
+/// Placeholder for the user's arguments in IR prompts.
+pub const ARGS_PLACEHOLDER: &str = "$ARGUMENTS";
+
+/// Built-in tools that tool permissions are expressed against.
+pub const KNOWN_TOOLS: &[&str] = &[
+    "Read",
+    "Write",
+    "Edit",
+    "Glob",
+    "Grep",
+    "Bash",
+    "Task",
+    "WebFetch",
+    "WebSearch",
+    "TodoWrite",
+    "AskUserQuestion",
+    "SlashCommand",
+    "Skill",
+    "NotebookEdit",
+    "BashOutput",
+    "KillShell",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Skill {
+    pub name: String,
+    pub version: Option<String>,
+    pub description: String,
+    /// Main instructions (SKILL.md body, GEMINI.md content)
+    pub instructions: String,
+    pub commands: Vec<SkillCommand>,
+    pub subagents: Vec<Subagent>,
+    pub mcp_servers: BTreeMap<String, McpServer>,
+    pub settings: Vec<Setting>,
+    pub tools: ToolPermissions,
+    pub assets: Vec<Asset>,
+    pub author: Option<Author>,
+    /// Source metadata no other field covers (e.g. unknown SKILL.md
+    /// frontmatter keys), kept verbatim
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// Who publishes the skill; marketplaces list it as the owner.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Author {
+    pub name: String,
+    pub email: Option<String>,
+}
+
+/// A slash command invoked explicitly by the user.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SkillCommand {
+    /// `feature:action` for namespaced commands
+    pub name: String,
+    pub description: Option<String>,
+    pub prompt: String,
+    /// Tools the command may use without asking, e.g. `Bash(git status:*)`
+    pub allowed_tools: Option<Vec<String>>,
+    /// Shown while typing arguments, e.g. `[pr-number]`
+    pub argument_hint: Option<String>,
+    pub model: Option<String>,
+}
+
+/// A persona the agent can delegate to.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Subagent {
+    pub name: String,
+    pub description: Option<String>,
+    /// System prompt, when the format stores one separately from the description
+    pub prompt: Option<String>,
+}
+
+/// An MCP server: a local process (`command`) or a remote endpoint (`url`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct McpServer {
+    pub command: Option<String>,
+    /// Arguments; file paths are relative to the skill root
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    /// Working directory of the process, relative to the skill root
+    pub cwd: Option<String>,
+    pub url: Option<String>,
+    pub transport: McpTransport,
+    /// HTTP headers sent to a remote server
+    pub headers: BTreeMap<String, String>,
+    /// Request timeout in milliseconds
+    pub timeout: Option<u64>,
+    /// Run the server's tools without asking for confirmation
+    pub trust: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpTransport {
+    #[default]
+    Stdio,
+    /// Server-sent events
+    Sse,
+    /// Streamable HTTP
+    Http,
+}
+
+impl McpServer {
+    pub fn is_remote(&self) -> bool {
+        self.transport != McpTransport::Stdio
+    }
+
+    /// Names of the options set on this server that `supported` leaves out,
+    /// for targets that have nowhere to put them.
+    pub fn unsupported_options(&self, supported: &[&str]) -> Vec<&'static str> {
+        let set = [
+            ("cwd", self.cwd.is_some()),
+            ("headers", !self.headers.is_empty()),
+            ("timeout", self.timeout.is_some()),
+            ("trust", self.trust.is_some()),
+        ];
+        set.iter()
+            .filter(|(name, is_set)| *is_set && !supported.contains(name))
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+/// A value the user configures when installing, e.g. an API key.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Setting {
+    pub name: String,
+    pub description: String,
+    pub default: Option<String>,
+    pub secret: bool,
+    pub required: bool,
+}
+
+/// Built-in tools the skill may use; `None` leaves every tool available.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolPermissions {
+    pub allowed: Option<Vec<String>>,
+}
+
+impl ToolPermissions {
+    pub fn from_excluded(excluded: &[String]) -> Self {
+        Self {
+            allowed: Some(
+                KNOWN_TOOLS
+                    .iter()
+                    .filter(|tool| !excluded.iter().any(|e| e == *tool))
+                    .map(|tool| tool.to_string())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Known tools outside the allowed list.
+    pub fn excluded(&self) -> Vec<String> {
+        let Some(allowed) = &self.allowed else {
+            return Vec::new();
+        };
+        KNOWN_TOOLS
+            .iter()
+            .filter(|tool| !allowed.iter().any(|a| a == *tool))
+            .map(|tool| tool.to_string())
+            .collect()
+    }
+}
+
+/// A file shipped with the skill and copied verbatim.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Asset {
+    /// Location relative to the skill root
+    pub path: String,
+    /// Where the reader found it
+    pub source: PathBuf,
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_permissions_round_trip() {
+        let tools = ToolPermissions::from_excluded(&["Edit".to_string(), "Grep".to_string()]);
+        let allowed = tools.allowed.clone().unwrap();
+
+        assert!(allowed.contains(&"Read".to_string()));
+        assert!(!allowed.contains(&"Edit".to_string()));
+        assert_eq!(tools.excluded(), vec!["Edit".to_string(), "Grep".to_string()]);
+    }
+}
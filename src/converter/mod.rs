@@ -0,0 +1,1117 @@
+pub mod batch;
+pub mod claude;
+pub mod claude_plugin;
+pub mod codex;
+pub mod copilot;
+pub mod cursor;
+pub mod gemini;
+pub mod ir;
+pub mod opencode;
+pub mod placeholders;
+pub mod settings;
+
+use anyhow::{anyhow, Result};
+use ir::{Asset, McpServer, McpTransport, Skill};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Skill layouts `bl convert` can read and write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Claude Code skill: `SKILL.md` plus optional `.claude-plugin/marketplace.json`
+    Claude,
+    /// Claude Code plugin: `.claude-plugin/plugin.json` with `commands/`, `agents/` and `skills/`
+    ClaudePlugin,
+    /// Gemini CLI extension: `gemini-extension.json` plus its context file
+    Gemini,
+    /// Codex CLI: `AGENTS.md`, `.codex/prompts/` and `.codex/config.toml` (write only)
+    Codex,
+    /// Cursor: `.cursor/rules/*.mdc`, `.cursor/commands/` and `.cursor/mcp.json` (write only)
+    Cursor,
+    /// GitHub Copilot: `.github/copilot-instructions.md`, prompt files and chat modes (write only)
+    Copilot,
+    /// opencode: `AGENTS.md`, `.opencode/command/`, `.opencode/agent/` and `opencode.json` (write only)
+    OpenCode,
+}
+
+impl Format {
+    pub const ALL: &'static [Format] = &[
+        Format::Claude,
+        Format::ClaudePlugin,
+        Format::Gemini,
+        Format::Codex,
+        Format::Cursor,
+        Format::Copilot,
+        Format::OpenCode,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|format| format.as_str() == name)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Format::Claude => "claude",
+            Format::ClaudePlugin => "claude-plugin",
+            Format::Gemini => "gemini",
+            Format::Codex => "codex",
+            Format::Cursor => "cursor",
+            Format::Copilot => "copilot",
+            Format::OpenCode => "opencode",
+        }
+    }
+
+    /// Whether a reader exists; the other formats are conversion targets only.
+    pub fn readable(self) -> bool {
+        matches!(self, Format::Claude | Format::ClaudePlugin | Format::Gemini)
+    }
+}
+
+/// Formats whose entry file is present in `path`. A directory converted in
+/// place holds several, so callers must ask for `--from` when this is ambiguous.
+pub fn detect_formats(path: &Path) -> Vec<Format> {
+    let mut formats = Vec::new();
+    if path.join("SKILL.md").is_file() {
+        formats.push(Format::Claude);
+    }
+    if path.join(".claude-plugin").join("plugin.json").is_file() {
+        formats.push(Format::ClaudePlugin);
+    }
+    if path.join("gemini-extension.json").is_file() {
+        formats.push(Format::Gemini);
+    }
+    formats
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConversionResult {
+    pub success: bool,
+    pub files: Vec<String>,
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+    /// Parts of the skill the target format has no place for
+    pub unmapped: Vec<String>,
+}
+
+// ============================================================================
+// Conversion Pipeline
+// ============================================================================
+
+/// Read `source` as `format`, restoring what the format cannot express from
+/// the provenance file an earlier conversion left behind.
+pub async fn read(format: Format, source: &Path, result: &mut ConversionResult) -> Result<Skill> {
+    let skill = read_native(format, source, result).await?;
+    restore_provenance(format, source, skill, result).await
+}
+
+async fn read_native(format: Format, source: &Path, result: &mut ConversionResult) -> Result<Skill> {
+    match format {
+        Format::Claude => claude::read(source, result).await,
+        Format::ClaudePlugin => claude_plugin::read(source, result).await,
+        Format::Gemini => gemini::read(source, result).await,
+        Format::Codex | Format::Cursor | Format::Copilot | Format::OpenCode => {
+            Err(anyhow!("{} can only be written, not read", format.as_str()))
+        }
+    }
+}
+
+/// Write `skill` in `format`; failures of individual files land in `result.errors`.
+pub async fn write(format: Format, skill: &Skill, output: &Path, result: &mut ConversionResult) {
+    match format {
+        Format::Claude => claude::write(skill, output, result).await,
+        Format::ClaudePlugin => claude_plugin::write(skill, output, result).await,
+        Format::Gemini => gemini::write(skill, output, result).await,
+        Format::Codex => codex::write(skill, output, result).await,
+        Format::Cursor => cursor::write(skill, output, result).await,
+        Format::Copilot => copilot::write(skill, output, result).await,
+        Format::OpenCode => opencode::write(skill, output, result).await,
+    }
+    settings::report_literal_secrets(skill, format, result);
+    settings::write_env_example(skill, output, result).await;
+}
+
+/// Read `source` as `from`, then write it to `output` as `to`.
+pub async fn convert(from: Format, to: Format, source: &Path, output: &Path) -> Result<ConversionResult> {
+    Ok(convert_skill(from, to, source, output).await?.0)
+}
+
+/// Like [`convert`], also handing back the skill when it could be read.
+pub async fn convert_skill(
+    from: Format,
+    to: Format,
+    source: &Path,
+    output: &Path,
+) -> Result<(ConversionResult, Option<Skill>)> {
+    let mut result = ConversionResult::default();
+    let mut converted = None;
+
+    // Step 1: Ensure output directory exists
+    fs::create_dir_all(output).await?;
+
+    // Step 2: Read the source into the intermediate representation
+    match read(from, source, &mut result).await {
+        Ok(skill) => {
+            // Step 3: Write the target format
+            write(to, &skill, output, &mut result).await;
+
+            // Step 4: Record the full skill so the reverse conversion is lossless
+            if to.readable() {
+                match write_provenance(from, to, &skill, output).await {
+                    Ok(path) => result.files.push(path),
+                    Err(e) => result.warnings.push(format!("Failed to write {}: {}", PROVENANCE_FILE, e)),
+                }
+            }
+            converted = Some(skill);
+        }
+        Err(e) => result.errors.push(format!("Failed to extract metadata: {:#}", e)),
+    }
+
+    result.success = result.errors.is_empty();
+    Ok((result, converted))
+}
+
+// ============================================================================
+// Provenance
+// ============================================================================
+//
+// Every target drops something: Gemini has no subagents, Claude commands have
+// no place for settings, and so on. The writer leaves the complete IR next to
+// its output, with a hash of each field as the output read back right after
+// the conversion; reading that output later takes each field from the sidecar
+// as long as its hash is unchanged, so edits made after the conversion win
+// over the stored copy.
+
+/// Sidecar written next to converted output.
+pub const PROVENANCE_FILE: &str = ".bl1nk-provenance.json";
+const PROVENANCE_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Provenance {
+    pub version: u32,
+    pub source_format: String,
+    pub target_format: String,
+    /// The skill as read from the source, without its assets
+    pub skill: Skill,
+    /// SHA-256 of each field of the written output as it read back
+    #[serde(default)]
+    pub written: BTreeMap<String, String>,
+}
+
+async fn write_provenance(from: Format, to: Format, skill: &Skill, output: &Path) -> Result<String> {
+    let provenance = Provenance {
+        version: PROVENANCE_VERSION,
+        source_format: from.as_str().to_string(),
+        target_format: to.as_str().to_string(),
+        skill: Skill {
+            assets: Vec::new(),
+            ..skill.clone()
+        },
+        written: field_hashes(&read_native(to, output, &mut ConversionResult::default()).await?),
+    };
+    let path = output.join(PROVENANCE_FILE);
+    fs::write(&path, serde_json::to_string_pretty(&provenance)?).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+async fn restore_provenance(format: Format, source: &Path, native: Skill, result: &mut ConversionResult) -> Result<Skill> {
+    let Ok(content) = fs::read_to_string(source.join(PROVENANCE_FILE)).await else {
+        return Ok(native);
+    };
+    let provenance: Provenance = match serde_json::from_str(&content) {
+        Ok(provenance) => provenance,
+        Err(e) => {
+            result.warnings.push(format!("Ignoring {}: {}", PROVENANCE_FILE, e));
+            return Ok(native);
+        }
+    };
+    // The sidecar describes a different layout in the same directory
+    if provenance.target_format != format.as_str() {
+        return Ok(native);
+    }
+    if provenance.version != PROVENANCE_VERSION {
+        result.warnings.push(format!(
+            "Ignoring {}: unsupported version {}",
+            PROVENANCE_FILE, provenance.version
+        ));
+        return Ok(native);
+    }
+
+    Ok(merge_unchanged(native, provenance.skill, &provenance.written))
+}
+
+fn field_hash(value: &impl Serialize) -> String {
+    let json = serde_json::to_vec(value).unwrap_or_default();
+    format!("{:x}", Sha256::digest(&json))
+}
+
+/// Hash of every field except the assets, which are never restored.
+fn field_hashes(skill: &Skill) -> BTreeMap<String, String> {
+    [
+        ("name", field_hash(&skill.name)),
+        ("version", field_hash(&skill.version)),
+        ("description", field_hash(&skill.description)),
+        ("instructions", field_hash(&skill.instructions)),
+        ("commands", field_hash(&skill.commands)),
+        ("subagents", field_hash(&skill.subagents)),
+        ("mcp_servers", field_hash(&skill.mcp_servers)),
+        ("settings", field_hash(&skill.settings)),
+        ("tools", field_hash(&skill.tools)),
+        ("author", field_hash(&skill.author)),
+        ("extra", field_hash(&skill.extra)),
+    ]
+    .into_iter()
+    .map(|(field, hash)| (field.to_string(), hash))
+    .collect()
+}
+
+/// Take each field from `stored` where `native` still hashes to what the
+/// writer produced for it.
+fn merge_unchanged(native: Skill, stored: Skill, written: &BTreeMap<String, String>) -> Skill {
+    fn pick<T: Serialize>(written: &BTreeMap<String, String>, field: &str, native: T, stored: T) -> T {
+        if written.get(field) == Some(&field_hash(&native)) {
+            stored
+        } else {
+            native
+        }
+    }
+
+    Skill {
+        name: pick(written, "name", native.name, stored.name),
+        version: pick(written, "version", native.version, stored.version),
+        description: pick(written, "description", native.description, stored.description),
+        instructions: pick(written, "instructions", native.instructions, stored.instructions),
+        commands: pick(written, "commands", native.commands, stored.commands),
+        subagents: pick(written, "subagents", native.subagents, stored.subagents),
+        mcp_servers: pick(written, "mcp_servers", native.mcp_servers, stored.mcp_servers),
+        settings: pick(written, "settings", native.settings, stored.settings),
+        tools: pick(written, "tools", native.tools, stored.tools),
+        assets: native.assets,
+        author: pick(written, "author", native.author, stored.author),
+        extra: pick(written, "extra", native.extra, stored.extra),
+    }
+}
+
+// ============================================================================
+// Shared Helpers
+// ============================================================================
+
+/// MCP server entry as it appears in `marketplace.json`, `.mcp.json` and
+/// `.cursor/mcp.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    /// `stdio`, `sse` or `http`; only remote servers spell it out
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub server_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+/// What `McpServerConfig` can carry besides command, args and env.
+pub const MCP_CONFIG_OPTIONS: &[&str] = &["headers"];
+
+impl McpServerConfig {
+    pub fn to_ir(&self) -> McpServer {
+        let transport = match (self.server_type.as_deref(), &self.url) {
+            (Some("sse"), _) => McpTransport::Sse,
+            (Some("http") | Some("streamable-http"), _) | (None, Some(_)) => McpTransport::Http,
+            _ => McpTransport::Stdio,
+        };
+        McpServer {
+            command: self.command.clone(),
+            args: self.args.clone(),
+            env: self.env.clone(),
+            url: self.url.clone(),
+            transport,
+            headers: self.headers.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_ir(server: &McpServer) -> Self {
+        let server_type = match server.transport {
+            McpTransport::Stdio => None,
+            McpTransport::Sse => Some("sse".to_string()),
+            McpTransport::Http => Some("http".to_string()),
+        };
+        Self {
+            server_type,
+            command: server.command.clone(),
+            args: server.args.clone(),
+            env: server.env.clone(),
+            url: server.url.clone(),
+            headers: server.headers.clone(),
+        }
+    }
+}
+
+/// Split `---` YAML frontmatter from a markdown document.
+pub fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+    let frontmatter_regex = Regex::new(r"^---\r?\n([\s\S]+?)\r?\n---(?:\r?\n|$)").unwrap();
+    let caps = frontmatter_regex.captures(content)?;
+    let whole = caps.get(0).unwrap();
+    Some((caps.get(1).unwrap().as_str(), &content[whole.end()..]))
+}
+
+/// Render a markdown file with YAML frontmatter.
+pub fn render_markdown(frontmatter: &impl Serialize, body: &str) -> Result<String> {
+    Ok(format!("---\n{}---\n\n{}\n", serde_yaml::to_string(frontmatter)?, body.trim()))
+}
+
+/// Instructions as a standalone markdown document, for formats whose context
+/// file has no frontmatter: title, description, configuration and body.
+pub fn instructions_markdown(skill: &Skill) -> String {
+    let mut content = format!("# {}\n\n", skill.name);
+    if !skill.description.trim().is_empty() {
+        content.push_str(&format!("{}\n\n", skill.description.trim()));
+    }
+    if !skill.settings.is_empty() {
+        content.push_str("## Configuration\n\n");
+        content.push_str("This skill requires the following environment variables:\n\n");
+        for setting in &skill.settings {
+            content.push_str(&claude::configuration_line(setting));
+        }
+        content.push('\n');
+    }
+    // Bundled files are copied next to the project, not into an installed skill
+    let instructions = rewrite_asset_links(&skill.instructions, &skill.assets, &installed_root_prefixes(&skill.name), "");
+    content.push_str(instructions.trim());
+    content.push('\n');
+    content
+}
+
+/// Settings become documentation in targets that have no install-time prompts.
+pub fn report_unmapped_settings(skill: &Skill, target: &str, context_file: &str, result: &mut ConversionResult) {
+    if !skill.settings.is_empty() {
+        let names: Vec<&str> = skill.settings.iter().map(|s| s.name.as_str()).collect();
+        result.unmapped.push(format!(
+            "Settings ({}): {} does not prompt for them; they are listed in {}",
+            names.join(", "),
+            target,
+            context_file
+        ));
+    }
+}
+
+/// MCP server options the target has no field for are dropped.
+pub fn report_unmapped_mcp_options(
+    name: &str,
+    server: &McpServer,
+    target: &str,
+    supported: &[&str],
+    result: &mut ConversionResult,
+) {
+    let dropped = server.unsupported_options(supported);
+    if !dropped.is_empty() {
+        result.unmapped.push(format!(
+            "MCP server {} ({}): {} has no such server options",
+            name,
+            dropped.join(", "),
+            target
+        ));
+    }
+}
+
+/// Targets without a per-skill tool allowlist drop `allowed-tools`.
+pub fn report_unmapped_tools(skill: &Skill, target: &str, result: &mut ConversionResult) {
+    if let Some(allowed) = &skill.tools.allowed {
+        result.unmapped.push(format!(
+            "Tool permissions (allowed: {}): {} has no per-skill tool allowlist",
+            allowed.join(", "),
+            target
+        ));
+    }
+}
+
+/// File for the command `name` below `dir`. Namespaced names such as
+/// `feature:action` live in subdirectories (`feature/action.<extension>`);
+/// `None` when a segment cannot be a file name.
+pub fn command_path(dir: &Path, name: &str, extension: &str) -> Option<PathBuf> {
+    let mut path = dir.to_path_buf();
+    let segments: Vec<&str> = name.split(':').collect();
+    for (i, segment) in segments.iter().enumerate() {
+        let valid = !segment.is_empty()
+            && !segment.starts_with('.')
+            && !segment.chars().any(|c| matches!(c, '/' | '\\' | '\0') || c.is_control());
+        if !valid {
+            return None;
+        }
+        if i + 1 == segments.len() {
+            path.push(format!("{}.{}", segment, extension));
+        } else {
+            path.push(segment);
+        }
+    }
+    Some(path)
+}
+
+/// Write a command file at [`command_path`], creating namespace directories.
+pub async fn write_command_file(dir: &Path, name: &str, extension: &str, content: &str) -> Result<String> {
+    let path =
+        command_path(dir, name, extension).ok_or_else(|| anyhow!("command {} cannot be mapped to a file name", name))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(&path, content).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Command name for a file found below `dir`; the inverse of [`command_path`].
+pub fn command_name(dir: &Path, path: &Path, extension: &str) -> Option<String> {
+    let relative = path.strip_prefix(dir).ok()?;
+    let mut segments: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_str().map(|s| s.to_string()))
+        .collect::<Option<_>>()?;
+    let file = segments.pop()?;
+    segments.push(file.strip_suffix(&format!(".{}", extension))?.to_string());
+    Some(segments.join(":"))
+}
+
+/// Files with `extension` anywhere below `dir`, sorted by path.
+pub fn find_command_files(dir: &Path, extension: &str) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == extension) {
+            files.push(entry.path().to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+/// Directories whose files a skill ships alongside its instructions.
+const ASSET_DIRS: &[&str] = &["scripts", "references", "assets", "agents"];
+
+/// Top-level files that must travel with the bundled scripts.
+const LEGAL_FILES: &[&str] = &["LICENSE", "LICENSE.txt", "LICENSE.md", "NOTICE", "NOTICE.txt", "NOTICE.md"];
+
+/// Collect the files under the asset directories of `root`, plus its license
+/// and notice files.
+pub fn collect_assets(root: &Path) -> Result<Vec<Asset>> {
+    let mut assets = Vec::new();
+    for name in LEGAL_FILES {
+        let path = root.join(name);
+        if path.is_file() {
+            assets.push(Asset {
+                path: name.to_string(),
+                source: path,
+            });
+        }
+    }
+    for dir in ASSET_DIRS {
+        let dir = root.join(dir);
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(&dir).sort_by_file_name() {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            assets.push(Asset {
+                path: relative.to_string_lossy().replace('\\', "/"),
+                source: entry.path().to_path_buf(),
+            });
+        }
+    }
+    Ok(assets)
+}
+
+/// Copy `assets` below `root`, skipping files that are already in place.
+pub async fn write_assets(assets: &[Asset], root: &Path, result: &mut ConversionResult) {
+    for asset in assets {
+        let target = root.join(&asset.path);
+        if target == asset.source {
+            continue;
+        }
+        let copied = async {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            fs::copy(&asset.source, &target).await
+        };
+        match copied.await {
+            Ok(_) => result.files.push(target.to_string_lossy().to_string()),
+            Err(e) => result.errors.push(format!("Failed to copy {}: {}", asset.path, e)),
+        }
+    }
+}
+
+/// Rewrite references to `assets` in `text` so they resolve from `root`.
+/// A reference is an asset path at the start of a word, optionally behind one
+/// of `prefixes` (another spelling of the skill root); paths inside URLs or
+/// longer paths are left alone.
+pub fn rewrite_asset_links(text: &str, assets: &[Asset], prefixes: &[String], root: &str) -> String {
+    let mut paths: Vec<&str> = assets.iter().map(|a| a.path.as_str()).collect();
+    // Longest first, so `scripts/a.py.bak` is not taken for `scripts/a.py`
+    paths.sort_by_key(|p| std::cmp::Reverse(p.len()));
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut prev: Option<char> = None;
+    'scan: while let Some(c) = rest.chars().next() {
+        if !prev.is_some_and(is_path_char) {
+            for prefix in prefixes.iter().map(String::as_str).chain([""]) {
+                let Some(after_prefix) = rest.strip_prefix(prefix) else {
+                    continue;
+                };
+                for path in &paths {
+                    let Some(after) = after_prefix.strip_prefix(path) else {
+                        continue;
+                    };
+                    if ends_path(after) {
+                        out.push_str(root);
+                        out.push_str(path);
+                        rest = after;
+                        prev = path.chars().last();
+                        continue 'scan;
+                    }
+                }
+            }
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+        prev = Some(c);
+    }
+    out
+}
+
+fn is_path_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/' | '.' | '$' | '{' | '}')
+}
+
+/// Whether a path ends where `after` starts; a trailing `.` ends a sentence.
+fn ends_path(after: &str) -> bool {
+    let mut chars = after.chars();
+    match chars.next() {
+        None => true,
+        Some('.') => !chars.next().is_some_and(char::is_alphanumeric),
+        Some(c) => !(c.is_alphanumeric() || matches!(c, '_' | '-' | '/')),
+    }
+}
+
+/// Spellings of an installed skill's root that instructions use in place of a
+/// relative path, e.g. `$CODEX_HOME/skills/playwright/`.
+pub fn installed_root_prefixes(skill_name: &str) -> Vec<String> {
+    vec![
+        format!("$CODEX_HOME/skills/{}/", skill_name),
+        format!("${{CODEX_HOME}}/skills/{}/", skill_name),
+        format!("~/.codex/skills/{}/", skill_name),
+    ]
+}
+
+/// The path an MCP argument or env value names, split from a `--flag=` head.
+/// Flags, placeholders, scoped packages, URLs, absolute paths and paths out of
+/// the skill are not paths of the skill.
+fn server_value_path(value: &str) -> (&str, &str) {
+    let split = match value.find('=') {
+        Some(i) if value.starts_with('-') => i + 1,
+        _ => 0,
+    };
+    (&value[..split], &value[split..])
+}
+
+fn is_relative_path(path: &str) -> bool {
+    !path.is_empty()
+        && !path.starts_with(['-', '$', '/', '~', '@', '{'])
+        && !path.contains("://")
+        && !path.split('/').any(|part| part == "..")
+}
+
+fn names_bundled_file(path: &str, assets: &[Asset]) -> bool {
+    let path = path.strip_prefix("./").unwrap_or(path).trim_end_matches('/');
+    is_relative_path(path)
+        && assets
+            .iter()
+            .any(|a| a.path == path || a.path.strip_prefix(path).is_some_and(|rest| rest.starts_with('/')))
+}
+
+/// `server` with the arguments, env values and `cwd` that name a bundled file
+/// or directory resolved from `root`, e.g. `${extensionPath}/`. Anything else
+/// (`run`, `--port`, `@scope/pkg`) is left as written.
+pub fn rewrite_server_paths(server: &McpServer, assets: &[Asset], root: &str) -> McpServer {
+    let rewrite = |value: &String| {
+        let (head, path) = server_value_path(value);
+        if names_bundled_file(path, assets) {
+            format!("{}{}{}", head, root, path.strip_prefix("./").unwrap_or(path))
+        } else {
+            value.clone()
+        }
+    };
+    McpServer {
+        args: server.args.iter().map(rewrite).collect(),
+        env: server.env.iter().map(|(key, value)| (key.clone(), rewrite(value))).collect(),
+        cwd: server.cwd.as_ref().map(|cwd| match cwd.as_str() {
+            "" | "." | "./" => root.trim_end_matches('/').to_string(),
+            _ => rewrite(cwd),
+        }),
+        ..server.clone()
+    }
+}
+
+/// Undo `rewrite_server_paths` for any of `roots`.
+pub fn relative_server_paths(server: &McpServer, roots: &[&str]) -> McpServer {
+    let relative = |value: &String| {
+        let (head, path) = server_value_path(value);
+        match roots.iter().find_map(|root| path.strip_prefix(root)) {
+            Some(path) => format!("{}{}", head, path),
+            None => value.clone(),
+        }
+    };
+    McpServer {
+        args: server.args.iter().map(relative).collect(),
+        env: server.env.iter().map(|(key, value)| (key.clone(), relative(value))).collect(),
+        cwd: server.cwd.as_ref().map(|cwd| {
+            if roots.iter().any(|root| cwd == root.trim_end_matches('/')) {
+                ".".to_string()
+            } else {
+                relative(cwd)
+            }
+        }),
+        ..server.clone()
+    }
+}
+
+/// Bundle the files below `root` that MCP servers of `skill` refer to by a
+/// relative path, so they travel with the converted skill.
+pub fn bundle_server_files(skill: &mut Skill, root: &Path) -> Result<()> {
+    let mut paths = Vec::new();
+    for server in skill.mcp_servers.values() {
+        let values = server.args.iter().chain(server.env.values()).chain(server.cwd.iter());
+        for value in values {
+            let path = server_value_path(value).1;
+            let path = path.strip_prefix("./").unwrap_or(path).trim_end_matches('/');
+            if is_relative_path(path) && path != "." {
+                paths.push(path.to_string());
+            }
+        }
+    }
+
+    for path in paths {
+        let full = root.join(&path);
+        if !full.exists() || names_bundled_file(&path, &skill.assets) {
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(&full).sort_by_file_name() {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            skill.assets.push(Asset {
+                path: relative.to_string_lossy().replace('\\', "/"),
+                source: entry.path().to_path_buf(),
+            });
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_claude_to_gemini_workflow() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let output_path = temp_dir.path().join("output");
+
+        fs::create_dir_all(source_path.join("scripts")).unwrap();
+
+        // Create a mock SKILL.md
+        let skill_content = r#"---
+name: Test Skill
+description: A test skill
+subagents:
+  - name: Agent1
+    description: First agent
+---
+# Content here
+"#;
+
+        fs::write(source_path.join("SKILL.md"), skill_content).unwrap();
+        fs::write(source_path.join("scripts").join("run.sh"), "echo hi\n").unwrap();
+
+        let result = convert(Format::Claude, Format::Gemini, &source_path, &output_path)
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        assert!(output_path.join("gemini-extension.json").exists());
+        assert!(output_path.join("commands").join("Agent1.toml").exists());
+        assert!(output_path.join("scripts").join("run.sh").exists());
+        assert!(!output_path.join("shared").exists());
+        assert!(!output_path.join("docs").exists());
+    }
+
+    #[tokio::test]
+    async fn test_bundled_files_follow_the_skill() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let gemini_path = temp_dir.path().join("gemini");
+        let claude_path = temp_dir.path().join("claude");
+
+        fs::create_dir_all(source_path.join("scripts")).unwrap();
+        fs::create_dir_all(source_path.join("agents")).unwrap();
+        let skill_content = r#"---
+name: demo
+description: Demo skill
+---
+Run `scripts/run.sh`, or `$CODEX_HOME/skills/demo/scripts/run.sh` when installed.
+Never touch https://example.com/scripts/run.sh or vendor/scripts/run.sh.
+"#;
+        fs::write(source_path.join("SKILL.md"), skill_content).unwrap();
+        fs::write(source_path.join("scripts").join("run.sh"), "echo hi\n").unwrap();
+        fs::write(source_path.join("agents").join("openai.yaml"), "interface: {}\n").unwrap();
+        fs::write(source_path.join("LICENSE.txt"), "MIT\n").unwrap();
+
+        let result = convert(Format::Claude, Format::Gemini, &source_path, &gemini_path)
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.errors);
+        assert!(gemini_path.join("agents").join("openai.yaml").exists());
+        assert!(gemini_path.join("LICENSE.txt").exists());
+
+        let context = fs::read_to_string(gemini_path.join("GEMINI.md")).unwrap();
+        assert!(context.contains(
+            "Run `${extensionPath}/scripts/run.sh`, or `${extensionPath}/scripts/run.sh` when installed."
+        ));
+        assert!(context.contains("https://example.com/scripts/run.sh or vendor/scripts/run.sh."));
+
+        // Without the provenance sidecar, links come back relative to the skill root
+        fs::remove_file(gemini_path.join(PROVENANCE_FILE)).unwrap();
+        convert(Format::Gemini, Format::Claude, &gemini_path, &claude_path)
+            .await
+            .unwrap();
+        let skill_md = fs::read_to_string(claude_path.join("SKILL.md")).unwrap();
+        assert!(skill_md.contains("Run `scripts/run.sh`, or `scripts/run.sh` when installed."));
+        assert!(claude_path.join("scripts").join("run.sh").exists());
+    }
+
+    #[tokio::test]
+    async fn test_server_files_follow_the_skill() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let gemini_path = temp_dir.path().join("gemini");
+        let plugin_path = temp_dir.path().join("plugin");
+
+        fs::create_dir_all(source_path.join("server")).unwrap();
+        fs::create_dir_all(source_path.join(".claude-plugin")).unwrap();
+        fs::write(source_path.join("SKILL.md"), "---\nname: demo\ndescription: Demo skill\n---\n# Demo\n").unwrap();
+        fs::write(source_path.join("server").join("index.js"), "").unwrap();
+        let marketplace = json!({
+            "plugins": [{
+                "mcpServers": {
+                    "local": { "command": "node", "args": ["${CLAUDE_PLUGIN_ROOT}/server/index.js", "run", "--port", "8080"] },
+                    "pkg": { "command": "npx", "args": ["-y", "@scope/pkg"] }
+                }
+            }]
+        });
+        fs::write(source_path.join(".claude-plugin").join("marketplace.json"), marketplace.to_string()).unwrap();
+
+        let result = convert(Format::Claude, Format::Gemini, &source_path, &gemini_path)
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.errors);
+        assert!(gemini_path.join("server").join("index.js").exists());
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(gemini_path.join("gemini-extension.json")).unwrap()).unwrap();
+        assert_eq!(
+            manifest["mcpServers"]["local"]["args"],
+            json!(["${extensionPath}/server/index.js", "run", "--port", "8080"])
+        );
+        assert_eq!(manifest["mcpServers"]["pkg"]["args"], json!(["-y", "@scope/pkg"]));
+
+        convert(Format::Claude, Format::ClaudePlugin, &source_path, &plugin_path)
+            .await
+            .unwrap();
+        assert!(plugin_path.join("skills/demo/server/index.js").exists());
+        let mcp: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(plugin_path.join(".mcp.json")).unwrap()).unwrap();
+        assert_eq!(
+            mcp["mcpServers"]["local"]["args"][0],
+            "${CLAUDE_PLUGIN_ROOT}/skills/demo/server/index.js"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gemini_to_claude_workflow() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let output_path = temp_dir.path().join("output");
+
+        fs::create_dir_all(source_path.join("commands")).unwrap();
+
+        // Create mock gemini-extension.json
+        let manifest = json!({
+            "name": "Test Extension",
+            "version": "1.0.0",
+            "description": "A test extension",
+            "contextFileName": "GEMINI.md",
+            "excludeTools": ["Edit", "Grep"]
+        });
+
+        fs::write(
+            source_path.join("gemini-extension.json"),
+            serde_json::to_string_pretty(&manifest).unwrap(),
+        )
+        .unwrap();
+
+        // Create mock GEMINI.md
+        fs::write(
+            source_path.join("GEMINI.md"),
+            "# Test Extension - Gemini CLI Extension\n\nTest content\n",
+        )
+        .unwrap();
+
+        // Create mock command
+        let command_content = r#"description = "Test Command"
+
+prompt = """
+You are a test assistant.
+User Query: {{args}}
+"""
+"#;
+
+        fs::write(source_path.join("commands").join("test.toml"), command_content).unwrap();
+
+        let result = convert(Format::Gemini, Format::Claude, &source_path, &output_path)
+            .await
+            .unwrap();
+
+        assert!(result.success);
+
+        // Verify SKILL.md and marketplace.json were created
+        assert!(output_path.join("SKILL.md").exists());
+        assert!(output_path.join(".claude-plugin").join("marketplace.json").exists());
+
+        // Verify command was converted
+        let cmd_path = output_path.join(".claude").join("commands").join("test.md");
+        let cmd_content = fs::read_to_string(cmd_path).unwrap();
+        assert!(cmd_content.contains("$ARGUMENTS")); // Should convert {{args}} to $ARGUMENTS
+    }
+
+    #[tokio::test]
+    async fn test_claude_to_plugin_workflow() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let output_path = temp_dir.path().join("output");
+
+        fs::create_dir_all(&source_path).unwrap();
+        fs::write(
+            source_path.join("SKILL.md"),
+            "---\nname: demo\ndescription: Demo\n---\n# Demo\n",
+        )
+        .unwrap();
+
+        let result = convert(Format::Claude, Format::ClaudePlugin, &source_path, &output_path)
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        assert!(output_path.join(".claude-plugin").join("plugin.json").exists());
+        assert!(output_path.join("skills").join("demo").join("SKILL.md").exists());
+    }
+
+    /// Compare everything but where the assets were read from.
+    fn assert_equivalent(expected: &Skill, actual: &Skill, context: &str) {
+        let paths = |skill: &Skill| skill.assets.iter().map(|a| a.path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths(expected), paths(actual), "{}: assets", context);
+        assert_eq!(
+            Skill {
+                assets: Vec::new(),
+                ..expected.clone()
+            },
+            Skill {
+                assets: Vec::new(),
+                ..actual.clone()
+            },
+            "{}",
+            context
+        );
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_is_lossless_for_bundled_skills() {
+        let skills_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("skills");
+        let mut skill_dirs: Vec<_> = std::fs::read_dir(&skills_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.join("SKILL.md").is_file())
+            .collect();
+        skill_dirs.sort();
+        assert!(!skill_dirs.is_empty());
+
+        for skill_dir in &skill_dirs {
+            let mut result = ConversionResult::default();
+            let original = read(Format::Claude, skill_dir, &mut result).await.unwrap();
+
+            for via in [Format::Gemini, Format::ClaudePlugin] {
+                let temp_dir = TempDir::new().unwrap();
+                let there = temp_dir.path().join("there");
+                let back = temp_dir.path().join("back");
+
+                let forward = convert(Format::Claude, via, skill_dir, &there).await.unwrap();
+                assert!(forward.success, "{:?}", forward.errors);
+                let reverse = convert(via, Format::Claude, &there, &back).await.unwrap();
+                assert!(reverse.success, "{:?}", reverse.errors);
+
+                let round_tripped = read(Format::Claude, &back, &mut result).await.unwrap();
+                let context = format!("{} via {}", skill_dir.display(), via.as_str());
+                assert_equivalent(&original, &round_tripped, &context);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provenance_yields_to_edits() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let output_path = temp_dir.path().join("output");
+
+        fs::create_dir_all(&source_path).unwrap();
+        fs::write(
+            source_path.join("SKILL.md"),
+            "---\nname: demo\ndescription: Demo\nallowed-tools: Read, Bash(git:*)\nsubagents:\n  - name: Agent1\n---\n# Demo\n",
+        )
+        .unwrap();
+
+        convert(Format::Claude, Format::Gemini, &source_path, &output_path)
+            .await
+            .unwrap();
+        assert!(output_path.join(PROVENANCE_FILE).exists());
+
+        // Edit the description by hand; the tool list Gemini cannot express is still restored
+        let manifest_path = output_path.join("gemini-extension.json");
+        let manifest = fs::read_to_string(&manifest_path).unwrap();
+        fs::write(&manifest_path, manifest.replace("\"Demo\"", "\"Edited\"")).unwrap();
+
+        let mut result = ConversionResult::default();
+        let skill = read(Format::Gemini, &output_path, &mut result).await.unwrap();
+        assert_eq!(skill.description, "Edited");
+        assert_eq!(
+            skill.tools.allowed,
+            Some(vec!["Read".to_string(), "Bash(git:*)".to_string()])
+        );
+        assert_eq!(skill.subagents[0].name, "Agent1");
+    }
+
+    #[tokio::test]
+    async fn test_env_defaults_map_to_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let gemini_path = temp_dir.path().join("gemini");
+        let claude_path = temp_dir.path().join("claude");
+        let mut skill = Skill {
+            name: "demo".to_string(),
+            description: "Demo skill".to_string(),
+            ..Default::default()
+        };
+        skill.mcp_servers.insert(
+            "api".to_string(),
+            McpServer {
+                command: Some("node".to_string()),
+                args: vec![],
+                env: BTreeMap::from([
+                    ("PORT".to_string(), "${PORT:-8080}".to_string()),
+                    ("LAYOUT".to_string(), "${KEYBOARD_LAYOUT}".to_string()),
+                    ("KEY".to_string(), "${API_KEY}".to_string()),
+                ]),
+                ..Default::default()
+            },
+        );
+
+        fs::create_dir_all(&gemini_path).unwrap();
+        fs::create_dir_all(&claude_path).unwrap();
+        let mut result = ConversionResult::default();
+        write(Format::Gemini, &skill, &gemini_path, &mut result).await;
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(gemini_path.join("gemini-extension.json")).unwrap()).unwrap();
+        assert_eq!(manifest["mcpServers"]["api"]["env"]["PORT"], "${PORT}");
+        let settings = manifest["settings"].as_array().unwrap();
+        assert_eq!(settings.len(), 3);
+        assert_eq!(settings[0]["envVar"], "API_KEY");
+        assert_eq!(settings[0]["sensitive"], true);
+        assert!(settings[1].get("sensitive").is_none(), "{}", settings[1]);
+        assert_eq!(settings[2]["default"], "8080");
+        assert!(fs::read_to_string(gemini_path.join(".env.example")).unwrap().contains("\nPORT=8080\n"));
+
+        let mut result = ConversionResult::default();
+        let read_back = read_native(Format::Gemini, &gemini_path, &mut result).await.unwrap();
+        write(Format::Claude, &read_back, &claude_path, &mut result).await;
+        let marketplace: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(claude_path.join(".claude-plugin").join("marketplace.json")).unwrap(),
+        )
+        .unwrap();
+        let env = &marketplace["plugins"][0]["mcpServers"]["api"]["env"];
+        assert_eq!(env["PORT"], "${PORT:-8080}");
+        assert_eq!(env["KEY"], "${API_KEY}");
+    }
+
+    #[test]
+    fn test_rewrite_asset_links() {
+        let assets = vec![
+            Asset {
+                path: "references/cli.md".to_string(),
+                source: PathBuf::from("references/cli.md"),
+            },
+            Asset {
+                path: "references/cli.md.bak".to_string(),
+                source: PathBuf::from("references/cli.md.bak"),
+            },
+        ];
+        let prefixes = vec!["./".to_string()];
+        assert_eq!(
+            rewrite_asset_links("See references/cli.md. Or ./references/cli.md.bak", &assets, &prefixes, "${root}/"),
+            "See ${root}/references/cli.md. Or ${root}/references/cli.md.bak"
+        );
+        assert_eq!(
+            rewrite_asset_links("docs/references/cli.md and references/cli.mdx", &assets, &prefixes, "${root}/"),
+            "docs/references/cli.md and references/cli.mdx"
+        );
+    }
+
+    #[test]
+    fn test_command_paths() {
+        let dir = Path::new("commands");
+        let path = command_path(dir, "feature:action", "toml").unwrap();
+        assert_eq!(path, Path::new("commands/feature/action.toml"));
+        assert_eq!(command_name(dir, &path, "toml").as_deref(), Some("feature:action"));
+
+        assert!(command_path(dir, "../escape", "toml").is_none());
+        assert!(command_path(dir, "a::b", "toml").is_none());
+        assert!(command_path(dir, "a/b", "toml").is_none());
+    }
+
+    #[test]
+    fn test_detect_formats() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(detect_formats(temp_dir.path()).is_empty());
+
+        fs::write(temp_dir.path().join("gemini-extension.json"), "{}").unwrap();
+        assert_eq!(detect_formats(temp_dir.path()), vec![Format::Gemini]);
+    }
+}
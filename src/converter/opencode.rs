@@ -0,0 +1,290 @@
+use super::ir::Skill;
+use super::{
+    instructions_markdown, render_markdown, report_unmapped_mcp_options, report_unmapped_settings, settings,
+    write_assets, write_command_file, ConversionResult,
+};
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::fs;
+
+const TARGET: &str = "opencode";
+const CONFIG_SCHEMA: &str = "https://opencode.ai/config.json";
+
+/// Built-in tools with an opencode counterpart that `tools` can switch off.
+const TOOL_NAMES: &[(&str, &str)] = &[
+    ("Read", "read"),
+    ("Write", "write"),
+    ("Edit", "edit"),
+    ("Glob", "glob"),
+    ("Grep", "grep"),
+    ("Bash", "bash"),
+    ("WebFetch", "webfetch"),
+    ("TodoWrite", "todowrite"),
+];
+
+// ============================================================================
+// Models
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+struct CommandFrontmatter {
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AgentFrontmatter {
+    description: String,
+    mode: String,
+}
+
+/// `opencode.json` at the project root.
+#[derive(Debug, Clone, Serialize)]
+struct OpenCodeConfig {
+    #[serde(rename = "$schema")]
+    schema: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    mcp: BTreeMap<String, OpenCodeMcpServer>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    tools: BTreeMap<String, bool>,
+}
+
+/// A `local` server runs `command`; a `remote` one is reached at `url`.
+#[derive(Debug, Clone, Serialize)]
+struct OpenCodeMcpServer {
+    #[serde(rename = "type")]
+    server_type: String,
+    /// Command followed by its arguments
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    command: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    environment: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    headers: BTreeMap<String, String>,
+    /// Milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<u64>,
+    enabled: bool,
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+/// Write `AGENTS.md`, `.opencode/command/*.md`, `.opencode/agent/*.md` and
+/// `opencode.json`.
+pub async fn write(skill: &Skill, output: &Path, result: &mut ConversionResult) {
+    // Step 1: Generate AGENTS.md
+    match write_agents_md(skill, output).await {
+        Ok(path) => result.files.push(path),
+        Err(e) => result.errors.push(format!("Failed to generate AGENTS.md: {}", e)),
+    }
+
+    // Step 2: Generate commands and agents
+    match write_commands_and_agents(skill, output, result).await {
+        Ok(paths) => result.files.extend(paths),
+        Err(e) => result.errors.push(format!("Failed to generate commands: {}", e)),
+    }
+
+    // Step 3: Generate opencode.json
+    match write_config(skill, output, result).await {
+        Ok(Some(path)) => result.files.push(path),
+        Ok(None) => {}
+        Err(e) => result.errors.push(format!("Failed to generate opencode.json: {}", e)),
+    }
+
+    // Step 4: Copy bundled files
+    write_assets(&skill.assets, output, result).await;
+
+    report_unmapped_settings(skill, TARGET, "AGENTS.md", result);
+}
+
+async fn write_agents_md(skill: &Skill, output: &Path) -> Result<String> {
+    let path = output.join("AGENTS.md");
+    fs::write(&path, instructions_markdown(skill)).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+async fn write_commands_and_agents(skill: &Skill, output: &Path, result: &mut ConversionResult) -> Result<Vec<String>> {
+    let mut written = Vec::new();
+
+    // opencode commands share Claude's placeholder and injection syntax, so prompts carry over as-is
+    if !skill.commands.is_empty() {
+        let commands_dir = output.join(".opencode").join("command");
+        fs::create_dir_all(&commands_dir).await?;
+        for cmd in &skill.commands {
+            let frontmatter = CommandFrontmatter {
+                description: cmd.description.clone().unwrap_or_else(|| format!("Run {}", cmd.name)),
+                model: cmd.model.clone(),
+            };
+            if cmd.allowed_tools.is_some() {
+                result.warnings.push(format!(
+                    "Command {}: allowed-tools has no {} command equivalent",
+                    cmd.name, TARGET
+                ));
+            }
+            if cmd.argument_hint.is_some() {
+                result.warnings.push(format!(
+                    "Command {}: argument-hint has no {} equivalent",
+                    cmd.name, TARGET
+                ));
+            }
+            let content = render_markdown(&frontmatter, &cmd.prompt)?;
+            written.push(write_command_file(&commands_dir, &cmd.name, "md", &content).await?);
+        }
+    }
+
+    if !skill.subagents.is_empty() {
+        let agents_dir = output.join(".opencode").join("agent");
+        fs::create_dir_all(&agents_dir).await?;
+        for agent in &skill.subagents {
+            let description = agent
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("{} agent", agent.name));
+            let body = agent.prompt.clone().unwrap_or_else(|| description.clone());
+            let frontmatter = AgentFrontmatter {
+                description,
+                mode: "subagent".to_string(),
+            };
+            let path = agents_dir.join(format!("{}.md", agent.name));
+            fs::write(&path, render_markdown(&frontmatter, &body)?).await?;
+            written.push(path.to_string_lossy().to_string());
+        }
+    }
+
+    Ok(written)
+}
+
+async fn write_config(skill: &Skill, output: &Path, result: &mut ConversionResult) -> Result<Option<String>> {
+    let env_regex = Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}")?;
+    let mut config = OpenCodeConfig {
+        schema: CONFIG_SCHEMA.to_string(),
+        mcp: BTreeMap::new(),
+        tools: BTreeMap::new(),
+    };
+
+    for (name, server) in &skill.mcp_servers {
+        report_unmapped_mcp_options(name, server, TARGET, &["headers", "timeout"], result);
+        // ${VAR} -> {env:VAR}; the default is only kept in the setting
+        let server = settings::map_values(server, |value| {
+            env_regex
+                .replace_all(&settings::strip_defaults(value), "{env:$1}")
+                .to_string()
+        });
+        let server_type = if server.is_remote() { "remote" } else { "local" };
+        config.mcp.insert(
+            name.clone(),
+            OpenCodeMcpServer {
+                server_type: server_type.to_string(),
+                command: server.command.iter().chain(server.args.iter()).cloned().collect(),
+                environment: server.env,
+                url: server.url,
+                headers: server.headers,
+                timeout: server.timeout,
+                enabled: true,
+            },
+        );
+    }
+
+    // Excluded tools are switched off project-wide where opencode has a counterpart
+    let mut unmapped_tools = Vec::new();
+    for tool in skill.tools.excluded() {
+        match TOOL_NAMES.iter().find(|(claude, _)| *claude == tool) {
+            Some((_, opencode)) => {
+                config.tools.insert(opencode.to_string(), false);
+            }
+            None => unmapped_tools.push(tool),
+        }
+    }
+    if !unmapped_tools.is_empty() {
+        result.unmapped.push(format!(
+            "Tool permissions (excluded: {}): {} has no matching tools",
+            unmapped_tools.join(", "),
+            TARGET
+        ));
+    }
+
+    if config.mcp.is_empty() && config.tools.is_empty() {
+        return Ok(None);
+    }
+
+    let path = output.join("opencode.json");
+    fs::write(&path, serde_json::to_string_pretty(&config)?).await?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::ir::{McpServer, McpTransport, Subagent, ToolPermissions};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_opencode_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut skill = Skill {
+            name: "demo".to_string(),
+            description: "Demo skill".to_string(),
+            instructions: "Do the thing.\n".to_string(),
+            subagents: vec![Subagent {
+                name: "reviewer".to_string(),
+                description: Some("Reviews code".to_string()),
+                prompt: None,
+            }],
+            tools: ToolPermissions::from_excluded(&["Edit".to_string(), "Task".to_string()]),
+            ..Default::default()
+        };
+        skill.mcp_servers.insert(
+            "db".to_string(),
+            McpServer {
+                command: Some("node".to_string()),
+                args: vec!["server.js".to_string()],
+                env: BTreeMap::from([("PASS".to_string(), "${DB_PASSWORD}".to_string())]),
+                trust: Some(true),
+                ..Default::default()
+            },
+        );
+        skill.mcp_servers.insert(
+            "search".to_string(),
+            McpServer {
+                url: Some("https://search.example.com/mcp".to_string()),
+                transport: McpTransport::Http,
+                headers: BTreeMap::from([("X-Api-Key".to_string(), "${SEARCH_API_KEY}".to_string())]),
+                timeout: Some(10000),
+                ..Default::default()
+            },
+        );
+
+        let mut result = ConversionResult::default();
+        write(&skill, temp_dir.path(), &mut result).await;
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let agent = std::fs::read_to_string(temp_dir.path().join(".opencode/agent/reviewer.md")).unwrap();
+        assert_eq!(agent, "---\ndescription: Reviews code\nmode: subagent\n---\n\nReviews code\n");
+
+        let config: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(temp_dir.path().join("opencode.json")).unwrap()).unwrap();
+        assert_eq!(config["mcp"]["db"]["command"], serde_json::json!(["node", "server.js"]));
+        assert_eq!(config["mcp"]["db"]["environment"]["PASS"], "{env:DB_PASSWORD}");
+        assert_eq!(config["mcp"]["search"]["type"], "remote");
+        assert_eq!(config["mcp"]["search"]["headers"]["X-Api-Key"], "{env:SEARCH_API_KEY}");
+        assert_eq!(config["mcp"]["search"]["timeout"], 10000);
+        assert!(config["mcp"]["search"].get("command").is_none());
+        assert_eq!(config["tools"], serde_json::json!({"edit": false}));
+
+        assert_eq!(result.unmapped.len(), 2, "{:?}", result.unmapped);
+        assert_eq!(result.unmapped[0], "MCP server db (trust): opencode has no such server options");
+        assert!(result.unmapped[1].contains("excluded: Task"));
+    }
+}
@@ -0,0 +1,487 @@
+// ============================================================================
+// Command Body Syntax
+// ============================================================================
+//
+// IR prompts use Claude's syntax. Each format lexes its own syntax into
+// tokens and renders tokens back, so a construct is either translated or
+// reported; it never passes through as text another agent would interpret
+// differently.
+//
+//   construct          Claude          Gemini          Copilot
+//   all arguments      $ARGUMENTS      {{args}}        ${input:args}
+//   one argument       $1              [argument 1]*   ${input:arg1}
+//   shell output       !`cmd`          !{cmd}          -
+//   file contents      @path           @{path}         #file:path
+//
+// * Gemini only substitutes the whole argument string, so a body using
+//   positional arguments starts with POSITIONAL_PREAMBLE, which hands the
+//   model {{args}} and tells it how to split them.
+
+/// A piece of a command body.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Text(String),
+    /// Everything the user typed after the command
+    AllArgs,
+    /// One positional argument, counted from 1
+    Arg(u32),
+    /// Output of a shell command; only argument placeholders nest inside
+    Shell(Vec<Token>),
+    /// Contents of a file, relative to the working directory
+    File(String),
+}
+
+fn push_text(tokens: &mut Vec<Token>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match tokens.last_mut() {
+        Some(Token::Text(existing)) => existing.push_str(text),
+        _ => tokens.push(Token::Text(text.to_string())),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Claude
+// ----------------------------------------------------------------------------
+
+/// Lex a Claude command body. `@` starts a file reference only at the start of
+/// a word and when the path has a `/` or `.`, so `@octocat` stays text.
+pub fn lex_claude(body: &str) -> Vec<Token> {
+    lex_claude_inner(body, true)
+}
+
+fn lex_claude_inner(body: &str, injections: bool) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = body;
+    let mut at_word_start = true;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("$ARGUMENTS") {
+            tokens.push(Token::AllArgs);
+            rest = after;
+            at_word_start = false;
+            continue;
+        }
+        if c == '$' {
+            let digits: String = rest[1..].chars().take_while(|d| d.is_ascii_digit()).collect();
+            if let Some(n) = digits.parse::<u32>().ok().filter(|n| *n > 0) {
+                tokens.push(Token::Arg(n));
+                rest = &rest[1 + digits.len()..];
+                at_word_start = false;
+                continue;
+            }
+        }
+        if injections {
+            if let Some(after) = rest.strip_prefix("!`") {
+                if let Some(end) = after.find('`') {
+                    tokens.push(Token::Shell(lex_claude_inner(&after[..end], false)));
+                    rest = &after[end + 1..];
+                    at_word_start = false;
+                    continue;
+                }
+            }
+            if c == '@' && at_word_start {
+                let word: &str = rest[1..].split(char::is_whitespace).next().unwrap_or("");
+                let path = word.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '"', '\'']);
+                if path.contains(['/', '.']) {
+                    tokens.push(Token::File(path.to_string()));
+                    rest = &rest[1 + path.len()..];
+                    at_word_start = false;
+                    continue;
+                }
+            }
+        }
+
+        push_text(&mut tokens, &rest[..c.len_utf8()]);
+        rest = &rest[c.len_utf8()..];
+        at_word_start = c.is_whitespace();
+    }
+
+    tokens
+}
+
+/// Render tokens in Claude syntax.
+pub fn render_claude(tokens: &[Token], warnings: &mut Vec<String>) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        match token {
+            Token::Text(text) => {
+                if text.contains("$ARGUMENTS") || text.contains("!`") || has_positional(text) {
+                    warn(warnings, "text contains Claude placeholder syntax that Claude will substitute");
+                }
+                out.push_str(text);
+            }
+            Token::AllArgs => out.push_str("$ARGUMENTS"),
+            Token::Arg(n) => out.push_str(&format!("${}", n)),
+            Token::Shell(inner) => {
+                let command = render_claude(inner, warnings);
+                if command.contains('`') {
+                    warn(warnings, "shell command contains a backtick, which Claude cannot quote");
+                }
+                out.push_str(&format!("!`{}`", command));
+            }
+            Token::File(path) => {
+                if path.contains(char::is_whitespace) {
+                    warn(warnings, &format!("file reference @{} contains whitespace", path));
+                }
+                out.push_str(&format!("@{}", path));
+            }
+        }
+    }
+    out
+}
+
+fn has_positional(text: &str) -> bool {
+    text.match_indices('$')
+        .any(|(i, _)| text[i + 1..].chars().next().is_some_and(|c| c.is_ascii_digit() && c != '0'))
+}
+
+// ----------------------------------------------------------------------------
+// Gemini
+// ----------------------------------------------------------------------------
+
+/// Opens a Gemini body whose text refers to `[argument N]`.
+pub const POSITIONAL_PREAMBLE: &str = "Arguments: {{args}}\n\
+    Split the arguments above on whitespace, keeping quoted text together; \
+    [argument 1] below means the first one, [argument 2] the second, and so on.\n\n";
+
+/// Lex a Gemini command body. `[argument N]` is a positional argument only
+/// after [`POSITIONAL_PREAMBLE`].
+pub fn lex_gemini(body: &str) -> Vec<Token> {
+    match body.strip_prefix(POSITIONAL_PREAMBLE) {
+        Some(rest) => lex_gemini_inner(rest, true, true),
+        None => lex_gemini_inner(body, true, false),
+    }
+}
+
+fn lex_gemini_inner(body: &str, injections: bool, positional: bool) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = body;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("{{args}}") {
+            tokens.push(Token::AllArgs);
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("[argument ").filter(|_| positional) {
+            let digits: String = after.chars().take_while(|d| d.is_ascii_digit()).collect();
+            let n = digits.parse::<u32>().ok().filter(|n| *n > 0);
+            if let (Some(n), Some(after)) = (n, after[digits.len()..].strip_prefix(']')) {
+                tokens.push(Token::Arg(n));
+                rest = after;
+                continue;
+            }
+        }
+        if injections {
+            if let Some(after) = rest.strip_prefix("!{") {
+                if let Some(end) = closing_brace(after) {
+                    tokens.push(Token::Shell(lex_gemini_inner(&after[..end], false, false)));
+                    rest = &after[end + 1..];
+                    continue;
+                }
+            }
+            if let Some(after) = rest.strip_prefix("@{") {
+                if let Some(end) = closing_brace(after) {
+                    tokens.push(Token::File(after[..end].to_string()));
+                    rest = &after[end + 1..];
+                    continue;
+                }
+            }
+        }
+
+        push_text(&mut tokens, &rest[..c.len_utf8()]);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    tokens
+}
+
+/// Index of the `}` closing an injection whose `{` was just consumed.
+fn closing_brace(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Render tokens in Gemini syntax. Positional arguments become `[argument N]`
+/// under [`POSITIONAL_PREAMBLE`]; inside shell commands they collapse to `{{args}}`.
+pub fn render_gemini(tokens: &[Token], warnings: &mut Vec<String>) -> String {
+    let mut out = String::new();
+    if tokens.iter().any(|token| matches!(token, Token::Arg(_))) {
+        out.push_str(POSITIONAL_PREAMBLE);
+    }
+    out.push_str(&render_gemini_inner(tokens, warnings));
+    out
+}
+
+fn render_gemini_inner(tokens: &[Token], warnings: &mut Vec<String>) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        match token {
+            Token::Text(text) => {
+                if text.contains("{{args}}") || text.contains("!{") || text.contains("@{") {
+                    warn(warnings, "text contains Gemini injection syntax that Gemini will expand");
+                }
+                out.push_str(text);
+            }
+            Token::AllArgs => out.push_str("{{args}}"),
+            Token::Arg(n) => out.push_str(&format!("[argument {}]", n)),
+            Token::Shell(inner) => {
+                let command = render_shell_gemini(inner, warnings);
+                if closing_brace(&format!("{}}}", command)) != Some(command.len()) {
+                    warn(warnings, "shell command has unbalanced braces, which Gemini cannot parse");
+                }
+                out.push_str(&format!("!{{{}}}", command));
+            }
+            Token::File(path) => out.push_str(&format!("@{{{}}}", path)),
+        }
+    }
+    out
+}
+
+/// A shell command runs before the model sees it, so only `{{args}}` works there.
+fn render_shell_gemini(tokens: &[Token], warnings: &mut Vec<String>) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        match token {
+            Token::Arg(n) => {
+                warn(
+                    warnings,
+                    &format!(
+                        "positional argument ${} in a shell command has no Gemini equivalent; replaced with {{{{args}}}}",
+                        n
+                    ),
+                );
+                out.push_str("{{args}}");
+            }
+            other => out.push_str(&render_gemini_inner(std::slice::from_ref(other), warnings)),
+        }
+    }
+    out
+}
+
+// ----------------------------------------------------------------------------
+// Copilot
+// ----------------------------------------------------------------------------
+
+/// Lex a Copilot prompt file body. Only the inputs [`render_copilot`] writes
+/// are arguments; other `${...}` variables stay text.
+pub fn lex_copilot(body: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = body;
+    let mut at_word_start = true;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("${input:args}") {
+            tokens.push(Token::AllArgs);
+            rest = after;
+            at_word_start = false;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("${input:arg") {
+            let digits: String = after.chars().take_while(|d| d.is_ascii_digit()).collect();
+            let n = digits.parse::<u32>().ok().filter(|n| *n > 0);
+            if let (Some(n), Some(after)) = (n, after[digits.len()..].strip_prefix('}')) {
+                tokens.push(Token::Arg(n));
+                rest = after;
+                at_word_start = false;
+                continue;
+            }
+        }
+        if let Some(after) = rest.strip_prefix("#file:").filter(|_| at_word_start) {
+            let word: &str = after.split(char::is_whitespace).next().unwrap_or("");
+            let path = word.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '"', '\'']);
+            if !path.is_empty() {
+                tokens.push(Token::File(path.to_string()));
+                rest = &after[path.len()..];
+                at_word_start = false;
+                continue;
+            }
+        }
+
+        push_text(&mut tokens, &rest[..c.len_utf8()]);
+        rest = &rest[c.len_utf8()..];
+        at_word_start = c.is_whitespace();
+    }
+
+    tokens
+}
+
+/// Render tokens as a Copilot prompt file body.
+pub fn render_copilot(tokens: &[Token], warnings: &mut Vec<String>) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        match token {
+            Token::Text(text) => out.push_str(text),
+            Token::AllArgs => out.push_str("${input:args}"),
+            Token::Arg(n) => out.push_str(&format!("${{input:arg{}}}", n)),
+            Token::Shell(inner) => {
+                warn(warnings, "shell injection has no Copilot equivalent; kept as text");
+                out.push_str(&format!("!`{}`", render_copilot(inner, warnings)));
+            }
+            Token::File(path) => out.push_str(&format!("#file:{}", path)),
+        }
+    }
+    out
+}
+
+/// Warnings for constructs a format without injections (e.g. Codex prompts)
+/// would leave as literal text.
+pub fn unsupported_injections(tokens: &[Token], target: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for token in tokens {
+        match token {
+            Token::Shell(_) => warn(
+                &mut warnings,
+                &format!("shell injection has no {} equivalent; kept as text", target),
+            ),
+            Token::File(path) => warn(
+                &mut warnings,
+                &format!("file reference @{} has no {} equivalent; kept as text", path, target),
+            ),
+            _ => {}
+        }
+    }
+    warnings
+}
+
+fn warn(warnings: &mut Vec<String>, message: &str) {
+    if !warnings.iter().any(|w| w == message) {
+        warnings.push(message.to_string());
+    }
+}
+
+/// Translate a Claude body to Gemini.
+pub fn claude_to_gemini(body: &str) -> (String, Vec<String>) {
+    let mut warnings = Vec::new();
+    let rendered = render_gemini(&lex_claude(body), &mut warnings);
+    (rendered, warnings)
+}
+
+/// Translate a Gemini body to Claude.
+pub fn gemini_to_claude(body: &str) -> (String, Vec<String>) {
+    let mut warnings = Vec::new();
+    let rendered = render_claude(&lex_gemini(body), &mut warnings);
+    (rendered, warnings)
+}
+
+/// Translate a Claude body to Copilot.
+pub fn claude_to_copilot(body: &str) -> (String, Vec<String>) {
+    let mut warnings = Vec::new();
+    let rendered = render_copilot(&lex_claude(body), &mut warnings);
+    (rendered, warnings)
+}
+
+/// Translate a Copilot body to Claude.
+pub fn copilot_to_claude(body: &str) -> (String, Vec<String>) {
+    let mut warnings = Vec::new();
+    let rendered = render_claude(&lex_copilot(body), &mut warnings);
+    (rendered, warnings)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lex_claude() {
+        assert_eq!(
+            lex_claude("Fix $1 in @src/main.rs: !`git diff $ARGUMENTS` (mail me@example.com, ping @octocat)"),
+            vec![
+                Token::Text("Fix ".to_string()),
+                Token::Arg(1),
+                Token::Text(" in ".to_string()),
+                Token::File("src/main.rs".to_string()),
+                Token::Text(": ".to_string()),
+                Token::Shell(vec![Token::Text("git diff ".to_string()), Token::AllArgs]),
+                Token::Text(" (mail me@example.com, ping @octocat)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_claude_to_gemini() {
+        let (body, warnings) = claude_to_gemini("Review @README.md with !`git log -n $ARGUMENTS`");
+        assert_eq!(body, "Review @{README.md} with !{git log -n {{args}}}");
+        assert!(warnings.is_empty());
+
+        let (body, warnings) = claude_to_gemini("Count !`wc -l $1`");
+        assert_eq!(body, "Count !{wc -l {{args}}}");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_positional_args_keep_their_meaning() {
+        let body = "Compare $1 and $2 using $ARGUMENTS, then $1 again; costs $0";
+        let (gemini, warnings) = claude_to_gemini(body);
+        assert!(warnings.is_empty());
+        assert_eq!(
+            gemini,
+            format!(
+                "{}Compare [argument 1] and [argument 2] using {{{{args}}}}, then [argument 1] again; costs $0",
+                POSITIONAL_PREAMBLE
+            )
+        );
+        assert_eq!(gemini.matches("{{args}}").count(), 2);
+
+        let (claude, warnings) = gemini_to_claude(&gemini);
+        assert_eq!(claude, body);
+        assert!(warnings.is_empty());
+
+        // Without the preamble the marker is ordinary text
+        assert_eq!(
+            lex_gemini("See [argument 1]"),
+            vec![Token::Text("See [argument 1]".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_gemini_to_claude() {
+        let (body, warnings) = gemini_to_claude("Run !{echo {{args}} | wc -c} on @{docs/a b.md}");
+        assert_eq!(body, "Run !`echo $ARGUMENTS | wc -c` on @docs/a b.md");
+        assert_eq!(warnings, vec!["file reference @docs/a b.md contains whitespace".to_string()]);
+
+        let (_, warnings) = gemini_to_claude("Costs $5");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_copilot_round_trip() {
+        let body = "Compare $1 with $ARGUMENTS in @src/lib.rs, see #42";
+        let (copilot, warnings) = claude_to_copilot(body);
+        assert_eq!(copilot, "Compare ${input:arg1} with ${input:args} in #file:src/lib.rs, see #42");
+        assert!(warnings.is_empty());
+
+        let (claude, warnings) = copilot_to_claude(&copilot);
+        assert_eq!(claude, body);
+        assert!(warnings.is_empty());
+
+        // Inputs the writer never produces are left alone
+        assert_eq!(
+            lex_copilot("Use ${input:topic} and ${selection}"),
+            vec![Token::Text("Use ${input:topic} and ${selection}".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_round_trip_without_positionals() {
+        let body = "Summarize @notes/today.md then !`date` for $ARGUMENTS";
+        let (gemini, _) = claude_to_gemini(body);
+        let (claude, warnings) = gemini_to_claude(&gemini);
+        assert_eq!(claude, body);
+        assert!(warnings.is_empty());
+    }
+}
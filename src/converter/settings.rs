@@ -0,0 +1,499 @@
+// ============================================================================
+// Settings and Environment Placeholders
+// ============================================================================
+//
+// IR MCP servers use Claude's placeholder syntax in `env` and `args`:
+// `${VAR}`, or `${VAR:-default}` when the variable may be unset. Every
+// variable referenced there is a setting.
+//
+//   rule             Claude .mcp.json     IR                   Gemini manifest
+//   reference        ${VAR}               ${VAR}               $VAR or ${VAR}; written ${VAR}
+//   default          ${VAR:-value}        Setting::default     settings[].default
+//   secret           by name (see below)  Setting::secret      settings[].sensitive
+//   variable name    VAR                  Setting::name        settings[].envVar
+//
+// A variable is secret when one of its `_`-separated words is in SECRET_WORDS
+// or its last word is KEY, unless the last word says where a value lives
+// rather than what it is (SSH_KEY_PATH, TOKEN_URL). KEYBOARD_LAYOUT is not a
+// secret: no word of it matches.
+
+use super::ir::{McpServer, Setting, Skill};
+use super::{ConversionResult, Format};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::OnceLock;
+use tokio::fs;
+
+pub const ENV_EXAMPLE_FILE: &str = ".env.example";
+const ENV_EXAMPLE_HEADER: &str = "# Generated by bl convert: copy to .env and fill in the values.";
+
+const SECRET_WORDS: &[&str] = &[
+    "PASSWORD",
+    "PASSWD",
+    "PASSPHRASE",
+    "SECRET",
+    "TOKEN",
+    "CREDENTIAL",
+    "CREDENTIALS",
+    "APIKEY",
+    "PAT",
+];
+
+/// Last words naming where a value lives or what it identifies.
+const LOCATOR_WORDS: &[&str] = &[
+    "PATH", "FILE", "DIR", "URL", "URI", "HOST", "PORT", "NAME", "USER", "USERNAME", "ID", "REGION", "ENDPOINT",
+];
+
+/// Variables Gemini substitutes itself rather than reading from the environment.
+const GEMINI_BUILTINS: &[&str] = &["extensionPath", "workspacePath", "pathSeparator", "/"];
+
+fn placeholder_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(?::-([^}]*))?\}").unwrap())
+}
+
+fn bare_variable_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\$([A-Za-z_][A-Za-z0-9_]*)").unwrap())
+}
+
+/// A variable an MCP value reads, with the default it falls back to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvReference {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+/// The `${VAR}` and `${VAR:-default}` placeholders in `value`.
+pub fn env_references(value: &str) -> Vec<EnvReference> {
+    placeholder_regex()
+        .captures_iter(value)
+        .map(|caps| EnvReference {
+            name: caps[1].to_string(),
+            default: caps.get(2).map(|d| d.as_str().to_string()),
+        })
+        .collect()
+}
+
+pub fn is_secret_name(name: &str) -> bool {
+    let upper = name.to_uppercase();
+    let words: Vec<&str> = upper.split('_').filter(|w| !w.is_empty()).collect();
+    let Some(last) = words.last() else {
+        return false;
+    };
+    if LOCATOR_WORDS.contains(last) {
+        return false;
+    }
+    *last == "KEY" || words.iter().any(|word| SECRET_WORDS.contains(word))
+}
+
+// ----------------------------------------------------------------------------
+// Inference
+// ----------------------------------------------------------------------------
+
+/// Derive settings from the placeholders in MCP server environments and
+/// arguments, for formats that have no explicit settings of their own.
+pub fn infer_settings(mcp_servers: &BTreeMap<String, McpServer>) -> Vec<Setting> {
+    let mut settings = Vec::new();
+    add_missing_settings(&mut settings, mcp_servers);
+    settings
+}
+
+/// Add a setting for every referenced variable `settings` does not declare.
+pub fn add_missing_settings(settings: &mut Vec<Setting>, mcp_servers: &BTreeMap<String, McpServer>) {
+    let mut seen: BTreeSet<String> = settings.iter().map(|s| s.name.clone()).collect();
+
+    for server in mcp_servers.values() {
+        for value in server_values(server) {
+            for reference in env_references(value) {
+                if !seen.insert(reference.name.clone()) {
+                    continue;
+                }
+                let secret = is_secret_name(&reference.name);
+                let default = match reference.default {
+                    // A default spelled out next to a secret is still a secret
+                    Some(default) if !secret => Some(default),
+                    Some(_) => None,
+                    None if !secret => infer_default(&reference.name),
+                    None => None,
+                };
+                settings.push(Setting {
+                    description: infer_description(&reference.name),
+                    required: secret,
+                    name: reference.name,
+                    default,
+                    secret,
+                });
+            }
+        }
+    }
+}
+
+pub fn infer_description(var_name: &str) -> String {
+    let descriptions = [
+        ("DB_HOST", "Database server hostname"),
+        ("DB_PORT", "Database server port"),
+        ("DB_NAME", "Database name"),
+        ("DB_USER", "Database username"),
+        ("DB_PASSWORD", "Database password"),
+        ("API_KEY", "API authentication key"),
+        ("API_SECRET", "API secret"),
+        ("API_URL", "API endpoint URL"),
+        ("HOST", "Server hostname"),
+        ("PORT", "Server port"),
+    ];
+
+    for (key, desc) in &descriptions {
+        if *key == var_name {
+            return desc.to_string();
+        }
+    }
+
+    var_name
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(first) => first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn infer_default(var_name: &str) -> Option<String> {
+    match var_name {
+        "DB_HOST" => Some("localhost".to_string()),
+        "DB_PORT" => Some("5432".to_string()),
+        "HOST" => Some("localhost".to_string()),
+        "PORT" => Some("8080".to_string()),
+        "API_URL" => Some("https://api.example.com".to_string()),
+        _ => None,
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Placeholder Syntax
+// ----------------------------------------------------------------------------
+
+/// Gemini expands `$VAR` as well as `${VAR}`; the IR only knows the latter.
+/// Only the manifest's declared settings are rewritten, so a literal `$` in an
+/// argument or one of Gemini's own variables stays as it was.
+pub fn from_gemini_value(value: &str, settings: &[Setting]) -> String {
+    bare_variable_regex()
+        .replace_all(value, |caps: &regex::Captures| {
+            let name = &caps[1];
+            if !GEMINI_BUILTINS.contains(&name) && settings.iter().any(|s| s.name == name) {
+                format!("${{{}}}", name)
+            } else {
+                caps[0].to_string()
+            }
+        })
+        .to_string()
+}
+
+/// Drop `:-default` from placeholders, for formats that cannot express it;
+/// the default lives on in the setting.
+pub fn strip_defaults(value: &str) -> String {
+    placeholder_regex().replace_all(value, "$${$1}").to_string()
+}
+
+/// Spell out the defaults of non-secret settings, which Claude has no other
+/// place for.
+pub fn with_defaults(value: &str, settings: &[Setting]) -> String {
+    placeholder_regex()
+        .replace_all(value, |caps: &regex::Captures| {
+            let name = &caps[1];
+            let default = caps.get(2).map(|d| d.as_str().to_string()).or_else(|| {
+                settings
+                    .iter()
+                    .find(|s| s.name == name && !s.secret)
+                    .and_then(|s| s.default.clone())
+            });
+            match default {
+                Some(default) => format!("${{{}:-{}}}", name, default),
+                None => format!("${{{}}}", name),
+            }
+        })
+        .to_string()
+}
+
+/// `server` with `f` applied to every env value, argument, header and URL.
+pub fn map_values(server: &McpServer, f: impl Fn(&str) -> String) -> McpServer {
+    McpServer {
+        args: server.args.iter().map(|arg| f(arg)).collect(),
+        env: server.env.iter().map(|(key, value)| (key.clone(), f(value))).collect(),
+        url: server.url.as_deref().map(&f),
+        headers: server.headers.iter().map(|(key, value)| (key.clone(), f(value))).collect(),
+        ..server.clone()
+    }
+}
+
+/// Every value of `server` a placeholder may appear in.
+fn server_values(server: &McpServer) -> impl Iterator<Item = &String> {
+    server
+        .env
+        .values()
+        .chain(server.args.iter())
+        .chain(server.url.iter())
+        .chain(server.headers.values())
+}
+
+// ----------------------------------------------------------------------------
+// Reporting
+// ----------------------------------------------------------------------------
+
+/// The file a format writes MCP servers and settings to.
+fn manifest_file(format: Format) -> &'static str {
+    match format {
+        Format::Claude => ".claude-plugin/marketplace.json",
+        Format::ClaudePlugin => ".claude-plugin/plugin.json",
+        Format::Gemini => "gemini-extension.json",
+        Format::Codex => ".codex/config.toml",
+        Format::Cursor => ".cursor/mcp.json",
+        Format::Copilot => ".vscode/mcp.json",
+        Format::OpenCode => "opencode.json",
+    }
+}
+
+/// Warn about secret values the conversion writes into a manifest verbatim.
+pub fn report_literal_secrets(skill: &Skill, format: Format, result: &mut ConversionResult) {
+    let file = manifest_file(format);
+    for (name, server) in &skill.mcp_servers {
+        for (key, value) in &server.env {
+            if !env_references(value).is_empty() || value.is_empty() {
+                continue;
+            }
+            let kind = crate::hygiene::find_token(value).or_else(|| is_secret_name(key).then_some("secret"));
+            if let Some(kind) = kind {
+                result.warnings.push(format!(
+                    "MCP server {}: env {} holds a literal {} that is written into {}; use ${{{}}} instead",
+                    name, key, kind, file, key
+                ));
+            }
+        }
+        for (key, value) in &server.headers {
+            if !env_references(value).is_empty() || value.is_empty() {
+                continue;
+            }
+            let secret_header = key.eq_ignore_ascii_case("authorization") || is_secret_name(&key.replace('-', "_"));
+            let kind = crate::hygiene::find_token(value).or_else(|| secret_header.then_some("secret"));
+            if let Some(kind) = kind {
+                result.warnings.push(format!(
+                    "MCP server {}: header {} holds a literal {} that is written into {}",
+                    name, key, kind, file
+                ));
+            }
+        }
+        for arg in server.args.iter().chain(server.url.iter()) {
+            if let Some(kind) = crate::hygiene::find_token(arg) {
+                result.warnings.push(format!(
+                    "MCP server {}: an argument holds a literal {} that is written into {}",
+                    name, kind, file
+                ));
+            }
+        }
+    }
+
+    if format == Format::Gemini {
+        for setting in skill.settings.iter().filter(|s| s.secret && s.default.is_some()) {
+            result.warnings.push(format!(
+                "Setting {}: the default of a secret is written into {}",
+                setting.name, file
+            ));
+        }
+    }
+}
+
+/// `.env.example` listing every setting; secrets are left blank.
+pub fn env_example(settings: &[Setting]) -> String {
+    let mut content = format!("{}\n", ENV_EXAMPLE_HEADER);
+    for setting in settings {
+        let mut notes = Vec::new();
+        if setting.required {
+            notes.push("required");
+        }
+        if setting.secret {
+            notes.push("secret");
+        }
+        content.push('\n');
+        if notes.is_empty() {
+            content.push_str(&format!("# {}\n", setting.description));
+        } else {
+            content.push_str(&format!("# {} ({})\n", setting.description, notes.join(", ")));
+        }
+        let value = if setting.secret {
+            String::new()
+        } else {
+            setting.default.clone().unwrap_or_default()
+        };
+        content.push_str(&format!("{}={}\n", setting.name, value));
+    }
+    content
+}
+
+/// Write `.env.example` for the skill's settings and any variable its MCP
+/// servers reference. A file the user wrote is left alone.
+pub async fn write_env_example(skill: &Skill, output: &Path, result: &mut ConversionResult) {
+    let mut settings = skill.settings.clone();
+    add_missing_settings(&mut settings, &skill.mcp_servers);
+    if settings.is_empty() {
+        return;
+    }
+    let path = output.join(ENV_EXAMPLE_FILE);
+    if let Ok(existing) = fs::read_to_string(&path).await {
+        if !existing.starts_with(ENV_EXAMPLE_HEADER) {
+            result
+                .warnings
+                .push(format!("{} was not generated by bl; left unchanged", ENV_EXAMPLE_FILE));
+            return;
+        }
+    }
+    match fs::write(&path, env_example(&settings)).await {
+        Ok(()) => result.files.push(path.to_string_lossy().to_string()),
+        Err(e) => result.errors.push(format!("Failed to write {}: {}", ENV_EXAMPLE_FILE, e)),
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_description() {
+        assert_eq!(infer_description("DB_HOST"), "Database server hostname");
+        assert_eq!(infer_description("API_KEY"), "API authentication key");
+        assert_eq!(infer_description("CUSTOM_VAR"), "Custom Var");
+    }
+
+    #[test]
+    fn test_infer_defaults() {
+        assert_eq!(infer_default("DB_HOST"), Some("localhost".to_string()));
+        assert_eq!(infer_default("PORT"), Some("8080".to_string()));
+        assert_eq!(infer_default("UNKNOWN"), None);
+    }
+
+    #[test]
+    fn test_is_secret_name() {
+        for name in ["API_KEY", "OPENAI_API_KEY", "DB_PASSWORD", "GITHUB_TOKEN", "CLIENT_SECRET", "KEY"] {
+            assert!(is_secret_name(name), "{}", name);
+        }
+        for name in ["KEYBOARD_LAYOUT", "MONKEY_MODE", "SSH_KEY_PATH", "TOKEN_URL", "DB_HOST", "PRIMARY_KEY_COLUMN"] {
+            assert!(!is_secret_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_infer_settings_marks_secrets() {
+        let mut servers = BTreeMap::new();
+        servers.insert(
+            "db".to_string(),
+            McpServer {
+                command: Some("node".to_string()),
+                args: vec!["--layout=${KEYBOARD_LAYOUT:-us}".to_string()],
+                env: BTreeMap::from([
+                    ("HOST".to_string(), "${DB_HOST}".to_string()),
+                    ("PASS".to_string(), "${DB_PASSWORD:-hunter2}".to_string()),
+                    ("URL".to_string(), "postgres://${DB_USER}@${DB_HOST}".to_string()),
+                ]),
+                ..Default::default()
+            },
+        );
+
+        let settings = infer_settings(&servers);
+        let names: Vec<&str> = settings.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["DB_HOST", "DB_PASSWORD", "DB_USER", "KEYBOARD_LAYOUT"]);
+
+        let password = &settings[1];
+        assert!(password.secret && password.required);
+        assert_eq!(password.default, None);
+        assert_eq!(settings[0].default.as_deref(), Some("localhost"));
+        assert!(!settings[3].secret);
+        assert_eq!(settings[3].default.as_deref(), Some("us"));
+    }
+
+    #[test]
+    fn test_placeholder_syntax() {
+        let declared = vec![Setting {
+            name: "TOKEN".to_string(),
+            ..Default::default()
+        }];
+        assert_eq!(
+            from_gemini_value("$TOKEN and ${HOST}, not $HOME or $extensionPath or ${extensionPath}", &declared),
+            "${TOKEN} and ${HOST}, not $HOME or $extensionPath or ${extensionPath}"
+        );
+        assert_eq!(strip_defaults("${PORT:-8080}/${HOST}"), "${PORT}/${HOST}");
+
+        let settings = vec![
+            Setting {
+                name: "PORT".to_string(),
+                default: Some("8080".to_string()),
+                ..Default::default()
+            },
+            Setting {
+                name: "API_KEY".to_string(),
+                default: Some("dev".to_string()),
+                secret: true,
+                ..Default::default()
+            },
+        ];
+        assert_eq!(with_defaults("${PORT} ${API_KEY} ${HOST:-x}", &settings), "${PORT:-8080} ${API_KEY} ${HOST:-x}");
+    }
+
+    #[test]
+    fn test_report_literal_secrets() {
+        let mut skill = Skill::default();
+        skill.mcp_servers.insert(
+            "api".to_string(),
+            McpServer {
+                command: Some("node".to_string()),
+                args: vec![],
+                env: BTreeMap::from([
+                    ("API_KEY".to_string(), "abc123".to_string()),
+                    ("KEYBOARD_LAYOUT".to_string(), "dvorak".to_string()),
+                    ("GITHUB".to_string(), format!("ghp_{}", "a".repeat(36))),
+                    ("TOKEN".to_string(), "${TOKEN}".to_string()),
+                ]),
+                ..Default::default()
+            },
+        );
+
+        let mut result = ConversionResult::default();
+        report_literal_secrets(&skill, Format::Gemini, &mut result);
+        assert_eq!(result.warnings.len(), 2, "{:?}", result.warnings);
+        assert!(result.warnings[0].contains("env API_KEY holds a literal secret"));
+        assert!(result.warnings[1].contains("env GITHUB holds a literal GitHub token"));
+    }
+
+    #[test]
+    fn test_env_example() {
+        let settings = vec![
+            Setting {
+                name: "API_KEY".to_string(),
+                description: "API authentication key".to_string(),
+                default: Some("leaked".to_string()),
+                secret: true,
+                required: true,
+            },
+            Setting {
+                name: "PORT".to_string(),
+                description: "Server port".to_string(),
+                default: Some("8080".to_string()),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            env_example(&settings),
+            format!(
+                "{}\n\n# API authentication key (required, secret)\nAPI_KEY=\n\n# Server port\nPORT=8080\n",
+                ENV_EXAMPLE_HEADER
+            )
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Per-launch bearer token for the local HTTP server.
+//!
+//! Every route except the landing page and `/about` requires the token, sent
+//! either as `Authorization: Bearer <token>` or, for WebSockets (which cannot
+//! set headers from a browser), as a `Sec-WebSocket-Protocol` entry
+//! `bearer.<token>`.
+
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Routes reachable without a token.
+const PUBLIC_PATHS: &[&str] = &["/", "/about"];
+
+/// Prefix of the WebSocket subprotocol that carries the token.
+pub const SUBPROTOCOL_PREFIX: &str = "bearer.";
+
+pub const UNAUTHORIZED: &str = "unauthorized";
+
+#[derive(Clone)]
+pub struct AuthToken {
+    token: String,
+}
+
+/// Why a request was refused; sent to the client as the body of a 401.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuthError {
+    pub success: bool,
+    pub code: &'static str,
+    pub error: String,
+}
+
+impl AuthError {
+    fn new(error: &str) -> Self {
+        Self {
+            success: false,
+            code: UNAUTHORIZED,
+            error: error.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AuthToken(..)")
+    }
+}
+
+impl AuthToken {
+    /// A fresh random token: two v4 UUIDs, 244 random bits.
+    pub fn generate() -> Self {
+        Self {
+            token: format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.token
+    }
+
+    /// Write the token to `path`, readable by the current user only.
+    pub fn write_to(&self, path: &Path) -> io::Result<()> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            // `mode` only applies when the file is created
+            if path.exists() {
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+            }
+        }
+        let mut file = options.open(path)?;
+        writeln!(file, "{}", self.token)
+    }
+
+    pub fn matches(&self, candidate: &str) -> bool {
+        constant_time_eq(self.token.as_bytes(), candidate.as_bytes())
+    }
+
+    /// Check a request for `path` given its `Authorization` and
+    /// `Sec-WebSocket-Protocol` headers. On success, returns the subprotocol
+    /// the handshake must echo when the token came that way.
+    pub fn authorize(
+        &self,
+        path: &str,
+        authorization: Option<&str>,
+        protocols: Option<&str>,
+    ) -> Result<Option<String>, AuthError> {
+        if PUBLIC_PATHS.contains(&path) {
+            return Ok(None);
+        }
+
+        if let Some(authorization) = authorization {
+            let Some(token) = bearer_token(authorization) else {
+                return Err(AuthError::new("Authorization header is not a bearer token"));
+            };
+            return if self.matches(token) {
+                Ok(None)
+            } else {
+                Err(AuthError::new("invalid token"))
+            };
+        }
+
+        for protocol in protocols.into_iter().flat_map(|p| p.split(',')).map(str::trim) {
+            if let Some(token) = protocol.strip_prefix(SUBPROTOCOL_PREFIX) {
+                return if self.matches(token) {
+                    Ok(Some(protocol.to_string()))
+                } else {
+                    Err(AuthError::new("invalid token"))
+                };
+            }
+        }
+
+        Err(AuthError::new("missing token"))
+    }
+}
+
+fn bearer_token(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.trim().split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+/// Compare without an early exit, so the time taken does not reveal how much
+/// of a guess was right. Only the length can leak, and it is not secret.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token() -> AuthToken {
+        AuthToken {
+            token: "s3cret".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_generated_tokens_differ() {
+        let a = AuthToken::generate();
+        let b = AuthToken::generate();
+        assert_eq!(a.as_str().len(), 64);
+        assert!(!a.matches(b.as_str()));
+        assert!(!format!("{:?}", a).contains(a.as_str()));
+    }
+
+    #[test]
+    fn test_public_paths_need_no_token() {
+        assert_eq!(token().authorize("/", None, None), Ok(None));
+        assert_eq!(token().authorize("/about", None, None), Ok(None));
+    }
+
+    #[test]
+    fn test_requests_without_a_valid_token_are_rejected() {
+        let token = token();
+        for path in ["/shell", "/write", "/read", "/check-origin", "/socket/websocket"] {
+            assert_eq!(token.authorize(path, None, None).unwrap_err().error, "missing token");
+        }
+        let rejected = [
+            (Some("Bearer wrong"), None),
+            (Some("Bearer s3cre"), None),
+            (Some("Basic s3cret"), None),
+            (Some("s3cret"), None),
+            (None, Some("bearer.wrong")),
+            (None, Some("chat, bearer.s3cret2")),
+            // A wrong header is not rescued by a right subprotocol
+            (Some("Bearer wrong"), Some("bearer.s3cret")),
+        ];
+        for (authorization, protocols) in rejected {
+            let denied = token.authorize("/shell", authorization, protocols).unwrap_err();
+            assert_eq!(denied.code, UNAUTHORIZED, "{:?} {:?}", authorization, protocols);
+            assert!(!denied.success);
+        }
+        assert_eq!(token.authorize("/shell", None, Some("chat")).unwrap_err().error, "missing token");
+    }
+
+    #[test]
+    fn test_token_is_accepted_as_header_or_subprotocol() {
+        let token = token();
+        assert_eq!(token.authorize("/shell", Some("Bearer s3cret"), None), Ok(None));
+        assert_eq!(token.authorize("/shell", Some("bearer  s3cret "), None), Ok(None));
+        assert_eq!(
+            token.authorize("/socket/websocket", None, Some("chat, bearer.s3cret")),
+            Ok(Some("bearer.s3cret".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_token_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("token");
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let token = token();
+        token.write_to(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "s3cret\n");
+    }
+}
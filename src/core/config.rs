@@ -0,0 +1,24 @@
+//! Settings the local HTTP server is started with.
+
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub port: u16,
+    pub https_port: Option<u16>,
+    /// PEM certificate chain, required with `https_port`
+    pub https_cert_path: Option<String>,
+    /// PEM private key, required with `https_port`
+    pub https_key_path: Option<String>,
+    /// Listen on all interfaces instead of loopback only
+    pub allow_remote_access: bool,
+    /// Origins accepted besides the server's own localhost ones
+    pub allowed_origins: Vec<String>,
+    /// Root specs, `PATH`, `PATH:rw` or `PATH:ro`; the current directory when empty
+    pub workspace_roots: Vec<String>,
+    /// TOML shell policy for `/shell` and `/pty`; everything is allowed without one
+    pub shell_policy_file: Option<PathBuf>,
+    /// Where to write the per-launch access token; without it the token is
+    /// printed to stderr
+    pub token_file: Option<PathBuf>,
+}
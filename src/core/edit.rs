@@ -0,0 +1,595 @@
+//! Edit operations behind `/edit`.
+//!
+//! A request carries a list of operations applied in order to the file's
+//! text; if any of them fails, nothing is written. Operations are:
+//!
+//! - `replace`: swap `old` for `new`, where `old` must occur exactly once
+//!   (or set `all` to replace every occurrence)
+//! - `replace_lines`: replace lines `start..=end` (1-based) with `content`;
+//!   `end = start - 1` inserts before `start`
+//! - `patch`: apply a unified diff to the file. A hunk may have moved up to
+//!   `MAX_HUNK_OFFSET` lines from where its header says, but never back over
+//!   the hunk before it; a hunk without context lines must apply exactly there
+//!
+//! Line-based operations keep each line's own ending, so CRLF files stay CRLF.
+
+use serde::{Deserialize, Serialize};
+
+/// The edit does not fit the file as it is now (HTTP 409).
+pub const EDIT_CONFLICT: &str = "edit_conflict";
+/// The edit itself is malformed (HTTP 400).
+pub const INVALID_EDIT: &str = "invalid_edit";
+
+/// How far from its header's line a hunk is searched for.
+const MAX_HUNK_OFFSET: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum EditOp {
+    Replace {
+        old: String,
+        new: String,
+        #[serde(default)]
+        all: bool,
+    },
+    ReplaceLines {
+        start: usize,
+        end: usize,
+        content: String,
+    },
+    Patch {
+        diff: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EditFailure {
+    pub success: bool,
+    pub code: &'static str,
+    pub error: String,
+    /// Index of the failing operation in the request
+    pub op: usize,
+    /// 1-based number of the failing hunk, for `patch`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hunk: Option<usize>,
+}
+
+struct OpError {
+    code: &'static str,
+    error: String,
+    hunk: Option<usize>,
+}
+
+impl OpError {
+    fn conflict(error: String) -> Self {
+        Self {
+            code: EDIT_CONFLICT,
+            error,
+            hunk: None,
+        }
+    }
+
+    fn invalid(error: String) -> Self {
+        Self {
+            code: INVALID_EDIT,
+            error,
+            hunk: None,
+        }
+    }
+}
+
+/// Apply `ops` in order, returning the new text or the first failure.
+pub fn apply_edits(content: &str, ops: &[EditOp]) -> Result<String, EditFailure> {
+    let mut content = content.to_string();
+    for (index, op) in ops.iter().enumerate() {
+        content = apply_op(&content, op).map_err(|e| EditFailure {
+            success: false,
+            code: e.code,
+            error: e.error,
+            op: index,
+            hunk: e.hunk,
+        })?;
+    }
+    Ok(content)
+}
+
+fn apply_op(content: &str, op: &EditOp) -> Result<String, OpError> {
+    match op {
+        EditOp::Replace { old, new, all } => replace(content, old, new, *all),
+        EditOp::ReplaceLines {
+            start,
+            end,
+            content: replacement,
+        } => replace_lines(content, *start, *end, replacement),
+        EditOp::Patch { diff } => apply_patch(content, diff),
+    }
+}
+
+fn replace(content: &str, old: &str, new: &str, all: bool) -> Result<String, OpError> {
+    if old.is_empty() {
+        return Err(OpError::invalid("old text is empty".to_string()));
+    }
+    match content.matches(old).count() {
+        0 => Err(OpError::conflict("old text not found".to_string())),
+        1 => Ok(content.replacen(old, new, 1)),
+        _ if all => Ok(content.replace(old, new)),
+        count => Err(OpError::conflict(format!(
+            "old text occurs {} times; add surrounding context or set all",
+            count
+        ))),
+    }
+}
+
+/// A line without its ending, and the ending (`\n`, `\r\n`, or none on
+/// the last line).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Line {
+    text: String,
+    eol: String,
+}
+
+fn split_lines(content: &str) -> Vec<Line> {
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            let text = line.trim_end_matches('\n').trim_end_matches('\r');
+            Line {
+                text: text.to_string(),
+                eol: line[text.len()..].to_string(),
+            }
+        })
+        .collect()
+}
+
+fn join_lines(lines: &[Line]) -> String {
+    lines
+        .iter()
+        .flat_map(|line| [line.text.as_str(), line.eol.as_str()])
+        .collect()
+}
+
+/// The ending new lines get: whatever the file uses first.
+fn line_ending(lines: &[Line]) -> &str {
+    match lines.iter().find(|line| !line.eol.is_empty()) {
+        Some(line) => &line.eol,
+        None => "\n",
+    }
+}
+
+fn replace_lines(
+    content: &str,
+    start: usize,
+    end: usize,
+    replacement: &str,
+) -> Result<String, OpError> {
+    let mut lines = split_lines(content);
+    if start == 0 || start > lines.len() + 1 || end + 1 < start || end > lines.len() {
+        return Err(OpError::conflict(format!(
+            "lines {}-{} are not in the file, which has {} lines",
+            start,
+            end,
+            lines.len()
+        )));
+    }
+
+    let eol = line_ending(&lines).to_string();
+    let mut new_lines = split_lines(replacement);
+    // Replaced text followed by more lines must end its last line
+    let is_last = end == lines.len();
+    let ends_file = is_last && lines.last().is_none_or(|line| line.eol.is_empty());
+    if let Some(last) = new_lines.last_mut() {
+        if last.eol.is_empty() && !ends_file {
+            last.eol = eol;
+        }
+    }
+    lines.splice(start - 1..end, new_lines);
+    Ok(join_lines(&lines))
+}
+
+#[derive(Debug, Default)]
+struct Hunk {
+    /// 1-based line the hunk starts at in the original file
+    old_start: usize,
+    old: Vec<Line>,
+    new: Vec<Line>,
+    /// Unchanged lines, which anchor the hunk if the file has moved
+    context: usize,
+}
+
+/// Parse `-start[,count] +start[,count]` from a hunk header.
+fn parse_hunk_header(header: &str) -> Option<(usize, usize, usize)> {
+    let mut ranges = header.split_whitespace();
+    let parse = |range: &str| {
+        let (start, count) = range.split_once(',').unwrap_or((range, "1"));
+        Some((start.parse::<usize>().ok()?, count.parse::<usize>().ok()?))
+    };
+    let (old_start, old_count) = parse(ranges.next()?.strip_prefix('-')?)?;
+    let (_, new_count) = parse(ranges.next()?.strip_prefix('+')?)?;
+    Some((old_start, old_count, new_count))
+}
+
+fn parse_patch(diff: &str) -> Result<Vec<Hunk>, OpError> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut files = 0;
+    // Lines still expected in the current hunk, from its header
+    let (mut old_left, mut new_left) = (0, 0);
+    // Which side the previous line went to, for "\ No newline at end of file"
+    let mut last_side = ' ';
+
+    for line in diff.lines() {
+        if old_left == 0 && new_left == 0 {
+            if let Some(header) = line.strip_prefix("@@") {
+                let (old_start, old_count, new_count) = parse_hunk_header(header)
+                    .ok_or_else(|| OpError::invalid(format!("bad hunk header: {}", line)))?;
+                hunks.push(Hunk {
+                    old_start,
+                    ..Default::default()
+                });
+                (old_left, new_left) = (old_count, new_count);
+            } else if line.starts_with("+++ ") {
+                files += 1;
+            } else if line.starts_with('\\') {
+                mark_no_newline(hunks.last_mut(), last_side);
+            }
+            // Anything else between hunks is a header or commentary
+            continue;
+        }
+
+        let Some(hunk) = hunks.last_mut() else {
+            continue;
+        };
+        let text = line.get(1..).unwrap_or("");
+        let diff_line = Line {
+            text: text.to_string(),
+            eol: "\n".to_string(),
+        };
+        match line.chars().next() {
+            // Editors often strip the space from empty context lines
+            Some(' ') | None => {
+                hunk.old.push(diff_line.clone());
+                hunk.new.push(diff_line);
+                hunk.context += 1;
+                old_left = old_left.saturating_sub(1);
+                new_left = new_left.saturating_sub(1);
+                last_side = ' ';
+            }
+            Some('-') => {
+                hunk.old.push(diff_line);
+                old_left = old_left.saturating_sub(1);
+                last_side = '-';
+            }
+            Some('+') => {
+                hunk.new.push(diff_line);
+                new_left = new_left.saturating_sub(1);
+                last_side = '+';
+            }
+            Some('\\') => mark_no_newline(Some(hunk), last_side),
+            Some(_) => {
+                return Err(OpError::invalid(format!(
+                    "hunk {} is cut short at: {}",
+                    hunks.len(),
+                    line
+                )))
+            }
+        }
+    }
+
+    if old_left > 0 || new_left > 0 {
+        return Err(OpError::invalid(format!("hunk {} is cut short", hunks.len())));
+    }
+    if files > 1 {
+        return Err(OpError::invalid("diff touches more than one file".to_string()));
+    }
+    if hunks.is_empty() {
+        return Err(OpError::invalid("diff has no hunks".to_string()));
+    }
+    Ok(hunks)
+}
+
+/// Apply "\ No newline at end of file" to the line before it.
+fn mark_no_newline(hunk: Option<&mut Hunk>, side: char) {
+    let Some(hunk) = hunk else {
+        return;
+    };
+    if side != '+' {
+        if let Some(last) = hunk.old.last_mut() {
+            last.eol.clear();
+        }
+    }
+    if side != '-' {
+        if let Some(last) = hunk.new.last_mut() {
+            last.eol.clear();
+        }
+    }
+}
+
+fn apply_patch(content: &str, diff: &str) -> Result<String, OpError> {
+    let hunks = parse_patch(diff)?;
+    let mut lines = split_lines(content);
+    let eol = line_ending(&lines).to_string();
+    // How far earlier hunks moved the rest of the file, by their edits and
+    // by where they were found
+    let mut shift: isize = 0;
+    // Later hunks apply after the end of the one before
+    let mut floor = 0;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        // A pure insertion's start is the line it goes after
+        let start = if hunk.old.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = (start as isize + shift).max(0) as usize;
+        let at = if hunk.old.is_empty() {
+            Some(expected.min(lines.len())).filter(|&at| at >= floor)
+        } else {
+            // Without context there is nothing to tell a moved hunk from a
+            // coincidence, so it only applies where it says
+            let max_offset = if hunk.context == 0 { 0 } else { MAX_HUNK_OFFSET };
+            find_block(&lines, &hunk.old, expected, floor, max_offset)
+        };
+        let Some(at) = at else {
+            return Err(OpError {
+                code: EDIT_CONFLICT,
+                error: format!(
+                    "hunk {} (@@ -{} @@) does not match the file",
+                    index + 1,
+                    hunk.old_start
+                ),
+                hunk: Some(index + 1),
+            });
+        };
+
+        let replaced_end = at + hunk.old.len();
+        let mut new_lines = hunk.new.clone();
+        for line in &mut new_lines {
+            if !line.eol.is_empty() {
+                line.eol.clone_from(&eol);
+            }
+        }
+        // A diff that does not mention the file's missing final newline
+        // leaves it missing
+        let file_lacks_newline =
+            replaced_end == lines.len() && lines.last().is_some_and(|line| line.eol.is_empty());
+        let diff_assumes_newline = hunk.old.last().is_some_and(|line| !line.eol.is_empty());
+        if file_lacks_newline && diff_assumes_newline {
+            if let Some(last) = new_lines.last_mut() {
+                last.eol.clear();
+            }
+        }
+
+        shift += at as isize - expected as isize;
+        shift += new_lines.len() as isize - hunk.old.len() as isize;
+        floor = at + new_lines.len();
+        lines.splice(at..replaced_end, new_lines);
+    }
+    Ok(join_lines(&lines))
+}
+
+/// Where `block` occurs in `lines`, comparing text only, closest to
+/// `expected`: at `floor` or later and at most `max_offset` lines away.
+fn find_block(
+    lines: &[Line],
+    block: &[Line],
+    expected: usize,
+    floor: usize,
+    max_offset: usize,
+) -> Option<usize> {
+    let last = lines.len().checked_sub(block.len())?;
+    let matches_at = |at: &usize| {
+        lines[*at..*at + block.len()]
+            .iter()
+            .zip(block)
+            .all(|(line, wanted)| line.text == wanted.text)
+    };
+    (0..=max_offset)
+        .flat_map(|distance| [expected.checked_sub(distance), Some(expected + distance)])
+        .flatten()
+        .filter(|&at| at >= floor && at <= last)
+        .find(matches_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(content: &str, ops: Vec<EditOp>) -> Result<String, EditFailure> {
+        apply_edits(content, &ops)
+    }
+
+    fn replace_op(old: &str, new: &str) -> EditOp {
+        EditOp::Replace {
+            old: old.to_string(),
+            new: new.to_string(),
+            all: false,
+        }
+    }
+
+    fn lines_op(start: usize, end: usize, content: &str) -> EditOp {
+        EditOp::ReplaceLines {
+            start,
+            end,
+            content: content.to_string(),
+        }
+    }
+
+    fn patch_op(diff: &str) -> EditOp {
+        EditOp::Patch {
+            diff: diff.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_replace_requires_a_unique_match() {
+        let content = "let a = 1;\nlet b = 1;\n";
+        assert_eq!(
+            edit(content, vec![replace_op("a = 1", "a = 2")]).unwrap(),
+            "let a = 2;\nlet b = 1;\n"
+        );
+
+        let ambiguous = edit(content, vec![replace_op("= 1", "= 2")]).unwrap_err();
+        assert_eq!(ambiguous.code, EDIT_CONFLICT);
+        assert!(ambiguous.error.contains("2 times"), "{}", ambiguous.error);
+
+        let all = EditOp::Replace {
+            old: "= 1".to_string(),
+            new: "= 2".to_string(),
+            all: true,
+        };
+        assert_eq!(edit(content, vec![all]).unwrap(), "let a = 2;\nlet b = 2;\n");
+
+        assert_eq!(edit(content, vec![replace_op("c", "d")]).unwrap_err().code, EDIT_CONFLICT);
+        assert_eq!(edit(content, vec![replace_op("", "d")]).unwrap_err().code, INVALID_EDIT);
+    }
+
+    #[test]
+    fn test_ops_apply_in_order_and_all_or_nothing() {
+        let content = "one\ntwo\n";
+        let ops = vec![replace_op("one", "uno"), replace_op("uno", "eins")];
+        assert_eq!(edit(content, ops).unwrap(), "eins\ntwo\n");
+
+        let failure = edit(content, vec![replace_op("one", "uno"), replace_op("three", "")]).unwrap_err();
+        assert_eq!(failure.op, 1);
+    }
+
+    #[test]
+    fn test_replace_lines() {
+        let content = "a\nb\nc\n";
+        assert_eq!(edit(content, vec![lines_op(2, 2, "B")]).unwrap(), "a\nB\nc\n");
+        assert_eq!(edit(content, vec![lines_op(2, 3, "x\ny\n")]).unwrap(), "a\nx\ny\n");
+        // Insert before line 1, and append after the last line
+        assert_eq!(edit(content, vec![lines_op(1, 0, "top")]).unwrap(), "top\na\nb\nc\n");
+        assert_eq!(edit(content, vec![lines_op(4, 3, "end\n")]).unwrap(), "a\nb\nc\nend\n");
+        // Delete
+        assert_eq!(edit(content, vec![lines_op(1, 2, "")]).unwrap(), "c\n");
+
+        assert_eq!(edit("a\r\nb\r\n", vec![lines_op(1, 1, "A")]).unwrap(), "A\r\nb\r\n");
+        assert_eq!(edit("a\nb", vec![lines_op(2, 2, "B")]).unwrap(), "a\nB");
+        assert_eq!(edit(content, vec![lines_op(3, 5, "")]).unwrap_err().code, EDIT_CONFLICT);
+        assert_eq!(edit(content, vec![lines_op(0, 1, "")]).unwrap_err().code, EDIT_CONFLICT);
+    }
+
+    #[test]
+    fn test_patch() {
+        let content = "fn main() {\n    println!(\"hi\");\n}\n\nfn other() {}\n";
+        let diff = "\
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,3 +1,4 @@
+ fn main() {
+-    println!(\"hi\");
++    println!(\"hello\");
++    println!(\"world\");
+ }
+@@ -5 +6 @@
+-fn other() {}
++fn other() -> u8 { 0 }
+";
+        assert_eq!(
+            edit(content, vec![patch_op(diff)]).unwrap(),
+            "fn main() {\n    println!(\"hello\");\n    println!(\"world\");\n}\n\nfn other() -> u8 { 0 }\n"
+        );
+    }
+
+    #[test]
+    fn test_patch_removes_lines_that_look_like_headers() {
+        let content = "-- drop this\n++ and this\nkeep\n";
+        let diff = "--- a/q.sql\n+++ b/q.sql\n@@ -1,3 +1,1 @@\n--- drop this\n-++ and this\n keep\n";
+        assert_eq!(edit(content, vec![patch_op(diff)]).unwrap(), "keep\n");
+
+        let cut_short = edit(content, vec![patch_op("@@ -1,3 +1,1 @@\n--- drop this\n")]).unwrap_err();
+        assert_eq!(cut_short.code, INVALID_EDIT);
+    }
+
+    #[test]
+    fn test_patch_tolerates_moved_lines() {
+        // Two lines were added at the top since the diff was made
+        let content = "// header\n// more\nkeep\nold\nkeep\n";
+        let diff = "@@ -1,3 +1,3 @@\n keep\n-old\n+new\n keep\n";
+        assert_eq!(
+            edit(content, vec![patch_op(diff)]).unwrap(),
+            "// header\n// more\nkeep\nnew\nkeep\n"
+        );
+    }
+
+    #[test]
+    fn test_patch_does_not_match_before_the_previous_hunk() {
+        // The second hunk's `}` no longer exists where it says; the only other
+        // one comes before the first hunk and must not be used
+        let content = "}\nb\nc\nd\n";
+        let diff = "@@ -2,2 +2,2 @@\n-b\n+B\n c\n@@ -5,1 +5,2 @@\n }\n+tail\n";
+        let failure = edit(content, vec![patch_op(diff)]).unwrap_err();
+        assert_eq!(failure.code, EDIT_CONFLICT);
+        assert_eq!(failure.hunk, Some(2));
+
+        // Context repeated earlier in the file does not pull a hunk back
+        let content = "keep\nold\nkeep\n// one\nkeep\nold\nkeep\n";
+        let diff = "@@ -1,1 +1,1 @@\n-keep\n+kept\n\
+                    @@ -5,3 +5,3 @@\n keep\n-old\n+new\n keep\n";
+        assert_eq!(
+            edit(content, vec![patch_op(diff)]).unwrap(),
+            "kept\nold\nkeep\n// one\nkeep\nnew\nkeep\n"
+        );
+    }
+
+    #[test]
+    fn test_patch_offsets_are_bounded() {
+        let mut content = "target\n".to_string();
+        content.push_str(&"filler\n".repeat(MAX_HUNK_OFFSET + 10));
+        let start = MAX_HUNK_OFFSET + 5;
+        let diff = format!("@@ -{start},3 +{start},3 @@\n filler\n-target\n+hit\n filler\n");
+        let failure = edit(&content, vec![patch_op(&diff)]).unwrap_err();
+        assert_eq!(failure.code, EDIT_CONFLICT);
+
+        // A hunk with no context applies only at its own line
+        let content = "a\nb\nc\nb\n";
+        assert_eq!(
+            edit(content, vec![patch_op("@@ -4 +4 @@\n-b\n+B\n")]).unwrap(),
+            "a\nb\nc\nB\n"
+        );
+        let failure = edit(content, vec![patch_op("@@ -3 +3 @@\n-b\n+B\n")]).unwrap_err();
+        assert_eq!(failure.code, EDIT_CONFLICT);
+    }
+
+    #[test]
+    fn test_patch_reports_the_failing_hunk() {
+        let content = "a\nb\nc\n";
+        let diff = "@@ -1 +1 @@\n-a\n+A\n@@ -3 +3 @@\n-z\n+Z\n";
+        let failure = edit(content, vec![patch_op(diff)]).unwrap_err();
+        assert_eq!(failure.code, EDIT_CONFLICT);
+        assert_eq!(failure.hunk, Some(2));
+        assert_eq!(failure.op, 0);
+
+        let json = serde_json::to_value(&failure).unwrap();
+        assert_eq!(json["hunk"], 2);
+
+        let no_hunks = edit(content, vec![patch_op("--- a\n+++ b\n")]).unwrap_err();
+        assert_eq!(no_hunks.code, INVALID_EDIT);
+        assert_eq!(no_hunks.hunk, None);
+    }
+
+    #[test]
+    fn test_patch_line_endings() {
+        let diff = "@@ -1,2 +1,2 @@\n a\n-b\n+B\n";
+        assert_eq!(edit("a\r\nb\r\n", vec![patch_op(diff)]).unwrap(), "a\r\nB\r\n");
+
+        let no_newline = "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+B\n\\ No newline at end of file\n";
+        assert_eq!(edit("a\nb", vec![patch_op(no_newline)]).unwrap(), "a\nB");
+
+        let add_newline = "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n";
+        assert_eq!(edit("a\nb", vec![patch_op(add_newline)]).unwrap(), "a\nb\n");
+    }
+
+    #[test]
+    fn test_ops_parse() {
+        let ops: Vec<EditOp> = serde_json::from_str(
+            r#"[{"op":"replace","old":"a","new":"b"},
+                {"op":"replace_lines","start":1,"end":2,"content":"x"},
+                {"op":"patch","diff":"@@ -1 +1 @@"}]"#,
+        )
+        .unwrap();
+        assert_eq!(ops[0], replace_op("a", "b"));
+        assert_eq!(ops[1], lines_op(1, 2, "x"));
+    }
+}
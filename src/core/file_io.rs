@@ -0,0 +1,548 @@
+//! Binary-safe, ranged file reads and writes for the file API.
+//!
+//! `/read` and `/write` carry file content in JSON, as UTF-8 text or base64
+//! named by an `encoding` field. Without one, `/read` answers with text when
+//! the bytes are valid UTF-8 and base64 otherwise. JSON reads are capped at
+//! `MAX_INLINE_BYTES`; larger files are read in `offset`/`length` slices or
+//! streamed through `/read/raw` and `/write/raw`.
+//!
+//! Writes go to a temporary file that is renamed over the target, so readers
+//! never see a half-written file. A write can be made conditional on the
+//! current content (`if_match`, a SHA-256 hex digest) or modification time
+//! (`if_mtime_ns`); when it no longer matches, the write is refused with the
+//! current values so the client can re-read and retry. Nanosecond times are
+//! sent as strings, since JSON numbers lose precision past 2^53 in browsers.
+//! Writers of the same file take its `PathLocks` lock around the check and the
+//! rename, so two conditional writes cannot both pass the same check.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::time::UNIX_EPOCH;
+
+/// Largest read answered inline in a JSON response.
+pub const MAX_INLINE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Largest body accepted by `/write/raw`.
+pub const MAX_UPLOAD_BYTES: u64 = 1024 * 1024 * 1024;
+
+pub const PRECONDITION_FAILED: &str = "precondition_failed";
+
+/// An exclusive write found the file already there.
+pub const FILE_EXISTS: &str = "file_exists";
+
+/// The filesystem cannot create a file without possibly replacing another.
+pub const EXCLUSIVE_UNSUPPORTED: &str = "exclusive_unsupported";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    Utf8,
+    Base64,
+}
+
+/// A slice of a file: `length` bytes from `offset`, or to the end.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct ByteRange {
+    #[serde(default)]
+    pub offset: u64,
+    pub length: Option<u64>,
+}
+
+impl ByteRange {
+    /// Number of bytes this range covers in a file of `size` bytes.
+    pub fn len_in(&self, size: u64) -> u64 {
+        let available = size.saturating_sub(self.offset);
+        self.length.map_or(available, |length| length.min(available))
+    }
+}
+
+#[derive(Debug)]
+pub struct RangeRead {
+    pub data: Vec<u8>,
+    /// Size of the whole file
+    pub size: u64,
+}
+
+/// Read `range` of the file at `path`, refusing to load more than `max`
+/// bytes into memory.
+pub fn read_range(path: &Path, range: ByteRange, max: u64) -> Result<RangeRead, String> {
+    let mut file = File::open(path).map_err(|e| e.kind().to_string())?;
+    let size = file.metadata().map_err(|e| e.kind().to_string())?.len();
+
+    let length = range.len_in(size);
+    if length > max {
+        return Err(format!(
+            "{} bytes requested, more than the {} byte limit; read with offset/length or /read/raw",
+            length, max
+        ));
+    }
+
+    file.seek(SeekFrom::Start(range.offset))
+        .map_err(|e| e.kind().to_string())?;
+    let mut data = Vec::with_capacity(length as usize);
+    file.take(length)
+        .read_to_end(&mut data)
+        .map_err(|e| e.kind().to_string())?;
+    Ok(RangeRead { data, size })
+}
+
+/// Encode file bytes for a JSON response. Asking for UTF-8 fails on bytes
+/// that are not valid UTF-8, as reads always did.
+pub fn encode(data: Vec<u8>, requested: Option<Encoding>) -> Result<(String, Encoding), String> {
+    match requested {
+        Some(Encoding::Base64) => Ok((STANDARD.encode(&data), Encoding::Base64)),
+        Some(Encoding::Utf8) => String::from_utf8(data)
+            .map(|content| (content, Encoding::Utf8))
+            .map_err(|_| io::ErrorKind::InvalidData.to_string()),
+        None => match String::from_utf8(data) {
+            Ok(content) => Ok((content, Encoding::Utf8)),
+            Err(e) => Ok((STANDARD.encode(e.as_bytes()), Encoding::Base64)),
+        },
+    }
+}
+
+pub fn decode(content: String, encoding: Encoding) -> Result<Vec<u8>, String> {
+    match encoding {
+        Encoding::Utf8 => Ok(content.into_bytes()),
+        Encoding::Base64 => STANDARD
+            .decode(content.as_bytes())
+            .map_err(|e| format!("invalid base64 content: {}", e)),
+    }
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// SHA-256 of the file at `path`, or `None` when there is no such file.
+pub fn file_sha256(path: &Path) -> io::Result<Option<String>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(Some(hex(&hasher.finalize())))
+}
+
+pub fn mtime_ns(metadata: &Metadata) -> io::Result<u64> {
+    let since_epoch = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| io::Error::other("system time error"))?;
+    Ok(since_epoch.as_nanos() as u64)
+}
+
+pub fn serialize_ns<S: Serializer>(ns: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(ns)
+}
+
+/// Accept a nanosecond time as a string or, from clients that can hold it
+/// exactly, a number.
+pub fn deserialize_ns<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Ns {
+        Number(u64),
+        Text(String),
+    }
+    match Option::<Ns>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Ns::Number(ns)) => Ok(Some(ns)),
+        Some(Ns::Text(text)) => text.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Conditions a write must meet; both must hold when both are given.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Precondition {
+    /// SHA-256 hex digest of the content the client expects to replace
+    pub if_match: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_ns")]
+    pub if_mtime_ns: Option<u64>,
+}
+
+/// A failed precondition, answered with 409 and the file's current state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Conflict {
+    pub success: bool,
+    pub code: &'static str,
+    pub error: String,
+    /// `None` when the file does not exist
+    pub current_sha256: Option<String>,
+    #[serde(serialize_with = "serialize_optional_ns")]
+    pub current_mtime_ns: Option<u64>,
+}
+
+fn serialize_optional_ns<S: Serializer>(
+    ns: &Option<u64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match ns {
+        Some(ns) => serializer.collect_str(ns),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl Precondition {
+    pub fn is_empty(&self) -> bool {
+        self.if_match.is_none() && self.if_mtime_ns.is_none()
+    }
+
+    /// Compare against the file at `path`. An `io::Error` means the file
+    /// could not be inspected at all.
+    pub fn check(&self, path: &Path) -> io::Result<Result<(), Conflict>> {
+        if self.is_empty() {
+            return Ok(Ok(()));
+        }
+        let current_sha256 = file_sha256(path)?;
+        let current_mtime_ns = match std::fs::metadata(path) {
+            Ok(metadata) => Some(mtime_ns(&metadata)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        let hash_matches = self.if_match.as_ref().is_none_or(|expected| {
+            current_sha256
+                .as_ref()
+                .is_some_and(|current| current.eq_ignore_ascii_case(expected.trim()))
+        });
+        let mtime_matches = self
+            .if_mtime_ns
+            .is_none_or(|expected| current_mtime_ns == Some(expected));
+        if hash_matches && mtime_matches {
+            return Ok(Ok(()));
+        }
+
+        let error = if current_sha256.is_none() {
+            "file does not exist".to_string()
+        } else if !hash_matches {
+            "file content has changed".to_string()
+        } else {
+            "file modification time has changed".to_string()
+        };
+        Ok(Err(Conflict {
+            success: false,
+            code: PRECONDITION_FAILED,
+            error,
+            current_sha256,
+            current_mtime_ns,
+        }))
+    }
+}
+
+/// A temporary file next to `target` that replaces it on `commit`. Dropped
+/// without committing, the temporary file is removed.
+pub struct AtomicFile {
+    temp: PathBuf,
+    target: PathBuf,
+    committed: bool,
+}
+
+impl AtomicFile {
+    pub fn create(target: &Path) -> io::Result<(Self, File)> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let parent = target.parent().unwrap_or(Path::new("."));
+        let name = target
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+        let temp = parent.join(format!(
+            ".{}.{}.{}.tmp",
+            name.to_string_lossy(),
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        Ok((
+            Self {
+                temp,
+                target: target.to_path_buf(),
+                committed: false,
+            },
+            file,
+        ))
+    }
+
+    /// Move the written file into place. With `exclusive`, fail with
+    /// `AlreadyExists` rather than replace an existing file.
+    pub fn commit(mut self, exclusive: bool) -> io::Result<()> {
+        if exclusive {
+            // A hard link, unlike a rename, refuses to replace the target
+            match std::fs::hard_link(&self.temp, &self.target) {
+                Ok(()) => std::fs::remove_file(&self.temp)?,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Err(e),
+                // e.g. FAT or some network mounts, which have no hard links
+                Err(_) => self.claim_and_rename()?,
+            }
+        } else {
+            // Keep the permissions of the file being replaced
+            if let Ok(metadata) = std::fs::metadata(&self.target) {
+                std::fs::set_permissions(&self.temp, metadata.permissions())?;
+            }
+            std::fs::rename(&self.temp, &self.target)?;
+        }
+        self.committed = true;
+        Ok(())
+    }
+
+    /// Reserve the target name with `O_CREAT | O_EXCL`, then rename the
+    /// written file over the empty placeholder. Readers may briefly see an
+    /// empty file, but an existing one is never replaced.
+    fn claim_and_rename(&self) -> io::Result<()> {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&self.target)?;
+        std::fs::rename(&self.temp, &self.target).inspect_err(|_| {
+            let _ = std::fs::remove_file(&self.target);
+        })
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = std::fs::remove_file(&self.temp);
+        }
+    }
+}
+
+/// Write `data` to `path` through a temporary file and a rename.
+pub fn write_atomic(path: &Path, data: &[u8], exclusive: bool) -> io::Result<()> {
+    let (atomic, mut file) = AtomicFile::create(path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    atomic.commit(exclusive)
+}
+
+/// One lock per file being written, shared by every request for that file.
+#[derive(Debug, Clone, Default)]
+pub struct PathLocks {
+    locks: Arc<Mutex<HashMap<PathBuf, Weak<Mutex<()>>>>>,
+}
+
+impl PathLocks {
+    /// The lock for `path`, which should be canonical so that every name for
+    /// a file gets the same lock.
+    pub fn for_path(&self, path: &Path) -> Arc<Mutex<()>> {
+        let mut locks = self.locks.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(lock) = locks.get(path).and_then(Weak::upgrade) {
+            return lock;
+        }
+        // Forget the files nobody is writing any more
+        locks.retain(|_, lock| lock.strong_count() > 0);
+        let lock = Arc::new(Mutex::new(()));
+        locks.insert(path.to_path_buf(), Arc::downgrade(&lock));
+        lock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_encoding_round_trip() {
+        let png = vec![0x89, b'P', b'N', b'G', 0x00, 0xff];
+        let (content, encoding) = encode(png.clone(), None).unwrap();
+        assert_eq!(encoding, Encoding::Base64);
+        assert_eq!(decode(content, encoding).unwrap(), png);
+
+        let (content, encoding) = encode(b"text".to_vec(), None).unwrap();
+        assert_eq!((content.as_str(), encoding), ("text", Encoding::Utf8));
+
+        let (content, _) = encode(b"text".to_vec(), Some(Encoding::Base64)).unwrap();
+        assert_eq!(content, "dGV4dA==");
+        assert!(encode(png, Some(Encoding::Utf8)).is_err());
+        assert!(decode("not base64!".to_string(), Encoding::Base64).is_err());
+    }
+
+    #[test]
+    fn test_read_range() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("log");
+        std::fs::write(&path, b"0123456789").unwrap();
+
+        let read = |offset, length| {
+            read_range(&path, ByteRange { offset, length }, MAX_INLINE_BYTES).unwrap()
+        };
+        assert_eq!(read(0, None).data, b"0123456789");
+        assert_eq!(read(2, Some(3)).data, b"234");
+        assert_eq!(read(8, Some(10)).data, b"89");
+        let past_end = read(20, None);
+        assert!(past_end.data.is_empty());
+        assert_eq!(past_end.size, 10);
+    }
+
+    #[test]
+    fn test_read_range_size_guard() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("big");
+        std::fs::write(&path, vec![0u8; 100]).unwrap();
+
+        assert!(read_range(&path, ByteRange::default(), 99).is_err());
+        let range = ByteRange {
+            offset: 50,
+            length: None,
+        };
+        assert_eq!(read_range(&path, range, 50).unwrap().data.len(), 50);
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("notes.md");
+
+        write_atomic(&path, b"one", true).unwrap();
+        let err = write_atomic(&path, b"two", true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        write_atomic(&path, b"three", false).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"three");
+
+        // No temporary files are left behind, even by the failed write
+        let names: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(names.len(), 1);
+    }
+
+    #[test]
+    fn test_exclusive_commit_without_hard_links() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("notes.md");
+
+        let (atomic, mut file) = AtomicFile::create(&path).unwrap();
+        file.write_all(b"one").unwrap();
+        atomic.claim_and_rename().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"one");
+
+        let (atomic, mut file) = AtomicFile::create(&path).unwrap();
+        file.write_all(b"two").unwrap();
+        let err = atomic.claim_and_rename().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        drop(atomic);
+        assert_eq!(std::fs::read(&path).unwrap(), b"one");
+
+        let names: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(names.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("run.sh");
+        std::fs::write(&path, "#!/bin/sh").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o750)).unwrap();
+
+        write_atomic(&path, b"#!/bin/sh\necho hi", false).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+    }
+
+    #[test]
+    fn test_preconditions() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "hello").unwrap();
+        let hash = sha256_hex(b"hello");
+        let mtime = mtime_ns(&std::fs::metadata(&path).unwrap()).unwrap();
+        assert_eq!(file_sha256(&path).unwrap(), Some(hash.clone()));
+
+        let check = |if_match: Option<&str>, if_mtime_ns| {
+            Precondition {
+                if_match: if_match.map(str::to_string),
+                if_mtime_ns,
+            }
+            .check(&path)
+            .unwrap()
+        };
+        assert!(check(None, None).is_ok());
+        assert!(check(Some(&hash), Some(mtime)).is_ok());
+        assert!(check(Some(&hash.to_uppercase()), None).is_ok());
+
+        let conflict = check(Some(&sha256_hex(b"other")), None).unwrap_err();
+        assert_eq!(conflict.code, PRECONDITION_FAILED);
+        assert_eq!(conflict.current_sha256, Some(hash.clone()));
+        assert_eq!(conflict.current_mtime_ns, Some(mtime));
+        assert!(check(Some(&hash), Some(mtime + 1)).is_err());
+
+        let json = serde_json::to_value(&conflict).unwrap();
+        assert_eq!(json["current_mtime_ns"], mtime.to_string());
+
+        std::fs::remove_file(&path).unwrap();
+        let missing = check(Some(&hash), None).unwrap_err();
+        assert_eq!(missing.current_sha256, None);
+        assert_eq!(missing.error, "file does not exist");
+    }
+
+    #[test]
+    fn test_mtime_ns_accepts_strings_and_numbers() {
+        let parse = |json: &str| serde_json::from_str::<Precondition>(json).unwrap().if_mtime_ns;
+        assert_eq!(parse(r#"{"if_mtime_ns":"1700000000123456789"}"#), Some(1700000000123456789));
+        assert_eq!(parse(r#"{"if_mtime_ns":42}"#), Some(42));
+        assert_eq!(parse(r#"{}"#), None);
+        assert!(serde_json::from_str::<Precondition>(r#"{"if_mtime_ns":"soon"}"#).is_err());
+    }
+
+    #[test]
+    fn test_path_locks_serialize_check_and_write() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("counter");
+        write_atomic(&path, b"0", false).unwrap();
+        let locks = PathLocks::default();
+
+        // Each writer only commits when the file still has the digest it read
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let (locks, path) = (locks.clone(), path.clone());
+                std::thread::spawn(move || {
+                    let mut committed = 0;
+                    while committed < 25 {
+                        let current = std::fs::read(&path).unwrap();
+                        let precondition = Precondition {
+                            if_match: Some(sha256_hex(&current)),
+                            if_mtime_ns: None,
+                        };
+                        let next = String::from_utf8(current).unwrap().parse::<u32>().unwrap() + 1;
+
+                        let lock = locks.for_path(&path);
+                        let _guard = lock.lock().unwrap();
+                        if precondition.check(&path).unwrap().is_ok() {
+                            write_atomic(&path, next.to_string().as_bytes(), false).unwrap();
+                            committed += 1;
+                        }
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "200");
+
+        let held = locks.for_path(&path);
+        assert!(Arc::ptr_eq(&held, &locks.for_path(&path)));
+        drop(held);
+        locks.for_path(&dir.path().join("other"));
+        assert_eq!(locks.locks.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,26 @@
+//! Local server building blocks: settings, workspace confinement, auth, shell
+//! policy, PTY sessions, file IO, edits, search and watches.
+//!
+//! These modules only need the crates the `server-core` feature enables, so
+//! they are built and tested with the CLI. The HTTP server
+//! itself (`server.rs`, `acp.rs`, `notify.rs`) also needs axum, rustls and the
+//! ACP/WebSocket modules, which are not part of this package; it is not built
+//! here.
+//!
+//! The modules refer to each other as `crate::<module>`, so the binary
+//! re-exports the ones they import at its root.
+
+// Nothing in the CLI calls into the server yet
+#![allow(dead_code)]
+
+pub mod auth;
+pub mod config;
+pub mod edit;
+pub mod file_io;
+pub mod path_match;
+pub mod pty;
+pub mod search;
+pub mod shell_policy;
+pub mod shell_protocol;
+pub mod watch;
+pub mod workspace;
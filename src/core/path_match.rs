@@ -0,0 +1,542 @@
+//! Path globs and `.gitignore` rules for the watch and search APIs.
+//!
+//! Paths are matched relative to a root, with `/` as the separator. A glob
+//! supports `*` and `?` within one component, `**` as a whole component for
+//! any number of components, `[abc]`/`[a-z]`/`[!a]` classes and `{a,b}`
+//! alternatives, so `src/**/*.rs` matches `src/main.rs` and `src/a/b.rs` but
+//! `*.rs` only matches files at the top. Wildcards also match a leading dot.
+//!
+//! A glob expands to at most `MAX_ALTERNATIVES` brace alternatives, and
+//! matching is linear in the number of `**` and path components rather than
+//! exponential.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Directory that is never reported, whatever the ignore files say.
+const GIT_DIR: &str = ".git";
+
+/// How many patterns the `{a,b}` groups of one glob may expand to.
+pub const MAX_ALTERNATIVES: usize = 1024;
+
+pub const INVALID_GLOB: &str = "invalid_glob";
+/// The glob expands to more than `MAX_ALTERNATIVES` patterns (HTTP 400).
+pub const GLOB_TOO_LARGE: &str = "glob_too_large";
+
+/// Why a glob was refused; a `GLOB_TOO_LARGE` one is sent as a 400 response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GlobError {
+    pub success: bool,
+    pub code: &'static str,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    /// `?`
+    One,
+    /// `*`
+    Any,
+    Class { negated: bool, ranges: Vec<(char, char)> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// `**`
+    AnyDepth,
+    Pattern(Vec<Token>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    /// One entry per `{a,b}` expansion
+    alternatives: Vec<Vec<Segment>>,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Self, GlobError> {
+        let invalid = |e| GlobError {
+            success: false,
+            code: INVALID_GLOB,
+            error: format!("invalid glob {:?}: {}", pattern, e),
+        };
+        let mut expanded = Vec::new();
+        if !expand_braces(pattern, &mut expanded).map_err(invalid)? {
+            return Err(GlobError {
+                success: false,
+                code: GLOB_TOO_LARGE,
+                error: format!(
+                    "glob {:?} expands to more than {} alternatives",
+                    pattern, MAX_ALTERNATIVES
+                ),
+            });
+        }
+        let alternatives = expanded
+            .iter()
+            .map(|pattern| parse_segments(pattern))
+            .collect::<Result<_, _>>()
+            .map_err(invalid)?;
+        Ok(Self { alternatives })
+    }
+
+    /// Whether the relative path `path` matches.
+    pub fn is_match(&self, path: &str) -> bool {
+        let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+        self.alternatives
+            .iter()
+            .any(|segments| match_segments(segments, &components))
+    }
+
+    /// Whether some path below the directory `dir` could match, so a walk
+    /// can skip directories that cannot contain results.
+    pub fn may_match_under(&self, dir: &str) -> bool {
+        let components: Vec<&str> = dir.split('/').filter(|c| !c.is_empty()).collect();
+        self.alternatives
+            .iter()
+            .any(|segments| match_prefix(segments, &components))
+    }
+}
+
+/// Expand the first top-level `{a,b}` group, recursively, into plain patterns
+/// added to `expanded`. `false` once there would be more than
+/// `MAX_ALTERNATIVES`, without expanding further.
+fn expand_braces(pattern: &str, expanded: &mut Vec<String>) -> Result<bool, String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut open = None;
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => {
+                if depth == 0 {
+                    open = Some(i);
+                }
+                depth += 1;
+            }
+            ',' if depth == 1 => commas.push(i),
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    let open = open.unwrap_or_default();
+                    let prefix: String = chars[..open].iter().collect();
+                    let suffix: String = chars[i + 1..].iter().collect();
+                    let mut bounds = vec![open];
+                    bounds.extend(&commas);
+                    bounds.push(i);
+                    for pair in bounds.windows(2) {
+                        let choice: String = chars[pair[0] + 1..pair[1]].iter().collect();
+                        let choice = format!("{}{}{}", prefix, choice, suffix);
+                        if !expand_braces(&choice, expanded)? {
+                            return Ok(false);
+                        }
+                    }
+                    return Ok(true);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if depth > 0 {
+        return Err("unclosed '{'".to_string());
+    }
+    if expanded.len() == MAX_ALTERNATIVES {
+        return Ok(false);
+    }
+    expanded.push(pattern.to_string());
+    Ok(true)
+}
+
+fn parse_segments(pattern: &str) -> Result<Vec<Segment>, String> {
+    pattern
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .map(|component| {
+            if component == "**" {
+                Ok(Segment::AnyDepth)
+            } else {
+                parse_tokens(component).map(Segment::Pattern)
+            }
+        })
+        .collect()
+}
+
+fn parse_tokens(component: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = component.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => tokens.push(Token::Char(chars.next().unwrap_or('\\'))),
+            '?' => tokens.push(Token::One),
+            '*' => {
+                // `a**b` is the same as `a*b`
+                if tokens.last() != Some(&Token::Any) {
+                    tokens.push(Token::Any);
+                }
+            }
+            '[' => {
+                let negated = matches!(chars.peek(), Some('!' | '^'));
+                if negated {
+                    chars.next();
+                }
+                let mut ranges = Vec::new();
+                let mut first = true;
+                loop {
+                    let Some(c) = chars.next() else {
+                        return Err("unclosed '['".to_string());
+                    };
+                    if c == ']' && !first {
+                        break;
+                    }
+                    first = false;
+                    let start = if c == '\\' { chars.next().unwrap_or('\\') } else { c };
+                    let mut lookahead = chars.clone();
+                    let is_range = lookahead.next() == Some('-')
+                        && lookahead.peek().is_some_and(|&c| c != ']');
+                    if is_range {
+                        chars.next();
+                        let end = chars.next().unwrap_or(start);
+                        ranges.push((start, end));
+                    } else {
+                        ranges.push((start, start));
+                    }
+                }
+                tokens.push(Token::Class { negated, ranges });
+            }
+            c => tokens.push(Token::Char(c)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Whether `segments` match all of `components`. Tracks every number of
+/// components the segments so far can consume, so several `**` do not
+/// backtrack into each other.
+fn match_segments(segments: &[Segment], components: &[&str]) -> bool {
+    let mut reachable = vec![false; components.len() + 1];
+    reachable[0] = true;
+    for segment in segments {
+        let mut next = vec![false; components.len() + 1];
+        match segment {
+            // Anything at or after a reachable position
+            Segment::AnyDepth => {
+                let mut any = false;
+                for (at, next) in next.iter_mut().enumerate() {
+                    any |= reachable[at];
+                    *next = any;
+                }
+            }
+            Segment::Pattern(tokens) => {
+                for (at, component) in components.iter().enumerate() {
+                    next[at + 1] = reachable[at] && match_tokens(tokens, component);
+                }
+            }
+        }
+        reachable = next;
+    }
+    reachable[components.len()]
+}
+
+/// Whether `components` can be the start of a match, i.e. the glob may
+/// match a path inside that directory.
+fn match_prefix(segments: &[Segment], components: &[&str]) -> bool {
+    let Some((first, tail)) = components.split_first() else {
+        return !segments.is_empty();
+    };
+    match segments.split_first() {
+        None => false,
+        Some((Segment::AnyDepth, _)) => true,
+        Some((Segment::Pattern(tokens), rest)) => {
+            match_tokens(tokens, first) && match_prefix(rest, tail)
+        }
+    }
+}
+
+fn match_tokens(tokens: &[Token], text: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let (mut t, mut c) = (0, 0);
+    // Position after the last `*` and the text it has swallowed so far
+    let mut backtrack = None;
+    while c < text.len() {
+        match tokens.get(t) {
+            Some(Token::Any) => {
+                backtrack = Some((t + 1, c));
+                t += 1;
+                continue;
+            }
+            Some(token) if token_matches(token, text[c]) => {
+                t += 1;
+                c += 1;
+                continue;
+            }
+            _ => {}
+        }
+        match backtrack {
+            Some((star_t, star_c)) => {
+                t = star_t;
+                c = star_c + 1;
+                backtrack = Some((star_t, star_c + 1));
+            }
+            None => return false,
+        }
+    }
+    tokens[t..].iter().all(|token| *token == Token::Any)
+}
+
+fn token_matches(token: &Token, c: char) -> bool {
+    match token {
+        Token::Char(expected) => *expected == c,
+        Token::One => true,
+        Token::Any => false,
+        Token::Class { negated, ranges } => {
+            ranges.iter().any(|&(start, end)| start <= c && c <= end) != *negated
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    glob: Glob,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// The `.gitignore` files under a root, keyed by the directory they are in.
+#[derive(Debug, Clone, Default)]
+pub struct GitIgnore {
+    rules: HashMap<String, Vec<IgnoreRule>>,
+}
+
+impl GitIgnore {
+    /// (Re)load `dir/.gitignore`, with `dir` relative to `root`. A missing
+    /// or unreadable file drops the rules of that directory.
+    pub fn load_dir(&mut self, root: &Path, dir: &str) {
+        match std::fs::read_to_string(root.join(dir).join(".gitignore")) {
+            Ok(contents) => self.add(dir, &contents),
+            Err(_) => {
+                self.rules.remove(dir);
+            }
+        }
+    }
+
+    /// Set the rules of `dir` from the contents of its `.gitignore`.
+    pub fn add(&mut self, dir: &str, contents: &str) {
+        let rules: Vec<IgnoreRule> = contents.lines().filter_map(parse_rule).collect();
+        if rules.is_empty() {
+            self.rules.remove(dir);
+        } else {
+            self.rules.insert(dir.to_string(), rules);
+        }
+    }
+
+    /// Move the rules of `from` and the directories below it to `to`.
+    pub fn rename_dir(&mut self, from: &str, to: &str) {
+        let moved: Vec<String> = self
+            .rules
+            .keys()
+            .filter(|dir| strip_dir(dir, from).is_some())
+            .cloned()
+            .collect();
+        for dir in moved {
+            if let Some(rules) = self.rules.remove(&dir) {
+                let rest = strip_dir(&dir, from).unwrap_or_default();
+                self.rules.insert(join(to, rest), rules);
+            }
+        }
+    }
+
+    /// Whether the relative `path` is ignored, either itself or through one
+    /// of its parent directories. `.git` is always ignored.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+        if components.contains(&GIT_DIR) {
+            return true;
+        }
+        // Git does not look inside an excluded directory, so nothing below
+        // it can be re-included
+        let mut prefix = String::new();
+        for (i, component) in components.iter().enumerate() {
+            let last = i + 1 == components.len();
+            prefix = join(&prefix, component);
+            if self.matches(&prefix, if last { is_dir } else { true }) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The last matching rule of the closest `.gitignore` decides.
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        let mut dirs = vec![""];
+        dirs.extend(path.match_indices('/').map(|(i, _)| &path[..i]));
+        for dir in dirs.into_iter().rev() {
+            let Some(rules) = self.rules.get(dir) else {
+                continue;
+            };
+            let relative = strip_dir(path, dir).unwrap_or(path);
+            if let Some(rule) = rules
+                .iter()
+                .rev()
+                .find(|rule| (is_dir || !rule.dir_only) && rule.glob.is_match(relative))
+            {
+                return !rule.negated;
+            }
+        }
+        false
+    }
+}
+
+fn parse_rule(line: &str) -> Option<IgnoreRule> {
+    // Trailing spaces are dropped unless escaped
+    let mut line = line.trim_end_matches('\r');
+    while line.ends_with(' ') && !line.ends_with("\\ ") {
+        line = &line[..line.len() - 1];
+    }
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    // A pattern with a slash is anchored to the directory of the
+    // `.gitignore`; one without matches at any depth
+    let pattern = if line.contains('/') {
+        line.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{}", line)
+    };
+    let glob = Glob::new(&pattern).ok()?;
+    Some(IgnoreRule {
+        glob,
+        negated,
+        dir_only,
+    })
+}
+
+/// `path` relative to `dir`, if it is `dir` itself or inside it.
+pub fn strip_dir<'a>(path: &'a str, dir: &str) -> Option<&'a str> {
+    if dir.is_empty() {
+        return Some(path);
+    }
+    match path.strip_prefix(dir)? {
+        "" => Some(""),
+        rest => rest.strip_prefix('/'),
+    }
+}
+
+/// Join two relative paths; an empty `dir` is the root.
+pub fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else if name.is_empty() {
+        dir.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).unwrap().is_match(path)
+    }
+
+    #[test]
+    fn test_glob() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(!matches("*.rs", "src/main.rs"));
+        assert!(matches("**/*.rs", "main.rs"));
+        assert!(matches("**/*.rs", "src/a/main.rs"));
+        assert!(matches("src/**", "src/a/b"));
+        assert!(!matches("src/**", "lib/a"));
+        assert!(matches("src/**/test_*.rs", "src/x/y/test_io.rs"));
+        assert!(matches("?.txt", "a.txt"));
+        assert!(!matches("?.txt", "ab.txt"));
+        assert!(matches("[a-c]x", "bx"));
+        assert!(!matches("[!a-c]x", "bx"));
+        assert!(matches("*.{js,ts}", "index.ts"));
+        assert!(matches("{src,lib}/**/*.{js,ts}", "lib/a/b.js"));
+        assert!(matches("a\\*b", "a*b"));
+        assert!(!matches("a\\*b", "axb"));
+        assert!(matches("*", ".hidden"));
+        assert!(Glob::new("[ab").is_err());
+        assert!(Glob::new("{a,b").is_err());
+    }
+
+    #[test]
+    fn test_glob_limits() {
+        // 2^10 alternatives fit, 2^11 do not, and are refused without expanding
+        // them all
+        let pattern = "{a,b}".repeat(10);
+        assert!(matches(&pattern, "abababbbba"));
+        let error = Glob::new(&"{a,b}".repeat(11)).unwrap_err();
+        assert_eq!(error.code, GLOB_TOO_LARGE);
+        let error = Glob::new(&"{a,b,c,d}".repeat(40)).unwrap_err();
+        assert_eq!(error.code, GLOB_TOO_LARGE);
+        assert_eq!(Glob::new("{a,b").unwrap_err().code, INVALID_GLOB);
+
+        // Many `**` against a deep path that almost matches
+        let pattern = "**/a/".repeat(20) + "b";
+        let path = "a/".repeat(60) + "c";
+        let started = std::time::Instant::now();
+        assert!(!matches(&pattern, &path));
+        assert!(matches(&pattern, &("a/".repeat(60) + "b")));
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_may_match_under() {
+        let glob = Glob::new("src/**/*.rs").unwrap();
+        assert!(glob.may_match_under("src"));
+        assert!(glob.may_match_under("src/deep/er"));
+        assert!(!glob.may_match_under("target"));
+        let glob = Glob::new("*.rs").unwrap();
+        assert!(!glob.may_match_under("src"));
+    }
+
+    #[test]
+    fn test_gitignore() {
+        let mut ignore = GitIgnore::default();
+        ignore.add("", "# build output\ntarget/\n*.log\n!keep.log\n/root-only\n");
+        ignore.add("web", "node_modules\ndist/*.map\n");
+
+        assert!(ignore.is_ignored("target", true));
+        assert!(ignore.is_ignored("target/debug/app", false));
+        // `target/` only matches directories
+        assert!(!ignore.is_ignored("target", false));
+        assert!(ignore.is_ignored("a/b/trace.log", false));
+        assert!(!ignore.is_ignored("keep.log", false));
+        assert!(ignore.is_ignored("root-only", false));
+        assert!(!ignore.is_ignored("sub/root-only", false));
+        assert!(ignore.is_ignored("web/node_modules/x/index.js", false));
+        assert!(!ignore.is_ignored("node_modules/x", false));
+        assert!(ignore.is_ignored("web/dist/app.js.map", false));
+        assert!(!ignore.is_ignored("web/dist/app.js", false));
+        assert!(ignore.is_ignored(".git/config", false));
+        assert!(!ignore.is_ignored("src/main.rs", false));
+
+        ignore.rename_dir("web", "app");
+        assert!(ignore.is_ignored("app/node_modules", true));
+        assert!(!ignore.is_ignored("web/node_modules", true));
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(join("", "a"), "a");
+        assert_eq!(join("a", "b"), "a/b");
+        assert_eq!(strip_dir("a/b/c", "a/b"), Some("c"));
+        assert_eq!(strip_dir("a/bc", "a/b"), None);
+        assert_eq!(strip_dir("a", ""), Some("a"));
+    }
+}
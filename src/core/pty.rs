@@ -0,0 +1,719 @@
+//! Interactive pseudo-terminal sessions for the `/pty` WebSocket.
+//!
+//! Unlike `/shell`, a PTY session outlives the connection that started it.
+//! Output is numbered and kept in a replay buffer until the client acks it,
+//! so a client that reconnects with the session id and the last chunk id it
+//! saw picks up where it left off, the same way ACP sessions replay their
+//! buffered notifications. Sessions nobody reattaches to are killed after
+//! `DETACH_TIMEOUT`.
+#![cfg(target_os = "linux")]
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+pub type PtySessionId = String;
+pub type ChunkId = u64;
+
+/// How long a session survives without a connected client.
+pub const DETACH_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Unacknowledged output kept per session; the oldest chunks are dropped first.
+const MAX_BUFFER_BYTES: usize = 1024 * 1024;
+const READ_CHUNK_SIZE: usize = 4096;
+const EVENT_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct PtySize {
+    pub cols: u16,
+    pub rows: u16,
+}
+
+impl Default for PtySize {
+    fn default() -> Self {
+        Self { cols: 80, rows: 24 }
+    }
+}
+
+impl PtySize {
+    fn winsize(self) -> libc::winsize {
+        libc::winsize {
+            ws_row: self.rows,
+            ws_col: self.cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PtySignal {
+    #[serde(rename = "SIGINT")]
+    Interrupt,
+    #[serde(rename = "SIGTERM")]
+    Terminate,
+}
+
+impl PtySignal {
+    fn number(self) -> libc::c_int {
+        match self {
+            PtySignal::Interrupt => libc::SIGINT,
+            PtySignal::Terminate => libc::SIGTERM,
+        }
+    }
+}
+
+/// Control messages a client sends as text frames. Binary frames are raw input.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Input { data: String },
+    Resize { cols: u16, rows: u16 },
+    Signal { signal: PtySignal },
+    /// The client has everything up to and including `latest_id`
+    Ack { latest_id: ChunkId },
+    Kill,
+}
+
+/// Control messages sent to the client as text frames. Output goes out as
+/// binary frames built by `output_frame`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Session { session_id: PtySessionId },
+    Exit { status: i32 },
+    Error { error: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputChunk {
+    pub id: ChunkId,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PtyEvent {
+    Output(OutputChunk),
+    Exit(i32),
+    /// Another connection attached to the session
+    Takeover,
+}
+
+/// A binary output frame: the chunk id as 8 big-endian bytes, then the data.
+pub fn output_frame(chunk: &OutputChunk) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(8 + chunk.data.len());
+    frame.extend_from_slice(&chunk.id.to_be_bytes());
+    frame.extend_from_slice(&chunk.data);
+    frame
+}
+
+pub struct PtySpawnOptions {
+    /// Run through `/bin/sh -c`; without one, the user's login shell
+    pub command: Option<String>,
+    pub cwd: PathBuf,
+    pub env: HashMap<String, String>,
+    pub size: PtySize,
+    /// Kill the session this long after it starts, attached or not
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+struct OutputBuffer {
+    chunks: VecDeque<OutputChunk>,
+    bytes: usize,
+    last_id: ChunkId,
+    exit_status: Option<i32>,
+}
+
+impl OutputBuffer {
+    fn push(&mut self, data: Vec<u8>) -> OutputChunk {
+        self.last_id += 1;
+        let chunk = OutputChunk {
+            id: self.last_id,
+            data,
+        };
+        self.bytes += chunk.data.len();
+        self.chunks.push_back(chunk.clone());
+
+        while self.bytes > MAX_BUFFER_BYTES && self.chunks.len() > 1 {
+            if let Some(dropped) = self.chunks.pop_front() {
+                self.bytes -= dropped.data.len();
+            }
+        }
+        chunk
+    }
+
+    fn prune(&mut self, latest_id: ChunkId) {
+        while self.chunks.front().is_some_and(|chunk| chunk.id <= latest_id) {
+            if let Some(dropped) = self.chunks.pop_front() {
+                self.bytes -= dropped.data.len();
+            }
+        }
+    }
+
+    fn after(&self, latest_id: ChunkId) -> Vec<OutputChunk> {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.id > latest_id)
+            .cloned()
+            .collect()
+    }
+}
+
+/// What a connection gets when it attaches: the output it has not seen yet
+/// and a receiver for everything after that.
+pub struct Attachment {
+    pub epoch: u64,
+    pub replay: Vec<OutputChunk>,
+    pub exit_status: Option<i32>,
+    pub events: broadcast::Receiver<PtyEvent>,
+}
+
+pub struct PtySession {
+    pub id: PtySessionId,
+    pid: libc::pid_t,
+    master: File,
+    input_tx: mpsc::Sender<Vec<u8>>,
+    output: Mutex<OutputBuffer>,
+    events: broadcast::Sender<PtyEvent>,
+    attach_epoch: AtomicU64,
+}
+
+impl PtySession {
+    fn spawn(options: PtySpawnOptions) -> io::Result<Arc<Self>> {
+        let (master, slave) = open_pty(options.size)?;
+
+        let mut command = match &options.command {
+            Some(line) => {
+                let mut command = Command::new("/bin/sh");
+                command.arg("-c").arg(line);
+                command
+            }
+            None => Command::new(
+                options
+                    .env
+                    .get("SHELL")
+                    .map(String::as_str)
+                    .unwrap_or("/bin/sh"),
+            ),
+        };
+        command
+            .env_clear()
+            .envs(&options.env)
+            .current_dir(&options.cwd)
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+        if !options.env.contains_key("TERM") {
+            command.env("TERM", "xterm-256color");
+        }
+        // SAFETY: only async-signal-safe calls between fork and exec
+        unsafe {
+            command.pre_exec(|| {
+                // A new session whose controlling terminal is the PTY, so the
+                // child leads its own process group and gets job control
+                if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        // Dropping `command` closes our copies of the slave, so reads on the
+        // master fail once the child side is gone
+        let child = command.spawn()?;
+        drop(command);
+
+        let master = File::from(master);
+        let reader = master.try_clone()?;
+        let writer = master.try_clone()?;
+        let (input_tx, input_rx) = mpsc::channel();
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+
+        let session = Arc::new(Self {
+            id: Uuid::new_v4().to_string(),
+            pid: child.id() as libc::pid_t,
+            master,
+            input_tx,
+            output: Mutex::new(OutputBuffer::default()),
+            events,
+            attach_epoch: AtomicU64::new(0),
+        });
+
+        // Blocking reads and writes on the master live on their own threads;
+        // writes are queued so input keeps its order without stalling the caller
+        std::thread::spawn(move || write_input(writer, input_rx));
+        std::thread::spawn({
+            let session = session.clone();
+            move || session.read_output(reader, child)
+        });
+
+        Ok(session)
+    }
+
+    fn read_output(&self, mut reader: File, mut child: Child) {
+        let mut buf = vec![0u8; READ_CHUNK_SIZE];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => self.push_output(buf[..n].to_vec()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                // EIO once the last process holding the terminal has gone
+                Err(_) => break,
+            }
+        }
+
+        let status = match child.wait() {
+            Ok(status) => status
+                .code()
+                .unwrap_or_else(|| 128 + status.signal().unwrap_or(0)),
+            Err(e) => {
+                warn!("Failed to wait for PTY session {}: {}", self.id, e);
+                -1
+            }
+        };
+        debug!("PTY session {} exited with status {}", self.id, status);
+
+        let mut output = self.lock_output();
+        output.exit_status = Some(status);
+        let _ = self.events.send(PtyEvent::Exit(status));
+    }
+
+    fn push_output(&self, data: Vec<u8>) {
+        // Sent under the lock so an attaching client sees each chunk exactly
+        // once, either in its replay or on its receiver
+        let mut output = self.lock_output();
+        let chunk = output.push(data);
+        let _ = self.events.send(PtyEvent::Output(chunk));
+    }
+
+    fn lock_output(&self) -> std::sync::MutexGuard<'_, OutputBuffer> {
+        self.output.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Attach a connection, replaying output after `latest_id`. Any connection
+    /// attached before gets a `Takeover` event.
+    pub fn attach(&self, latest_id: ChunkId) -> Attachment {
+        let output = self.lock_output();
+        let epoch = self.attach_epoch.fetch_add(1, Ordering::SeqCst) + 1;
+        let _ = self.events.send(PtyEvent::Takeover);
+        Attachment {
+            epoch,
+            replay: output.after(latest_id),
+            exit_status: output.exit_status,
+            events: self.events.subscribe(),
+        }
+    }
+
+    pub fn is_attached_since(&self, epoch: u64) -> bool {
+        self.attach_epoch.load(Ordering::SeqCst) != epoch
+    }
+
+    pub fn buffered_after(&self, latest_id: ChunkId) -> Vec<OutputChunk> {
+        self.lock_output().after(latest_id)
+    }
+
+    pub fn ack(&self, latest_id: ChunkId) {
+        self.lock_output().prune(latest_id);
+    }
+
+    pub fn exit_status(&self) -> Option<i32> {
+        self.lock_output().exit_status
+    }
+
+    pub fn write_input(&self, data: Vec<u8>) -> io::Result<()> {
+        self.input_tx
+            .send(data)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "PTY input is closed"))
+    }
+
+    pub fn resize(&self, size: PtySize) -> io::Result<()> {
+        let winsize = size.winsize();
+        // SAFETY: TIOCSWINSZ reads a winsize from the pointer
+        if unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ, &winsize) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Signal the terminal's foreground process group, as typing ^C would.
+    pub fn signal(&self, signal: PtySignal) -> io::Result<()> {
+        // SAFETY: plain syscalls on a descriptor and process group we own
+        let group = match unsafe { libc::tcgetpgrp(self.master.as_raw_fd()) } {
+            group if group > 0 => group,
+            _ => self.pid,
+        };
+        if unsafe { libc::kill(-group, signal.number()) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Kill the session's process group. The exit is reported once the
+    /// output reader has drained the terminal.
+    pub fn kill(&self) {
+        if self.exit_status().is_some() {
+            return;
+        }
+        // SAFETY: the child leads its own process group, see `spawn`
+        if unsafe { libc::kill(-self.pid, libc::SIGKILL) } == -1 {
+            debug!(
+                "Failed to kill PTY session {}: {}",
+                self.id,
+                io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+fn write_input(mut writer: File, input_rx: mpsc::Receiver<Vec<u8>>) {
+    for data in input_rx {
+        if writer.write_all(&data).is_err() {
+            break;
+        }
+    }
+}
+
+fn open_pty(size: PtySize) -> io::Result<(OwnedFd, OwnedFd)> {
+    let (mut master, mut slave) = (-1, -1);
+    let winsize = size.winsize();
+    // SAFETY: openpty fills in two descriptors we take ownership of below
+    let result = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            &winsize,
+        )
+    };
+    if result == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let (master, slave) = unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+    for fd in [&master, &slave] {
+        // Keep the master, and stray copies of the slave, out of the child
+        if unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok((master, slave))
+}
+
+#[derive(Clone, Default)]
+pub struct PtyState {
+    pub sessions: Arc<DashMap<PtySessionId, Arc<PtySession>>>,
+}
+
+impl PtyState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&self, options: PtySpawnOptions) -> io::Result<Arc<PtySession>> {
+        let timeout = options.timeout;
+        let session = PtySession::spawn(options)?;
+        debug!("Started PTY session {} (pid {})", session.id, session.pid);
+        self.sessions.insert(session.id.clone(), session.clone());
+
+        if let Some(timeout) = timeout {
+            // Kill but keep the session, so clients still get its output and status
+            let weak = Arc::downgrade(&session);
+            tokio::spawn(async move {
+                tokio::time::sleep(timeout).await;
+                if let Some(session) = weak.upgrade().filter(|s| s.exit_status().is_none()) {
+                    debug!("PTY session {} timed out after {:?}", session.id, timeout);
+                    session.kill();
+                }
+            });
+        }
+        Ok(session)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<PtySession>> {
+        self.sessions.get(id).map(|entry| entry.value().clone())
+    }
+
+    /// Forget the session and kill whatever is still running in it.
+    pub fn remove(&self, id: &str) {
+        if let Some((_, session)) = self.sessions.remove(id) {
+            session.kill();
+        }
+    }
+
+    /// Called when the connection holding `epoch` goes away. Unless another
+    /// connection attaches within `timeout`, the session is removed.
+    pub fn detach(&self, id: &str, epoch: u64, timeout: Duration) {
+        let state = self.clone();
+        let id = id.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let Some(session) = state.get(&id) else {
+                return;
+            };
+            if !session.is_attached_since(epoch) {
+                debug!("Removing detached PTY session {}", id);
+                state.remove(&id);
+            }
+        });
+    }
+
+    pub fn clear(&self) {
+        for entry in self.sessions.iter() {
+            entry.value().kill();
+        }
+        self.sessions.clear();
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(command: &str) -> PtySpawnOptions {
+        PtySpawnOptions {
+            command: Some(command.to_string()),
+            cwd: std::env::temp_dir(),
+            env: std::env::vars().collect(),
+            size: PtySize::default(),
+            timeout: None,
+        }
+    }
+
+    /// Collect output until the session exits.
+    async fn run_to_exit(attachment: &mut Attachment) -> (String, i32) {
+        let mut output: Vec<u8> = attachment
+            .replay
+            .iter()
+            .flat_map(|chunk| chunk.data.clone())
+            .collect();
+        if let Some(status) = attachment.exit_status {
+            return (String::from_utf8_lossy(&output).into_owned(), status);
+        }
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(10), attachment.events.recv())
+                .await
+                .expect("PTY session did not exit")
+                .unwrap();
+            match event {
+                PtyEvent::Output(chunk) => output.extend(chunk.data),
+                PtyEvent::Exit(status) => {
+                    return (String::from_utf8_lossy(&output).into_owned(), status)
+                }
+                PtyEvent::Takeover => {}
+            }
+        }
+    }
+
+    async fn wait_for_output(attachment: &mut Attachment, needle: &str) {
+        let mut output = String::new();
+        while !output.contains(needle) {
+            let event = tokio::time::timeout(Duration::from_secs(10), attachment.events.recv())
+                .await
+                .expect("PTY output did not arrive")
+                .unwrap();
+            if let PtyEvent::Output(chunk) = event {
+                output.push_str(&String::from_utf8_lossy(&chunk.data));
+            }
+        }
+    }
+
+    #[test]
+    fn test_output_buffer_prunes_and_replays() {
+        let mut buffer = OutputBuffer::default();
+        for data in ["a", "b", "c"] {
+            buffer.push(data.as_bytes().to_vec());
+        }
+        let ids: Vec<ChunkId> = buffer.after(1).iter().map(|chunk| chunk.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+
+        buffer.prune(2);
+        assert_eq!(buffer.after(0).len(), 1);
+        assert_eq!(buffer.bytes, 1);
+
+        // Ids keep counting after a prune
+        assert_eq!(buffer.push(b"d".to_vec()).id, 4);
+    }
+
+    #[test]
+    fn test_output_buffer_is_bounded() {
+        let mut buffer = OutputBuffer::default();
+        for _ in 0..3 {
+            buffer.push(vec![0u8; MAX_BUFFER_BYTES / 2]);
+        }
+        assert_eq!(buffer.bytes, MAX_BUFFER_BYTES);
+        assert_eq!(buffer.after(0).first().map(|chunk| chunk.id), Some(2));
+    }
+
+    #[test]
+    fn test_client_messages() {
+        let parse = |json: &str| serde_json::from_str::<ClientMessage>(json).unwrap();
+        assert_eq!(
+            parse(r#"{"type":"resize","cols":120,"rows":40}"#),
+            ClientMessage::Resize { cols: 120, rows: 40 }
+        );
+        assert_eq!(
+            parse(r#"{"type":"signal","signal":"SIGINT"}"#),
+            ClientMessage::Signal {
+                signal: PtySignal::Interrupt
+            }
+        );
+        assert_eq!(parse(r#"{"type":"ack","latest_id":7}"#), ClientMessage::Ack { latest_id: 7 });
+        assert_eq!(parse(r#"{"type":"kill"}"#), ClientMessage::Kill);
+        assert!(serde_json::from_str::<ClientMessage>(r#"{"type":"signal","signal":"SIGKILL"}"#).is_err());
+
+        let exit = serde_json::to_string(&ServerMessage::Exit { status: 3 }).unwrap();
+        assert_eq!(exit, r#"{"type":"exit","status":3}"#);
+
+        let frame = output_frame(&OutputChunk {
+            id: 258,
+            data: b"hi".to_vec(),
+        });
+        assert_eq!(frame, vec![0, 0, 0, 0, 0, 0, 1, 2, b'h', b'i']);
+    }
+
+    #[tokio::test]
+    async fn test_runs_in_a_terminal() {
+        let state = PtyState::new();
+        let session = state.spawn(options("test -t 0 && echo tty; exit 3")).unwrap();
+        let mut attachment = session.attach(0);
+
+        let (output, status) = run_to_exit(&mut attachment).await;
+        assert!(output.contains("tty"), "{:?}", output);
+        assert_eq!(status, 3);
+    }
+
+    #[tokio::test]
+    async fn test_input_and_resize() {
+        let state = PtyState::new();
+        let session = state
+            .spawn(options("read line; stty size; echo \"got $line\""))
+            .unwrap();
+        let mut attachment = session.attach(0);
+
+        session.resize(PtySize { cols: 120, rows: 40 }).unwrap();
+        session.write_input(b"hello\n".to_vec()).unwrap();
+
+        let (output, status) = run_to_exit(&mut attachment).await;
+        assert!(output.contains("40 120"), "{:?}", output);
+        assert!(output.contains("got hello"), "{:?}", output);
+        assert_eq!(status, 0);
+    }
+
+    #[tokio::test]
+    async fn test_signals_reach_the_foreground_group() {
+        let state = PtyState::new();
+        let session = state
+            .spawn(options(
+                "trap 'echo interrupted; exit 7' INT; echo ready; while :; do sleep 0.05; done",
+            ))
+            .unwrap();
+        let mut attachment = session.attach(0);
+        wait_for_output(&mut attachment, "ready").await;
+
+        session.signal(PtySignal::Interrupt).unwrap();
+        let (output, status) = run_to_exit(&mut attachment).await;
+        assert!(output.contains("interrupted"), "{:?}", output);
+        assert_eq!(status, 7);
+    }
+
+    #[tokio::test]
+    async fn test_reattach_replays_unacked_output() {
+        let state = PtyState::new();
+        let session = state.spawn(options("echo one; read _; echo two")).unwrap();
+        let mut first = session.attach(0);
+        wait_for_output(&mut first, "one").await;
+
+        // The first connection drops after seeing "one" without acking it
+        let seen = session.buffered_after(0).last().unwrap().id;
+        let mut second = session.attach(0);
+        assert_eq!(first.events.recv().await.unwrap(), PtyEvent::Takeover);
+        assert!(session.is_attached_since(first.epoch));
+        assert!(String::from_utf8_lossy(&second.replay[0].data).contains("one"));
+
+        // Acked output is not replayed again
+        session.ack(seen);
+        assert!(session.buffered_after(0).is_empty());
+
+        session.write_input(b"\n".to_vec()).unwrap();
+        second.replay.clear();
+        let (output, status) = run_to_exit(&mut second).await;
+        assert!(output.contains("two"), "{:?}", output);
+        assert_eq!(status, 0);
+
+        // A client reattaching after the exit still learns the status
+        let late = state.get(&session.id).unwrap().attach(seen);
+        assert_eq!(late.exit_status, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_kill_and_detach_clean_up() {
+        let state = PtyState::new();
+        let session = state.spawn(options("sleep 30")).unwrap();
+        let mut attachment = session.attach(0);
+
+        state.remove(&session.id);
+        assert!(state.get(&session.id).is_none());
+        let (_, status) = run_to_exit(&mut attachment).await;
+        assert_eq!(status, 128 + libc::SIGKILL);
+
+        let detached = state.spawn(options("sleep 30")).unwrap();
+        let mut attachment = detached.attach(0);
+        state.detach(&detached.id, attachment.epoch, Duration::from_millis(10));
+        let (_, status) = run_to_exit(&mut attachment).await;
+        assert_eq!(status, 128 + libc::SIGKILL);
+        assert!(state.sessions.is_empty());
+
+        // Reattaching in time keeps the session alive
+        let kept = state.spawn(options("sleep 30")).unwrap();
+        let old = kept.attach(0);
+        state.detach(&kept.id, old.epoch, Duration::from_millis(10));
+        let _new = kept.attach(0);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(state.get(&kept.id).is_some());
+        state.clear();
+        assert!(state.sessions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_the_session() {
+        let state = PtyState::new();
+        let session = state
+            .spawn(PtySpawnOptions {
+                timeout: Some(Duration::from_millis(100)),
+                ..options("echo started; sleep 30")
+            })
+            .unwrap();
+        let mut attachment = session.attach(0);
+        let (output, status) = run_to_exit(&mut attachment).await;
+        assert!(output.contains("started"), "{:?}", output);
+        assert_eq!(status, 128 + libc::SIGKILL);
+        // Still there for a client that reattaches to read what happened
+        assert_eq!(state.get(&session.id).unwrap().exit_status(), Some(128 + libc::SIGKILL));
+
+        let quick = state
+            .spawn(PtySpawnOptions {
+                timeout: Some(Duration::from_secs(30)),
+                ..options("exit 3")
+            })
+            .unwrap();
+        let (_, status) = run_to_exit(&mut quick.attach(0)).await;
+        assert_eq!(status, 3);
+        state.clear();
+    }
+}
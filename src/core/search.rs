@@ -0,0 +1,544 @@
+//! Directory listing, glob and content search for `/listdir`, `/glob` and
+//! `/grep`.
+//!
+//! All three walk a directory the same way: entries in name order, symlinks
+//! reported but never followed, and, when asked, `.git` and files excluded by
+//! `.gitignore` left out. Paths in results are relative to the directory
+//! searched, with `/` as the separator.
+
+use crate::file_io::{mtime_ns, serialize_ns};
+use crate::path_match::{join, GitIgnore, Glob};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::fs::{FileType, Metadata};
+use std::io;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+pub const DEFAULT_LIST_LIMIT: usize = 10_000;
+pub const DEFAULT_GLOB_LIMIT: usize = 1_000;
+pub const DEFAULT_GREP_LIMIT: usize = 500;
+/// Upper bound for any requested result limit.
+pub const MAX_RESULTS: usize = 100_000;
+/// Context lines on each side of a match are capped at this.
+pub const MAX_CONTEXT_LINES: usize = 20;
+/// Larger files are not searched.
+pub const MAX_GREP_FILE_BYTES: u64 = 8 * 1024 * 1024;
+/// Longer lines are cut in grep results.
+const MAX_LINE_BYTES: usize = 1024;
+/// Files with a NUL byte in this many leading bytes are taken as binary.
+const BINARY_CHECK_BYTES: usize = 8192;
+const REGEX_SIZE_LIMIT: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    /// How deep to go; 1 is the directory's own entries, `None` is no limit
+    pub max_depth: Option<usize>,
+    /// Leave out `.git` and what `.gitignore` files exclude
+    pub gitignore: bool,
+}
+
+pub struct WalkEntry<'a> {
+    pub path: &'a Path,
+    pub relative: &'a str,
+    /// Of the entry itself, not a symlink's target
+    pub metadata: &'a Metadata,
+}
+
+/// Results up to a limit, and whether there were more.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limited<T> {
+    pub items: Vec<T>,
+    pub truncated: bool,
+    limit: usize,
+}
+
+impl<T> Limited<T> {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            items: Vec::new(),
+            truncated: false,
+            limit,
+        }
+    }
+
+    /// Add `item`, or stop once the limit is reached.
+    fn push(&mut self, item: T) -> ControlFlow<()> {
+        if self.items.len() >= self.limit {
+            self.truncated = true;
+            return ControlFlow::Break(());
+        }
+        self.items.push(item);
+        ControlFlow::Continue(())
+    }
+}
+
+/// Walk `root` depth first. Directories are entered only if `descend`
+/// agrees; `visit` can end the walk early. Only an unreadable `root` is an
+/// error, unreadable directories below it are skipped.
+pub fn walk(
+    root: &Path,
+    options: &WalkOptions,
+    mut descend: impl FnMut(&str) -> bool,
+    mut visit: impl FnMut(&WalkEntry) -> ControlFlow<()>,
+) -> io::Result<()> {
+    let mut walker = Walker {
+        options,
+        ignore: options.gitignore.then(GitIgnore::default),
+    };
+    let entries = walker.read_dir(root, "")?;
+    // Stopping early is not an error
+    let _ = walker.walk_entries(entries, "", 1, &mut descend, &mut visit);
+    Ok(())
+}
+
+struct Walker<'a> {
+    options: &'a WalkOptions,
+    ignore: Option<GitIgnore>,
+}
+
+impl Walker<'_> {
+    fn read_dir(&mut self, path: &Path, relative: &str) -> io::Result<Vec<std::fs::DirEntry>> {
+        let mut entries: Vec<_> = std::fs::read_dir(path)?.flatten().collect();
+        entries.sort_by_key(|entry| entry.file_name());
+        if let Some(ignore) = &mut self.ignore {
+            let rules = std::fs::read_to_string(path.join(".gitignore")).unwrap_or_default();
+            ignore.add(relative, &rules);
+        }
+        Ok(entries)
+    }
+
+    fn walk_entries(
+        &mut self,
+        entries: Vec<std::fs::DirEntry>,
+        dir: &str,
+        depth: usize,
+        descend: &mut impl FnMut(&str) -> bool,
+        visit: &mut impl FnMut(&WalkEntry) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        for entry in entries {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let relative = join(dir, &entry.file_name().to_string_lossy());
+            let is_dir = metadata.is_dir();
+            if self.is_excluded(&relative, is_dir) {
+                continue;
+            }
+            let path = entry.path();
+            visit(&WalkEntry {
+                path: &path,
+                relative: &relative,
+                metadata: &metadata,
+            })?;
+
+            let deeper = self.options.max_depth.is_none_or(|max| depth < max);
+            if is_dir && deeper && descend(&relative) {
+                if let Ok(entries) = self.read_dir(&path, &relative) {
+                    self.walk_entries(entries, &relative, depth + 1, descend, visit)?;
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn is_excluded(&self, path: &str, is_dir: bool) -> bool {
+        self.ignore
+            .as_ref()
+            .is_some_and(|ignore| ignore.is_ignored(path, is_dir))
+    }
+}
+
+pub fn entry_type(file_type: FileType) -> &'static str {
+    if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_file() {
+        "file"
+    } else if file_type.is_symlink() {
+        "symlink"
+    } else {
+        "other"
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListEntry {
+    pub name: String,
+    /// Relative to the listed directory
+    pub path: String,
+    /// Base64 of the path's bytes if it is not valid UTF-8, in which case
+    /// `name` and `path` are lossy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_path: Option<String>,
+    #[serde(rename = "type")]
+    pub entry_type: &'static str,
+    pub size: u64,
+    #[serde(serialize_with = "serialize_ns")]
+    pub mtime_ns: u64,
+    /// Permission bits in octal, e.g. `0644`
+    pub permissions: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symlink_target: Option<String>,
+}
+
+pub fn list_dir(
+    root: &Path,
+    options: &WalkOptions,
+    limit: usize,
+) -> io::Result<Limited<ListEntry>> {
+    let mut entries = Limited::new(limit);
+    walk(
+        root,
+        options,
+        |_| true,
+        |entry| entries.push(list_entry(root, entry)),
+    )?;
+    Ok(entries)
+}
+
+fn list_entry(root: &Path, entry: &WalkEntry) -> ListEntry {
+    let relative = entry.path.strip_prefix(root).unwrap_or(entry.path);
+    let raw_path = match relative.to_str() {
+        Some(_) => None,
+        None => Some(STANDARD.encode(relative.as_os_str().as_encoded_bytes())),
+    };
+    let symlink_target = if entry.metadata.is_symlink() {
+        std::fs::read_link(entry.path)
+            .ok()
+            .map(|target| target.to_string_lossy().into_owned())
+    } else {
+        None
+    };
+    ListEntry {
+        name: entry
+            .relative
+            .rsplit('/')
+            .next()
+            .unwrap_or(entry.relative)
+            .to_string(),
+        path: entry.relative.to_string(),
+        raw_path,
+        entry_type: entry_type(entry.metadata.file_type()),
+        size: entry.metadata.len(),
+        mtime_ns: mtime_ns(entry.metadata).unwrap_or_default(),
+        permissions: permissions(entry.metadata),
+        symlink_target,
+    }
+}
+
+#[cfg(unix)]
+fn permissions(metadata: &Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    format!("{:04o}", metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn permissions(metadata: &Metadata) -> String {
+    let mode = if metadata.permissions().readonly() { 0o444 } else { 0o644 };
+    format!("{:04o}", mode)
+}
+
+/// Paths of the files (not directories) under `root` matching `glob`.
+pub fn glob(
+    root: &Path,
+    glob: &Glob,
+    options: &WalkOptions,
+    limit: usize,
+) -> io::Result<Limited<String>> {
+    let mut matches = Limited::new(limit);
+    walk(
+        root,
+        options,
+        |dir| glob.may_match_under(dir),
+        |entry| {
+            if entry.metadata.is_dir() || !glob.is_match(entry.relative) {
+                return ControlFlow::Continue(());
+            }
+            matches.push(entry.relative.to_string())
+        },
+    )?;
+    Ok(matches)
+}
+
+pub fn build_regex(pattern: &str, literal: bool, case_insensitive: bool) -> Result<Regex, String> {
+    let pattern = if literal {
+        regex::escape(pattern)
+    } else {
+        pattern.to_string()
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(case_insensitive)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GrepOptions {
+    pub walk: WalkOptions,
+    /// Only search files matching this
+    pub glob: Option<Glob>,
+    /// Lines of context before and after each match
+    pub context: usize,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GrepMatch {
+    pub path: String,
+    /// 1-based
+    pub line_number: usize,
+    pub line: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+}
+
+/// Search the text files under `root`, or `root` itself if it is a file.
+/// Binary files and files over `MAX_GREP_FILE_BYTES` are skipped.
+pub fn grep(root: &Path, regex: &Regex, options: &GrepOptions) -> io::Result<Limited<GrepMatch>> {
+    let mut matches = Limited::new(options.limit);
+    let context = options.context.min(MAX_CONTEXT_LINES);
+    let metadata = std::fs::metadata(root)?;
+    if metadata.is_file() {
+        let name = root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let _ = grep_file(root, &name, regex, context, &mut matches);
+        return Ok(matches);
+    }
+
+    walk(
+        root,
+        &options.walk,
+        |dir| options.glob.as_ref().is_none_or(|glob| glob.may_match_under(dir)),
+        |entry| {
+            let wanted = options
+                .glob
+                .as_ref()
+                .is_none_or(|glob| glob.is_match(entry.relative));
+            if !entry.metadata.is_file() || entry.metadata.len() > MAX_GREP_FILE_BYTES || !wanted {
+                return ControlFlow::Continue(());
+            }
+            grep_file(entry.path, entry.relative, regex, context, &mut matches)
+        },
+    )?;
+    Ok(matches)
+}
+
+fn grep_file(
+    path: &Path,
+    relative: &str,
+    regex: &Regex,
+    context: usize,
+    matches: &mut Limited<GrepMatch>,
+) -> ControlFlow<()> {
+    let Ok(data) = std::fs::read(path) else {
+        return ControlFlow::Continue(());
+    };
+    if data[..data.len().min(BINARY_CHECK_BYTES)].contains(&0) {
+        return ControlFlow::Continue(());
+    }
+    let text = String::from_utf8_lossy(&data);
+    let lines: Vec<&str> = text.lines().collect();
+    for (index, line) in lines.iter().enumerate() {
+        if !regex.is_match(line) {
+            continue;
+        }
+        let before = &lines[index.saturating_sub(context)..index];
+        let after = &lines[index + 1..(index + 1 + context).min(lines.len())];
+        matches.push(GrepMatch {
+            path: relative.to_string(),
+            line_number: index + 1,
+            line: clip(line),
+            before: before.iter().map(|line| clip(line)).collect(),
+            after: after.iter().map(|line| clip(line)).collect(),
+        })?;
+    }
+    ControlFlow::Continue(())
+}
+
+/// `line` cut to `MAX_LINE_BYTES` on a character boundary.
+fn clip(line: &str) -> String {
+    if line.len() <= MAX_LINE_BYTES {
+        return line.to_string();
+    }
+    let mut end = MAX_LINE_BYTES;
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    line[..end].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn tree() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let write = |path: &str, contents: &str| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write(".gitignore", "target/\n*.log\n");
+        write("README.md", "# demo\n");
+        write("src/main.rs", "fn main() {\n    run();\n}\n\nfn run() {}\n");
+        write("src/util/mod.rs", "pub fn helper() {}\n");
+        write("target/debug/app", "build output");
+        write("debug.log", "fn main() in a log\n");
+        write(".git/HEAD", "ref: refs/heads/main\n");
+        dir
+    }
+
+    fn paths(listing: &Limited<ListEntry>) -> Vec<&str> {
+        listing.items.iter().map(|entry| entry.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_list_dir() {
+        let dir = tree();
+        let one_level = WalkOptions {
+            max_depth: Some(1),
+            gitignore: false,
+        };
+        let listing = list_dir(dir.path(), &one_level, DEFAULT_LIST_LIMIT).unwrap();
+        assert_eq!(
+            paths(&listing),
+            vec![".git", ".gitignore", "README.md", "debug.log", "src", "target"]
+        );
+        let readme = &listing.items[2];
+        assert_eq!(readme.name, "README.md");
+        assert_eq!((readme.entry_type, readme.size), ("file", 7));
+        assert!(readme.mtime_ns > 0);
+        assert_eq!(listing.items[4].permissions.len(), 4);
+
+        let recursive = WalkOptions {
+            max_depth: None,
+            gitignore: true,
+        };
+        let listing = list_dir(dir.path(), &recursive, DEFAULT_LIST_LIMIT).unwrap();
+        assert_eq!(
+            paths(&listing),
+            vec![".gitignore", "README.md", "src", "src/main.rs", "src/util", "src/util/mod.rs"]
+        );
+        assert_eq!(listing.items[5].name, "mod.rs");
+
+        let two_levels = WalkOptions {
+            max_depth: Some(2),
+            gitignore: true,
+        };
+        let listing = list_dir(dir.path(), &two_levels, 4).unwrap();
+        assert_eq!(paths(&listing), vec![".gitignore", "README.md", "src", "src/main.rs"]);
+        assert!(listing.truncated);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_dir_special_names() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(OsStr::from_bytes(b"bad\xffname")), "").unwrap();
+        std::os::unix::fs::symlink("missing", dir.path().join("link")).unwrap();
+        let listing = list_dir(dir.path(), &WalkOptions::default(), DEFAULT_LIST_LIMIT).unwrap();
+
+        let bad = &listing.items[0];
+        assert_eq!(bad.name, "bad\u{fffd}name");
+        assert_eq!(bad.raw_path.as_deref(), Some(STANDARD.encode(b"bad\xffname").as_str()));
+        let link = &listing.items[1];
+        assert_eq!(link.entry_type, "symlink");
+        assert_eq!(link.symlink_target.as_deref(), Some("missing"));
+        assert_eq!(link.raw_path, None);
+    }
+
+    #[test]
+    fn test_glob() {
+        let dir = tree();
+        let options = WalkOptions {
+            max_depth: None,
+            gitignore: true,
+        };
+        let rust = Glob::new("**/*.rs").unwrap();
+        let found = glob(dir.path(), &rust, &options, DEFAULT_GLOB_LIMIT).unwrap();
+        assert_eq!(found.items, vec!["src/main.rs", "src/util/mod.rs"]);
+        assert!(!found.truncated);
+
+        let everything = Glob::new("**").unwrap();
+        let found = glob(dir.path(), &everything, &options, 2).unwrap();
+        assert_eq!(found.items, vec![".gitignore", "README.md"]);
+        assert!(found.truncated);
+
+        let ignored = Glob::new("*.log").unwrap();
+        assert!(glob(dir.path(), &ignored, &options, 10).unwrap().items.is_empty());
+        let options = WalkOptions::default();
+        assert_eq!(glob(dir.path(), &ignored, &options, 10).unwrap().items, vec!["debug.log"]);
+    }
+
+    #[test]
+    fn test_grep() {
+        let dir = tree();
+        std::fs::write(dir.path().join("src/blob.bin"), b"fn main\0binary").unwrap();
+        let options = GrepOptions {
+            walk: WalkOptions {
+                max_depth: None,
+                gitignore: true,
+            },
+            glob: None,
+            context: 1,
+            limit: DEFAULT_GREP_LIMIT,
+        };
+        let regex = build_regex(r"fn \w+\(", false, false).unwrap();
+        let found = grep(dir.path(), &regex, &options).unwrap();
+        assert_eq!(
+            found.items[0],
+            GrepMatch {
+                path: "src/main.rs".to_string(),
+                line_number: 1,
+                line: "fn main() {".to_string(),
+                before: vec![],
+                after: vec!["    run();".to_string()],
+            }
+        );
+        let lines: Vec<_> = found
+            .items
+            .iter()
+            .map(|m| (m.path.as_str(), m.line_number))
+            .collect();
+        assert_eq!(lines, vec![("src/main.rs", 1), ("src/main.rs", 5), ("src/util/mod.rs", 1)]);
+        assert_eq!(found.items[1].before, vec![""]);
+
+        let options = GrepOptions {
+            glob: Some(Glob::new("src/util/**").unwrap()),
+            ..options
+        };
+        let found = grep(dir.path(), &regex, &options).unwrap();
+        assert_eq!(found.items.len(), 1);
+
+        let options = GrepOptions { limit: 1, glob: None, ..options };
+        let found = grep(dir.path(), &regex, &options).unwrap();
+        assert_eq!(found.items.len(), 1);
+        assert!(found.truncated);
+
+        let literal = build_regex("RUN()", true, true).unwrap();
+        let found = grep(&dir.path().join("src/main.rs"), &literal, &options).unwrap();
+        assert_eq!(found.items[0].path, "main.rs");
+        assert_eq!(found.items[0].line_number, 2);
+        assert!(build_regex("(", false, false).is_err());
+    }
+
+    #[test]
+    fn test_clip() {
+        let long = "é".repeat(MAX_LINE_BYTES);
+        let clipped = clip(&long);
+        assert!(clipped.len() <= MAX_LINE_BYTES);
+        assert!(clipped.chars().all(|c| c == 'é'));
+        assert_eq!(clip("short"), "short");
+    }
+}
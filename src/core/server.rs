@@ -1,28 +1,53 @@
+use crate::auth::AuthToken;
 use crate::command::{create_shell_command, spawn_command};
 use crate::config::Config;
+use crate::edit::{apply_edits, EditFailure, EditOp, EDIT_CONFLICT};
+use crate::file_io::{
+    decode, deserialize_ns, encode, file_sha256, mtime_ns, read_range, serialize_ns, sha256_hex,
+    write_atomic, AtomicFile, ByteRange, Conflict, Encoding, PathLocks, Precondition,
+    EXCLUSIVE_UNSUPPORTED, FILE_EXISTS, MAX_INLINE_BYTES, MAX_UPLOAD_BYTES,
+};
 use crate::http_handlers::{client_proxy_handler, download_handler, proxy_handler, DownloadState};
+use crate::path_match::{Glob, GlobError, GLOB_TOO_LARGE};
+#[cfg(target_os = "linux")]
+use crate::pty::{
+    output_frame, ChunkId, ClientMessage, PtyEvent, PtySession, PtySize, PtySpawnOptions,
+    PtyState, ServerMessage, DETACH_TIMEOUT,
+};
+use crate::search::{
+    self, GrepMatch, GrepOptions, ListEntry, WalkOptions, DEFAULT_GLOB_LIMIT, DEFAULT_GREP_LIMIT,
+    DEFAULT_LIST_LIMIT, MAX_RESULTS,
+};
+use crate::shell_policy::{PolicyDenial, ShellPolicy};
+use crate::shell_protocol::{OutputBudget, OutputStream, ResourceLimits, ShellProtocol};
 use crate::utils::{load_tls_config_from_paths, normalize_path, wslpath_to_windows};
+#[cfg(target_os = "linux")]
+use crate::watch::{WatchChannelState, WatchReply, WatchSession};
+use crate::workspace::{Access, AccessDenied, Workspace};
 use axum::{
     body::{Body, Bytes},
-    extract::{Json, Query, Request},
-    http::{header, StatusCode},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Json, Query, Request, State,
+    },
+    http::{header, HeaderValue, StatusCode},
     middleware,
-    response::{Html, Response},
+    response::{Html, IntoResponse, Response},
     routing::{get, post},
-    Router,
+    Extension, Router,
 };
-use bytes::BytesMut;
 use reqwest::{Client, Url};
 use rustls::ClientConfig;
 use rustls_platform_verifier::ConfigVerifierExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use std::sync::Arc;
-use std::time::{Duration, UNIX_EPOCH};
-use tokio::io::AsyncReadExt;
+use std::sync::{Arc, PoisonError};
+use std::time::Duration;
+use futures::StreamExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tracing::{debug, error, info};
 use which;
 
@@ -31,11 +56,36 @@ struct ShellParams {
     command: String,
     cwd: Option<String>,
     env: Option<HashMap<String, String>>,
+    /// Frame protocol of the response, see `crate::shell_protocol`
+    #[serde(default)]
+    protocol: ShellProtocol,
+    timeout_ms: Option<u64>,
+    /// Cap on stdout and stderr together; the command is stopped past it
+    max_output_bytes: Option<u64>,
+    #[serde(default)]
+    rlimits: ResourceLimits,
     #[serde(default)]
     #[allow(dead_code)]
     is_wsl: bool,
 }
 
+#[cfg(target_os = "linux")]
+#[derive(Deserialize)]
+struct PtyParams {
+    /// Reattach to this session instead of starting a new one
+    session_id: Option<String>,
+    /// Last output chunk the client has seen, when reattaching
+    #[serde(default)]
+    latest_id: ChunkId,
+    /// Without a command the PTY runs the user's shell, if the policy allows it
+    command: Option<String>,
+    cwd: Option<String>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+    /// Kill the session after this long; the policy's timeout still applies
+    timeout_ms: Option<u64>,
+}
+
 #[derive(Deserialize)]
 struct StatParams {
     path: String,
@@ -48,6 +98,50 @@ struct StatParams {
 struct ListDirParams {
     path: String,
     #[serde(default)]
+    recursive: bool,
+    /// Levels below `path` when recursive, unlimited by default
+    max_depth: Option<usize>,
+    /// Leave out `.git` and entries excluded by `.gitignore`
+    #[serde(default)]
+    gitignore: bool,
+    limit: Option<usize>,
+    #[serde(default)]
+    #[allow(dead_code)]
+    is_wsl: bool,
+}
+
+#[derive(Deserialize)]
+struct GlobParams {
+    /// Directory to search in; `pattern` is relative to it
+    path: String,
+    pattern: String,
+    /// Respect `.gitignore`, on by default
+    gitignore: Option<bool>,
+    limit: Option<usize>,
+    #[serde(default)]
+    #[allow(dead_code)]
+    is_wsl: bool,
+}
+
+#[derive(Deserialize)]
+struct GrepParams {
+    /// Directory or file to search
+    path: String,
+    pattern: String,
+    /// Match `pattern` as plain text rather than a regex
+    #[serde(default)]
+    literal: bool,
+    #[serde(default)]
+    case_insensitive: bool,
+    /// Only search files matching this glob
+    glob: Option<String>,
+    /// Lines of context before and after each match
+    #[serde(default)]
+    context: usize,
+    /// Respect `.gitignore`, on by default
+    gitignore: Option<bool>,
+    limit: Option<usize>,
+    #[serde(default)]
     #[allow(dead_code)]
     is_wsl: bool,
 }
@@ -63,6 +157,10 @@ struct MkdirParams {
 #[derive(Deserialize)]
 struct ReadFileParams {
     path: String,
+    /// Without one, text if the bytes are UTF-8 and base64 otherwise
+    encoding: Option<Encoding>,
+    #[serde(flatten)]
+    range: ByteRange,
     #[serde(default)]
     #[allow(dead_code)]
     is_wsl: bool,
@@ -72,6 +170,10 @@ struct ReadFileParams {
 struct WriteFileParams {
     path: String,
     content: String,
+    /// How `content` is encoded, UTF-8 text unless given
+    encoding: Option<Encoding>,
+    #[serde(flatten)]
+    precondition: Precondition,
     #[serde(default)]
     exclusive: bool,
     #[serde(default)]
@@ -79,6 +181,44 @@ struct WriteFileParams {
     is_wsl: bool,
 }
 
+#[derive(Deserialize)]
+struct EditFileParams {
+    path: String,
+    /// Applied in order; if one fails, the file is left untouched
+    edits: Vec<EditOp>,
+    #[serde(flatten)]
+    precondition: Precondition,
+    #[serde(default)]
+    #[allow(dead_code)]
+    is_wsl: bool,
+}
+
+// Query strings cannot use `#[serde(flatten)]` for numeric fields, so the
+// raw routes list their fields out
+#[derive(Deserialize)]
+struct RawReadParams {
+    path: String,
+    #[serde(default)]
+    offset: u64,
+    length: Option<u64>,
+    #[serde(default)]
+    #[allow(dead_code)]
+    is_wsl: bool,
+}
+
+#[derive(Deserialize)]
+struct RawWriteParams {
+    path: String,
+    #[serde(default)]
+    exclusive: bool,
+    if_match: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_ns")]
+    if_mtime_ns: Option<u64>,
+    #[serde(default)]
+    #[allow(dead_code)]
+    is_wsl: bool,
+}
+
 #[derive(Deserialize)]
 struct DeleteFileParams {
     path: String,
@@ -111,6 +251,8 @@ enum StatResponse {
     StatResponseOk {
         success: bool,
         mtime: u64,
+        #[serde(serialize_with = "serialize_ns")]
+        mtime_ns: u64,
         #[serde(rename = "type")]
         path_type: String,
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -122,19 +264,14 @@ enum StatResponse {
     },
 }
 
-#[derive(Serialize)]
-struct DirEntry {
-    name: String,
-    #[serde(rename = "type")]
-    entry_type: String,
-}
-
 #[derive(Serialize)]
 #[serde(untagged)]
 enum ListDirResponse {
     ListDirResponseOk {
         success: bool,
-        entries: Vec<DirEntry>,
+        entries: Vec<ListEntry>,
+        /// More entries than `limit`
+        truncated: bool,
     },
     ListDirResponseErr {
         success: bool,
@@ -142,6 +279,34 @@ enum ListDirResponse {
     },
 }
 
+#[derive(Serialize)]
+#[serde(untagged)]
+enum GlobResponse {
+    GlobResponseOk {
+        success: bool,
+        matches: Vec<String>,
+        truncated: bool,
+    },
+    GlobResponseErr {
+        success: bool,
+        error: String,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum GrepResponse {
+    GrepResponseOk {
+        success: bool,
+        matches: Vec<GrepMatch>,
+        truncated: bool,
+    },
+    GrepResponseErr {
+        success: bool,
+        error: String,
+    },
+}
+
 #[derive(Serialize)]
 #[serde(untagged)]
 enum MkdirResponse {
@@ -155,7 +320,15 @@ enum ReadFileResponse {
     ReadFileResponseOk {
         success: bool,
         content: String,
+        encoding: Encoding,
+        /// Size of the whole file, which may be more than was read
+        size: u64,
+        /// Hash of the whole file, for `if_match`; only sent when all of it was read
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sha256: Option<String>,
         mtime: u64,
+        #[serde(serialize_with = "serialize_ns")]
+        mtime_ns: u64,
     },
     ReadFileResponseErr {
         success: bool,
@@ -169,7 +342,10 @@ enum WriteFileResponse {
     WriteFileResponseOk {
         success: bool,
         bytes_written: usize,
+        sha256: String,
         mtime: u64,
+        #[serde(serialize_with = "serialize_ns")]
+        mtime_ns: u64,
     },
     WriteFileResponseErr {
         success: bool,
@@ -177,6 +353,30 @@ enum WriteFileResponse {
     },
 }
 
+#[derive(Serialize)]
+#[serde(untagged)]
+enum EditFileResponse {
+    EditFileResponseOk {
+        success: bool,
+        sha256: String,
+        mtime: u64,
+        #[serde(serialize_with = "serialize_ns")]
+        mtime_ns: u64,
+    },
+    EditFileResponseErr {
+        success: bool,
+        error: String,
+    },
+}
+
+#[derive(Serialize)]
+struct FileError {
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
+    error: String,
+}
+
 #[derive(Serialize)]
 #[serde(untagged)]
 enum DeleteFileResponse {
@@ -215,6 +415,11 @@ struct ServerConfig {
     allowed_origins: Vec<String>,
     port: u16,
     https_port: Option<u16>,
+    workspace: Workspace,
+    token: AuthToken,
+    shell_policy: Arc<ShellPolicy>,
+    /// Held from a write's precondition check until its rename
+    write_locks: PathLocks,
 }
 
 pub async fn start_http_server(
@@ -272,6 +477,32 @@ async fn serve_http_server_inner(
     };
 
     let https_port = config.https_port;
+
+    // The file API only reaches into these directories
+    let workspace = Workspace::from_specs(&config.workspace_roots)?;
+    for root in workspace.roots() {
+        info!(
+            "Serving workspace root {}{}",
+            root.path.display(),
+            if root.read_only { " (read-only)" } else { "" }
+        );
+    }
+
+    let shell_policy = match &config.shell_policy_file {
+        Some(path) => ShellPolicy::load(path)?,
+        None => ShellPolicy::default(),
+    };
+
+    // A new token every launch; clients read it from the file or the terminal
+    let token = AuthToken::generate();
+    match &config.token_file {
+        Some(path) => {
+            token.write_to(path)?;
+            info!("Wrote access token to {}", path.display());
+        }
+        None => eprintln!("Access token: {}", token.as_str()),
+    }
+
     let mcp_state = crate::mcp_remote::McpRemoteState::new();
     let acp_state = crate::acp_proxy::AcpProxyState::new();
     let download_state = DownloadState::new();
@@ -291,6 +522,10 @@ async fn serve_http_server_inner(
         allowed_origins,
         port,
         https_port,
+        workspace,
+        token,
+        shell_policy: Arc::new(shell_policy),
+        write_locks: PathLocks::default(),
     };
 
     // Create the MCP routes that need state
@@ -342,6 +577,21 @@ async fn serve_http_server_inner(
         .route("/socket/websocket", get(crate::ws::ws_handler))
         .with_state(ws_state.clone());
 
+    // File change subscriptions, dropped with the connection that made them
+    #[cfg(target_os = "linux")]
+    let watch_channel_state = WatchChannelState::new(server_config.workspace.clone());
+    #[cfg(target_os = "linux")]
+    let watch_routes = Router::new()
+        .route("/socket/watch", get(watch_handler))
+        .with_state(watch_channel_state.clone());
+
+    #[cfg(target_os = "linux")]
+    let pty_state = PtyState::new();
+    #[cfg(target_os = "linux")]
+    let pty_routes = Router::new()
+        .route("/pty", get(pty_handler))
+        .with_state(pty_state.clone());
+
     // Create the main app without state
     let client_for_proxy = client.clone();
     let mut app = Router::new()
@@ -350,9 +600,14 @@ async fn serve_http_server_inner(
         .route("/check-origin", post(check_origin_handler))
         .route("/read", post(read_file_handler))
         .route("/write", post(write_file_handler))
+        .route("/edit", post(edit_file_handler))
+        .route("/read/raw", get(raw_read_handler))
+        .route("/write/raw", post(raw_write_handler))
         .route("/delete", post(delete_file_handler))
         .route("/stat", get(stat_handler))
         .route("/listdir", get(listdir_handler))
+        .route("/glob", get(glob_handler))
+        .route("/grep", post(grep_handler))
         .route("/mkdir", post(mkdir_handler))
         .route("/shell", post(shell_handler))
         .route("/which", post(which_handler))
@@ -369,6 +624,13 @@ async fn serve_http_server_inner(
         .merge(ws_routes)
         .merge(mcp_channel_post_routes);
 
+    // Interactive terminals need a PTY and watches need inotify, which only
+    // the Linux build provides
+    #[cfg(target_os = "linux")]
+    {
+        app = app.merge(pty_routes).merge(watch_routes);
+    }
+
     // Add dev mode proxy routes if TIDEWAVE_CLIENT_PROXY=1 and
     // TIDEWAVE_CLIENT_URL is set
     if env::var("TIDEWAVE_CLIENT_PROXY").as_deref() == Ok("1") {
@@ -388,10 +650,7 @@ async fn serve_http_server_inner(
         }
     }
 
-    let app = app.layer(middleware::from_fn(move |mut req: Request, next| {
-        req.extensions_mut().insert(server_config.clone());
-        verify_origin(req, next)
-    }));
+    let app = with_auth(app, server_config);
 
     // Optionally set up HTTPS server
     let (https_handle, https_task) = if let Some(https_port) = https_port {
@@ -485,9 +744,24 @@ async fn serve_http_server_inner(
     // Clear all WebSocket state (includes ACP channel process cleanup)
     ws_state.clear().await;
 
+    #[cfg(target_os = "linux")]
+    pty_state.clear();
+    #[cfg(target_os = "linux")]
+    watch_channel_state.clear();
+
     Ok(())
 }
 
+/// Wrap every route in the origin and token checks, which see `config`.
+fn with_auth(app: Router, config: ServerConfig) -> Router {
+    // Layers run last-added first: origin check, then token check
+    app.layer(middleware::from_fn(verify_token))
+        .layer(middleware::from_fn(move |mut req: Request, next| {
+            req.extensions_mut().insert(config.clone());
+            verify_origin(req, next)
+        }))
+}
+
 fn is_valid_origin(req: &Request, config: &ServerConfig) -> bool {
     let headers = req.headers();
     let origin_str = headers
@@ -559,29 +833,165 @@ async fn verify_origin(
     Err(StatusCode::FORBIDDEN)
 }
 
+async fn verify_token(
+    req: Request,
+    next: axum::middleware::Next,
+) -> Result<Response<Body>, Response<Body>> {
+    let config = req.extensions().get::<ServerConfig>().ok_or_else(|| {
+        error!("ServerConfig not found in request extensions");
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    let header_str = |name| req.headers().get(name).and_then(|value| value.to_str().ok());
+    let authorized = config.token.authorize(
+        req.uri().path(),
+        header_str(header::AUTHORIZATION),
+        header_str(header::SEC_WEBSOCKET_PROTOCOL),
+    );
+    let protocol = match authorized {
+        Ok(protocol) => protocol,
+        Err(denied) => {
+            debug!("Rejected request to {}: {}", req.uri().path(), denied.error);
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                Json(denied),
+            )
+                .into_response());
+        }
+    };
+
+    let mut response = next.run(req).await;
+
+    // Browsers drop a WebSocket whose handshake picks none of the offered subprotocols
+    if let Some(protocol) = protocol {
+        if response.status() == StatusCode::SWITCHING_PROTOCOLS
+            && !response.headers().contains_key(header::SEC_WEBSOCKET_PROTOCOL)
+        {
+            if let Ok(value) = HeaderValue::from_str(&protocol) {
+                response
+                    .headers_mut()
+                    .insert(header::SEC_WEBSOCKET_PROTOCOL, value);
+            }
+        }
+    }
+
+    Ok(response)
+}
+
 #[derive(Serialize)]
 struct ShellError {
     error: String,
+    /// Why the shell policy refused the command, for agents to relay
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    denied: Option<PolicyDenial>,
 }
 
+/// Exit status reported for a command killed by its timeout, as timeout(1) does.
+const TIMEOUT_STATUS: i32 = 124;
+
 fn shell_error(message: &str) -> (StatusCode, Json<ShellError>) {
     (
         StatusCode::INTERNAL_SERVER_ERROR,
         Json(ShellError {
             error: message.to_string(),
+            denied: None,
+        }),
+    )
+}
+
+fn shell_denied(message: String, denied: PolicyDenial) -> (StatusCode, Json<ShellError>) {
+    debug!("Denied shell command ({}): {}", denied.code, message);
+    (
+        StatusCode::FORBIDDEN,
+        Json(ShellError {
+            error: message,
+            denied: Some(denied),
         }),
     )
 }
 
+/// The request may shorten the policy's timeout, never extend it.
+fn effective_timeout(
+    policy_timeout: Option<Duration>,
+    requested_ms: Option<u64>,
+) -> Option<Duration> {
+    match (policy_timeout, requested_ms) {
+        (Some(policy_timeout), Some(ms)) => Some(policy_timeout.min(Duration::from_millis(ms))),
+        (policy_timeout, ms) => policy_timeout.or(ms.map(Duration::from_millis)),
+    }
+}
+
+/// Commands run inside the workspace, in the first root unless told otherwise.
+/// Returns the directory as the command gets it and as resolved locally.
+async fn shell_cwd(
+    config: &ServerConfig,
+    cwd: Option<String>,
+    is_wsl: bool,
+) -> Result<(String, PathBuf), (StatusCode, Json<ShellError>)> {
+    let cwd = cwd.unwrap_or_else(|| config.workspace.roots()[0].path.display().to_string());
+    let local_cwd = normalize_path(&cwd, is_wsl)
+        .await
+        .map_err(|e| shell_error(&e))?;
+    let resolved = config
+        .workspace
+        .resolve(&local_cwd, Access::Read)
+        .map_err(|denied| {
+            let code = denied.code;
+            shell_denied(
+                denied.error,
+                PolicyDenial {
+                    code,
+                    command: None,
+                    rule: None,
+                    redirect: None,
+                },
+            )
+        })?;
+    Ok((cwd, resolved))
+}
+
 async fn shell_handler(
+    Extension(config): Extension<ServerConfig>,
     Json(payload): Json<ShellParams>,
 ) -> Result<Response<Body>, (StatusCode, Json<ShellError>)> {
-    let cwd = payload.cwd.unwrap_or(".".to_string());
-    let env = payload.env.unwrap_or_else(|| std::env::vars().collect());
+    let policy = &config.shell_policy;
+    let (cwd, local_cwd) = shell_cwd(&config, payload.cwd, payload.is_wsl).await?;
+    policy
+        .check(&payload.command, &local_cwd, &config.workspace)
+        .map_err(|denied| shell_denied(denied.message(), denied))?;
+
+    let env = policy.scrub_env(payload.env.unwrap_or_else(|| std::env::vars().collect()));
+    let timeout = effective_timeout(policy.timeout_for(&payload.command), payload.timeout_ms);
+    let started = tokio::time::Instant::now();
+    let deadline = timeout.map(|timeout| started + timeout);
+    let protocol = payload.protocol;
+    let mut budget = OutputBudget::new(payload.max_output_bytes);
 
     let mut command = create_shell_command(&payload.command, env, &cwd, payload.is_wsl);
     command.stdout(Stdio::piped()).stderr(Stdio::piped());
 
+    let rlimits = payload.rlimits;
+    if !rlimits.is_empty() {
+        #[cfg(target_os = "linux")]
+        {
+            // SAFETY: `apply` only calls setrlimit, which is async-signal-safe
+            unsafe {
+                command.pre_exec(move || rlimits.apply());
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ShellError {
+                    error: "rlimits are only supported on Linux".to_string(),
+                    denied: None,
+                }),
+            ));
+        }
+    }
+
     let mut process = spawn_command(command)
         .map_err(|e| shell_error(&format!("Failed to spawn command: {}", e)))?;
 
@@ -610,43 +1020,95 @@ async fn shell_handler(
         let _process_holder = process_holder_clone;
 
         let (mut stdout_buf, mut stderr_buf) = (vec![0u8; 4096], vec![0u8; 4096]);
+        let mut timed_out = false;
 
         loop {
-            tokio::select! {
+            let (stream, result) = tokio::select! {
                 result = async { stdout.as_mut().unwrap().read(&mut stdout_buf).await }, if stdout.is_some() => {
-                    match result {
-                        Ok(0) => stdout = None,
-                        Ok(n) => yield Ok(create_data_chunk(&stdout_buf[..n])),
-                        Err(e) => { yield Err(e); break; }
-                    }
+                    (OutputStream::Stdout, result)
                 }
                 result = async { stderr.as_mut().unwrap().read(&mut stderr_buf).await }, if stderr.is_some() => {
-                    match result {
-                        Ok(0) => stderr = None,
-                        Ok(n) => yield Ok(create_data_chunk(&stderr_buf[..n])),
-                        Err(e) => { yield Err(e); break; }
-                    }
+                    (OutputStream::Stderr, result)
+                }
+                _ = async { tokio::time::sleep_until(deadline.unwrap()).await },
+                    if deadline.is_some() && (stdout.is_some() || stderr.is_some()) => {
+                    timed_out = true;
+                    break;
                 }
                 else => break,
+            };
+            let buf = match stream {
+                OutputStream::Stdout => &stdout_buf,
+                OutputStream::Stderr => &stderr_buf,
+            };
+            match result {
+                Ok(0) if stream == OutputStream::Stdout => stdout = None,
+                Ok(0) => stderr = None,
+                Ok(n) => {
+                    let allowed = budget.take(stream, n);
+                    if allowed > 0 {
+                        yield Ok(Bytes::from(protocol.data_frame(stream, &buf[..allowed])));
+                    }
+                    // Past the output cap the command is stopped, as `head` would
+                    if budget.is_truncated() {
+                        break;
+                    }
+                }
+                Err(e) => { yield Err(e); break; }
             }
         }
 
         // Take the process to wait on it (prevents Drop from killing it since process completed normally)
         let process_opt = _process_holder.lock().ok().and_then(|mut g| g.take());
-        let status = if let Some(mut process) = process_opt {
-            match process.child.wait().await {
-                Ok(status) => Some(status.code().unwrap_or(-1)),
-                Err(e) => {
+        let exit_status = if let Some(mut process) = process_opt {
+            if timed_out || budget.is_truncated() {
+                let _ = process.child.start_kill();
+            }
+            let wait = process.child.wait();
+            let waited = match deadline {
+                Some(deadline) if !timed_out => tokio::time::timeout_at(deadline, wait).await.ok(),
+                _ => Some(wait.await),
+            };
+            match waited {
+                Some(Ok(status)) => Some(status),
+                Some(Err(e)) => {
                     yield Err(e);
                     None
                 }
+                // Dropping the process at the end of this block kills its tree
+                None => {
+                    timed_out = true;
+                    None
+                }
             }
         } else {
             None
         };
 
-        if let Some(code) = status {
-            yield Ok(create_status_chunk(code));
+        let mut stats = budget.stats();
+        stats.wall_time_ms = started.elapsed().as_millis() as u64;
+        stats.timed_out = timed_out;
+        if let Some(exit_status) = exit_status {
+            stats.exit_code = exit_status.code();
+            #[cfg(unix)]
+            {
+                use std::os::unix::process::ExitStatusExt;
+                stats.exit_signal = exit_status.signal();
+            }
+        }
+
+        if timed_out {
+            let message = format!(
+                "\ncommand timed out after {}s\n",
+                timeout.unwrap_or_default().as_secs_f64()
+            );
+            yield Ok(Bytes::from(protocol.data_frame(OutputStream::Stderr, message.as_bytes())));
+            yield Ok(Bytes::from(protocol.status_frame(TIMEOUT_STATUS)));
+        } else if let Some(exit_status) = exit_status {
+            yield Ok(Bytes::from(protocol.status_frame(exit_status.code().unwrap_or(-1))));
+        }
+        if let Some(frame) = protocol.stats_frame(&stats) {
+            yield Ok(Bytes::from(frame));
         }
     };
 
@@ -659,26 +1121,229 @@ async fn shell_handler(
         .map_err(|e| shell_error(&format!("Failed to build response: {}", e)))?)
 }
 
-fn create_data_chunk(data: &[u8]) -> Bytes {
-    let mut chunk = BytesMut::new();
-    chunk.extend_from_slice(&[0u8]); // type = 0 (data)
-    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes()); // length
-    chunk.extend_from_slice(data); // data
-    chunk.freeze()
+#[cfg(target_os = "linux")]
+async fn pty_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<PtyState>,
+    Extension(config): Extension<ServerConfig>,
+    Query(params): Query<PtyParams>,
+) -> Result<Response<Body>, (StatusCode, Json<ShellError>)> {
+    // Reattaching only needs the id; the session was checked when it started
+    if let Some(session_id) = params.session_id {
+        let session = state.get(&session_id).ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ShellError {
+                    error: format!("PTY session {} not found", session_id),
+                    denied: None,
+                }),
+            )
+        })?;
+        let latest_id = params.latest_id;
+        return Ok(ws.on_upgrade(move |socket| pty_socket(socket, state, session, latest_id)));
+    }
+
+    let policy = &config.shell_policy;
+    let (cwd, local_cwd) = shell_cwd(&config, params.cwd, false).await?;
+    let checked = match &params.command {
+        Some(command) => policy.check(command, &local_cwd, &config.workspace),
+        // Without a command the PTY runs $SHELL, which no rule can see into
+        None => policy.check_interactive(),
+    };
+    checked.map_err(|denied| shell_denied(denied.message(), denied))?;
+    // An interactive shell has no command for a rule to match, so it gets the default
+    let policy_timeout = match &params.command {
+        Some(command) => policy.timeout_for(command),
+        None => policy.timeout_secs.map(Duration::from_secs),
+    };
+    let options = PtySpawnOptions {
+        command: params.command,
+        cwd: cwd.into(),
+        env: policy.scrub_env(std::env::vars().collect()),
+        size: PtySize {
+            cols: params.cols.unwrap_or(PtySize::default().cols),
+            rows: params.rows.unwrap_or(PtySize::default().rows),
+        },
+        timeout: effective_timeout(policy_timeout, params.timeout_ms),
+    };
+
+    // Spawn only once the upgrade succeeded, so a failed handshake leaves nothing behind
+    Ok(ws.on_upgrade(move |mut socket| async move {
+        match state.spawn(options) {
+            Ok(session) => pty_socket(socket, state, session, 0).await,
+            Err(e) => {
+                let error = ServerMessage::Error {
+                    error: format!("Failed to spawn PTY: {}", e),
+                };
+                let _ = send_pty_message(&mut socket, &error).await;
+            }
+        }
+    }))
+}
+
+#[cfg(target_os = "linux")]
+async fn send_pty_message(
+    socket: &mut WebSocket,
+    message: &ServerMessage,
+) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(message).unwrap_or_default();
+    socket.send(Message::Text(json.into())).await
+}
+
+/// Relay a session over one connection until the process exits, the client
+/// goes away, or another connection takes the session over.
+#[cfg(target_os = "linux")]
+async fn pty_socket(
+    mut socket: WebSocket,
+    state: PtyState,
+    session: Arc<PtySession>,
+    latest_id: ChunkId,
+) {
+    let mut attachment = session.attach(latest_id);
+    let session_message = ServerMessage::Session {
+        session_id: session.id.clone(),
+    };
+    if send_pty_message(&mut socket, &session_message).await.is_err() {
+        state.detach(&session.id, attachment.epoch, DETACH_TIMEOUT);
+        return;
+    }
+
+    let mut last_sent = latest_id;
+    let mut exit_status = attachment.exit_status;
+    for chunk in std::mem::take(&mut attachment.replay) {
+        last_sent = chunk.id;
+        if socket.send(Message::Binary(output_frame(&chunk).into())).await.is_err() {
+            state.detach(&session.id, attachment.epoch, DETACH_TIMEOUT);
+            return;
+        }
+    }
+
+    while exit_status.is_none() {
+        tokio::select! {
+            event = attachment.events.recv() => {
+                let chunks = match event {
+                    Ok(PtyEvent::Output(chunk)) => vec![chunk],
+                    Ok(PtyEvent::Exit(status)) => {
+                        exit_status = Some(status);
+                        continue;
+                    }
+                    Ok(PtyEvent::Takeover) => {
+                        let error = ServerMessage::Error {
+                            error: "session attached from another connection".to_string(),
+                        };
+                        let _ = send_pty_message(&mut socket, &error).await;
+                        return;
+                    }
+                    // Fell behind the live stream; catch up from the replay buffer
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
+                        exit_status = session.exit_status();
+                        session.buffered_after(last_sent)
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+                };
+                for chunk in chunks {
+                    last_sent = chunk.id;
+                    if socket.send(Message::Binary(output_frame(&chunk).into())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            message = socket.recv() => {
+                let result = match message {
+                    Some(Ok(Message::Binary(data))) => session.write_input(data.to_vec()),
+                    Some(Ok(Message::Text(text))) => handle_pty_message(&state, &session, &text),
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => Ok(()),
+                };
+                if let Err(e) = result {
+                    let error = ServerMessage::Error {
+                        error: e.to_string(),
+                    };
+                    let _ = send_pty_message(&mut socket, &error).await;
+                }
+            }
+        }
+    }
+
+    match exit_status {
+        Some(status) => {
+            let _ = send_pty_message(&mut socket, &ServerMessage::Exit { status }).await;
+            let _ = socket.send(Message::Close(None)).await;
+            state.remove(&session.id);
+        }
+        None => {
+            debug!("PTY session {} detached", session.id);
+            state.detach(&session.id, attachment.epoch, DETACH_TIMEOUT);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn handle_pty_message(state: &PtyState, session: &PtySession, text: &str) -> std::io::Result<()> {
+    let message = serde_json::from_str::<ClientMessage>(text)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    match message {
+        ClientMessage::Input { data } => session.write_input(data.into_bytes()),
+        ClientMessage::Resize { cols, rows } => session.resize(PtySize { cols, rows }),
+        ClientMessage::Signal { signal } => session.signal(signal),
+        ClientMessage::Ack { latest_id } => {
+            session.ack(latest_id);
+            Ok(())
+        }
+        // The exit event follows once the process is gone
+        ClientMessage::Kill => {
+            state.remove(&session.id);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+async fn watch_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<WatchChannelState>,
+) -> Response<Body> {
+    ws.on_upgrade(move |socket| watch_socket(socket, WatchSession::new(state)))
+}
+
+/// Answer subscribe/unsubscribe messages and send event batches until the
+/// client goes away; dropping the session then ends its watches.
+#[cfg(target_os = "linux")]
+async fn watch_socket(mut socket: WebSocket, mut session: WatchSession) {
+    loop {
+        let reply = tokio::select! {
+            Some(batch) = session.next_batch() => WatchReply::Events(batch),
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => session.handle(&text).await,
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        let json = serde_json::to_string(&reply).unwrap_or_default();
+        if socket.send(Message::Text(json.into())).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// A glob whose braces expand too far is refused outright rather than
+/// reported as a failed search.
+fn glob_too_large(e: GlobError) -> Response<Body> {
+    debug!("Refused glob ({}): {}", e.code, e.error);
+    (StatusCode::BAD_REQUEST, Json(e)).into_response()
 }
 
-fn create_status_chunk(status: i32) -> Bytes {
-    let json = format!(r#"{{"status":{}}}"#, status);
-    let mut chunk = BytesMut::new();
-    chunk.extend_from_slice(&[1u8]); // type = 1 (status)
-    chunk.extend_from_slice(&(json.len() as u32).to_be_bytes());
-    chunk.extend_from_slice(json.as_bytes());
-    chunk.freeze()
+/// Paths outside the workspace roots, or writes to a read-only root, are
+/// answered with 403 and a machine-readable `code`.
+fn access_denied(denied: AccessDenied) -> Response<Body> {
+    debug!("Denied file access ({}): {}", denied.code, denied.error);
+    (StatusCode::FORBIDDEN, Json(denied)).into_response()
 }
 
 async fn read_file_handler(
+    Extension(config): Extension<ServerConfig>,
     Json(payload): Json<ReadFileParams>,
-) -> Result<Json<ReadFileResponse>, StatusCode> {
+) -> Result<Json<ReadFileResponse>, Response<Body>> {
     let file_path = match normalize_path(&payload.path, payload.is_wsl).await {
         Ok(path) => path,
         Err(error) => {
@@ -690,24 +1355,38 @@ async fn read_file_handler(
     };
 
     if !Path::new(&file_path).is_absolute() {
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into_response());
     }
-
-    let result = async {
-        let content = tokio::fs::read_to_string(&file_path)
-            .await
-            .map_err(|e| e.kind().to_string())?;
-        let mtime = fetch_mtime(file_path)?;
-        Ok::<_, String>((content, mtime))
-    }
-    .await;
+    let file_path = config
+        .workspace
+        .resolve(&file_path, Access::Read)
+        .map_err(access_denied)?;
+
+    let range = payload.range;
+    let encoding = payload.encoding;
+    let result = tokio::task::spawn_blocking(move || {
+        let read = read_range(&file_path, range, MAX_INLINE_BYTES)?;
+        let sha256 = (range.offset == 0 && read.data.len() as u64 == read.size)
+            .then(|| sha256_hex(&read.data));
+        let (content, encoding) = encode(read.data, encoding)?;
+        let mtime_ns = fetch_mtime_ns(&file_path)?;
+        Ok::<_, String>((content, encoding, read.size, sha256, mtime_ns))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
 
     match result {
-        Ok((content, mtime)) => Ok(Json(ReadFileResponse::ReadFileResponseOk {
-            success: true,
-            content,
-            mtime,
-        })),
+        Ok((content, encoding, size, sha256, mtime_ns)) => {
+            Ok(Json(ReadFileResponse::ReadFileResponseOk {
+                success: true,
+                content,
+                encoding,
+                size,
+                sha256,
+                mtime: mtime_ns / NANOS_PER_SEC,
+                mtime_ns,
+            }))
+        }
         Err(error) => Ok(Json(ReadFileResponse::ReadFileResponseErr {
             success: false,
             error,
@@ -716,8 +1395,9 @@ async fn read_file_handler(
 }
 
 async fn write_file_handler(
+    Extension(config): Extension<ServerConfig>,
     Json(payload): Json<WriteFileParams>,
-) -> Result<Json<WriteFileResponse>, StatusCode> {
+) -> Result<Json<WriteFileResponse>, Response<Body>> {
     let file_path = match normalize_path(&payload.path, payload.is_wsl).await {
         Ok(path) => path,
         Err(error) => {
@@ -729,68 +1409,352 @@ async fn write_file_handler(
     };
 
     if !Path::new(&file_path).is_absolute() {
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into_response());
     }
+    let file_path = config
+        .workspace
+        .resolve(&file_path, Access::Write)
+        .map_err(access_denied)?;
 
-    let content = payload.content.clone();
+    let content = match decode(payload.content, payload.encoding.unwrap_or(Encoding::Utf8)) {
+        Ok(content) => content,
+        Err(error) => {
+            return Ok(Json(WriteFileResponse::WriteFileResponseErr {
+                success: false,
+                error,
+            }));
+        }
+    };
     let exclusive = payload.exclusive;
+    let precondition = payload.precondition;
     let bytes_written = content.len();
+    let path_lock = config.write_locks.for_path(&file_path);
 
-    let result = async {
-        let path = Path::new(&file_path);
-
-        let parent_path = path.parent().unwrap_or(path);
-        if !parent_path.exists() {
-            tokio::fs::create_dir_all(parent_path)
-                .await
-                .map_err(|e| (false, e.kind().to_string()))?;
+    let result = tokio::task::spawn_blocking(move || {
+        let _guard = path_lock.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(conflict) = precondition.check(&file_path)? {
+            return Err(WriteError::Conflict(conflict));
         }
+        if let Some(parent_path) = file_path.parent() {
+            std::fs::create_dir_all(parent_path)?;
+        }
+        write_atomic(&file_path, &content, exclusive)?;
+        Ok((sha256_hex(&content), fetch_mtime_ns(&file_path)?))
+    })
+    .await
+    .unwrap_or_else(|e| Err(WriteError::Failed(e.to_string())));
 
-        if exclusive {
-            // Atomic exclusive write using O_CREAT | O_EXCL semantics
-            use tokio::io::AsyncWriteExt;
-            let mut file = tokio::fs::OpenOptions::new()
-                .write(true)
-                .create_new(true)
-                .open(&path)
-                .await
-                .map_err(|e| {
-                    (
-                        e.kind() == std::io::ErrorKind::AlreadyExists,
-                        e.kind().to_string(),
-                    )
-                })?;
-            file.write_all(content.as_bytes())
-                .await
-                .map_err(|e| (false, e.kind().to_string()))?;
-        } else {
-            tokio::fs::write(&path, content)
-                .await
-                .map_err(|e| (false, e.kind().to_string()))?;
+    match result {
+        Ok((sha256, mtime_ns)) => Ok(Json(WriteFileResponse::WriteFileResponseOk {
+            success: true,
+            bytes_written,
+            sha256,
+            mtime: mtime_ns / NANOS_PER_SEC,
+            mtime_ns,
+        })),
+        Err(WriteError::Exists) => Err(file_exists()),
+        Err(WriteError::Conflict(conflict)) => Err(precondition_failed(conflict)),
+        Err(WriteError::Edit(failure)) => Err(edit_failed(failure)),
+        Err(WriteError::Failed(error)) => Ok(Json(WriteFileResponse::WriteFileResponseErr {
+            success: false,
+            error,
+        })),
+    }
+}
+
+async fn edit_file_handler(
+    Extension(config): Extension<ServerConfig>,
+    Json(payload): Json<EditFileParams>,
+) -> Result<Json<EditFileResponse>, Response<Body>> {
+    let file_path = match normalize_path(&payload.path, payload.is_wsl).await {
+        Ok(path) => path,
+        Err(error) => {
+            return Ok(Json(EditFileResponse::EditFileResponseErr {
+                success: false,
+                error,
+            }));
         }
+    };
 
-        let mtime = fetch_mtime(file_path).map_err(|e| (false, e))?;
-        Ok::<_, (bool, String)>(mtime)
+    if !Path::new(&file_path).is_absolute() || payload.edits.is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into_response());
     }
-    .await;
+    let file_path = config
+        .workspace
+        .resolve(&file_path, Access::Write)
+        .map_err(access_denied)?;
+
+    let precondition = payload.precondition;
+    let edits = payload.edits;
+    let path_lock = config.write_locks.for_path(&file_path);
+    let result = tokio::task::spawn_blocking(move || {
+        // Read, edit and replace as one step against other writers of the file
+        let _guard = path_lock.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(conflict) = precondition.check(&file_path)? {
+            return Err(WriteError::Conflict(conflict));
+        }
+        let content = std::fs::read_to_string(&file_path)?;
+        let edited = apply_edits(&content, &edits).map_err(WriteError::Edit)?;
+        write_atomic(&file_path, edited.as_bytes(), false)?;
+        Ok((sha256_hex(edited.as_bytes()), fetch_mtime_ns(&file_path)?))
+    })
+    .await
+    .unwrap_or_else(|e| Err(WriteError::Failed(e.to_string())));
 
     match result {
-        Ok(mtime) => Ok(Json(WriteFileResponse::WriteFileResponseOk {
+        Ok((sha256, mtime_ns)) => Ok(Json(EditFileResponse::EditFileResponseOk {
             success: true,
-            bytes_written,
-            mtime,
+            sha256,
+            mtime: mtime_ns / NANOS_PER_SEC,
+            mtime_ns,
         })),
-        Err((true, _)) => Err(StatusCode::CONFLICT),
-        Err((false, error)) => Ok(Json(WriteFileResponse::WriteFileResponseErr {
+        Err(WriteError::Conflict(conflict)) => Err(precondition_failed(conflict)),
+        Err(WriteError::Edit(failure)) => Err(edit_failed(failure)),
+        Err(WriteError::Exists) => Err(file_exists()),
+        Err(WriteError::Failed(error)) => Ok(Json(EditFileResponse::EditFileResponseErr {
             success: false,
             error,
         })),
     }
 }
 
+/// Why a write did not happen.
+enum WriteError {
+    /// An exclusive write found the file already there
+    Exists,
+    /// The file no longer matches the client's precondition
+    Conflict(Conflict),
+    /// An `/edit` operation does not apply to the file
+    Edit(EditFailure),
+    Failed(String),
+}
+
+impl From<std::io::Error> for WriteError {
+    fn from(e: std::io::Error) -> Self {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            WriteError::Exists
+        } else {
+            WriteError::Failed(e.kind().to_string())
+        }
+    }
+}
+
+impl From<String> for WriteError {
+    fn from(error: String) -> Self {
+        WriteError::Failed(error)
+    }
+}
+
+/// An edit that does not fit the file is a conflict (409); a malformed one,
+/// such as a diff without hunks, a bad request (400).
+fn edit_failed(failure: EditFailure) -> Response<Body> {
+    debug!("Edit operation {} failed: {}", failure.op, failure.error);
+    let status = if failure.code == EDIT_CONFLICT {
+        StatusCode::CONFLICT
+    } else {
+        StatusCode::BAD_REQUEST
+    };
+    (status, Json(failure)).into_response()
+}
+
+/// A failed `if_match`/`if_mtime_ns` check: 409 with the file's current state.
+fn precondition_failed(conflict: Conflict) -> Response<Body> {
+    debug!("Write precondition failed: {}", conflict.error);
+    (StatusCode::CONFLICT, Json(conflict)).into_response()
+}
+
+/// Stream a file, or `offset`/`length` of it, as raw bytes.
+async fn raw_read_handler(
+    Extension(config): Extension<ServerConfig>,
+    Query(params): Query<RawReadParams>,
+) -> Result<Response<Body>, Response<Body>> {
+    let file_path = normalize_path(&params.path, params.is_wsl)
+        .await
+        .map_err(|error| file_error(StatusCode::BAD_REQUEST, error))?;
+    if !Path::new(&file_path).is_absolute() {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    let file_path = config
+        .workspace
+        .resolve(&file_path, Access::Read)
+        .map_err(access_denied)?;
+
+    let mut file = tokio::fs::File::open(&file_path)
+        .await
+        .map_err(io_file_error)?;
+    let size = file.metadata().await.map_err(io_file_error)?.len();
+    let mtime_ns = fetch_mtime_ns(&file_path)
+        .map_err(|error| file_error(StatusCode::INTERNAL_SERVER_ERROR, error))?;
+    let range = ByteRange {
+        offset: params.offset,
+        length: params.length,
+    };
+    let length = range.len_in(size);
+    file.seek(std::io::SeekFrom::Start(range.offset))
+        .await
+        .map_err(io_file_error)?;
+
+    let stream = async_stream::stream! {
+        let mut reader = file.take(length);
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            match reader.read(&mut buf).await {
+                Ok(0) => break,
+                Ok(n) => yield Ok(Bytes::copy_from_slice(&buf[..n])),
+                Err(e) => { yield Err(e); break; }
+            }
+        }
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::CONTENT_LENGTH, length)
+        .header("x-file-size", size)
+        .header("x-mtime-ns", mtime_ns)
+        .body(Body::from_stream(stream))
+        .map_err(|e| file_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Stream the request body into a file, for uploads too large for `/write`.
+async fn raw_write_handler(
+    Extension(config): Extension<ServerConfig>,
+    Query(params): Query<RawWriteParams>,
+    body: Body,
+) -> Result<Json<WriteFileResponse>, Response<Body>> {
+    let file_path = normalize_path(&params.path, params.is_wsl)
+        .await
+        .map_err(|error| file_error(StatusCode::BAD_REQUEST, error))?;
+    if !Path::new(&file_path).is_absolute() {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    let file_path = config
+        .workspace
+        .resolve(&file_path, Access::Write)
+        .map_err(access_denied)?;
+
+    let precondition = Precondition {
+        if_match: params.if_match,
+        if_mtime_ns: params.if_mtime_ns,
+    };
+    // Checked before the upload to fail fast, and again before the file is replaced
+    check_precondition(&precondition, &file_path)?;
+    if params.exclusive && file_path.exists() {
+        return Err(file_exists());
+    }
+
+    if let Some(parent_path) = file_path.parent() {
+        tokio::fs::create_dir_all(parent_path)
+            .await
+            .map_err(io_file_error)?;
+    }
+    let (atomic, file) = AtomicFile::create(&file_path).map_err(io_file_error)?;
+    let mut file = tokio::fs::File::from_std(file);
+
+    let mut stream = body.into_data_stream();
+    let mut bytes_written: u64 = 0;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| file_error(StatusCode::BAD_REQUEST, e.to_string()))?;
+        bytes_written += chunk.len() as u64;
+        if bytes_written > MAX_UPLOAD_BYTES {
+            return Err(file_error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("uploads are limited to {} bytes", MAX_UPLOAD_BYTES),
+            ));
+        }
+        file.write_all(&chunk).await.map_err(io_file_error)?;
+    }
+    file.sync_all().await.map_err(io_file_error)?;
+    drop(file);
+
+    let (sha256, mtime_ns) = {
+        // Nothing is awaited while the lock is held, so a plain mutex is enough
+        let path_lock = config.write_locks.for_path(&file_path);
+        let _guard = path_lock.lock().unwrap_or_else(PoisonError::into_inner);
+        check_precondition(&precondition, &file_path)?;
+        atomic.commit(params.exclusive).map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => file_exists(),
+            std::io::ErrorKind::Unsupported if params.exclusive => exclusive_unsupported(),
+            _ => io_file_error(e),
+        })?;
+        // Describe the file this request wrote, before another writer can replace it
+        let sha256 = file_sha256(&file_path)
+            .map_err(io_file_error)?
+            .unwrap_or_default();
+        let mtime_ns = fetch_mtime_ns(&file_path)
+            .map_err(|error| file_error(StatusCode::INTERNAL_SERVER_ERROR, error))?;
+        (sha256, mtime_ns)
+    };
+    Ok(Json(WriteFileResponse::WriteFileResponseOk {
+        success: true,
+        bytes_written: bytes_written as usize,
+        sha256,
+        mtime: mtime_ns / NANOS_PER_SEC,
+        mtime_ns,
+    }))
+}
+
+fn check_precondition(precondition: &Precondition, path: &Path) -> Result<(), Response<Body>> {
+    match precondition.check(path) {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(conflict)) => Err(precondition_failed(conflict)),
+        Err(e) => Err(io_file_error(e)),
+    }
+}
+
+/// Errors of the raw file routes, which answer with a status rather than a
+/// JSON `success: false`, since their successful bodies are not JSON.
+fn file_error(status: StatusCode, error: String) -> Response<Body> {
+    (
+        status,
+        Json(FileError {
+            success: false,
+            code: None,
+            error,
+        }),
+    )
+        .into_response()
+}
+
+/// An exclusive write found the file already there.
+fn file_exists() -> Response<Body> {
+    (
+        StatusCode::CONFLICT,
+        Json(FileError {
+            success: false,
+            code: Some(FILE_EXISTS),
+            error: "file already exists".to_string(),
+        }),
+    )
+        .into_response()
+}
+
+/// Neither a hard link nor `O_EXCL` works where the file would go.
+fn exclusive_unsupported() -> Response<Body> {
+    (
+        StatusCode::NOT_IMPLEMENTED,
+        Json(FileError {
+            success: false,
+            code: Some(EXCLUSIVE_UNSUPPORTED),
+            error: "the filesystem cannot create the file exclusively".to_string(),
+        }),
+    )
+        .into_response()
+}
+
+fn io_file_error(e: std::io::Error) -> Response<Body> {
+    let status = match e.kind() {
+        std::io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        std::io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    file_error(status, e.kind().to_string())
+}
+
 async fn delete_file_handler(
+    Extension(config): Extension<ServerConfig>,
     Json(payload): Json<DeleteFileParams>,
-) -> Result<Json<DeleteFileResponse>, StatusCode> {
+) -> Result<Json<DeleteFileResponse>, Response<Body>> {
     let file_path = match normalize_path(&payload.path, payload.is_wsl).await {
         Ok(path) => path,
         Err(error) => {
@@ -802,8 +1766,12 @@ async fn delete_file_handler(
     };
 
     if !Path::new(&file_path).is_absolute() {
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into_response());
     }
+    let file_path = config
+        .workspace
+        .resolve_entry(&file_path, Access::Write)
+        .map_err(access_denied)?;
 
     let result = tokio::fs::remove_file(&file_path).await;
 
@@ -818,7 +1786,10 @@ async fn delete_file_handler(
     }
 }
 
-async fn stat_handler(Query(query): Query<StatParams>) -> Result<Json<StatResponse>, StatusCode> {
+async fn stat_handler(
+    Extension(config): Extension<ServerConfig>,
+    Query(query): Query<StatParams>,
+) -> Result<Json<StatResponse>, Response<Body>> {
     let file_path = match normalize_path(&query.path, query.is_wsl).await {
         Ok(path) => path,
         Err(error) => {
@@ -830,13 +1801,17 @@ async fn stat_handler(Query(query): Query<StatParams>) -> Result<Json<StatRespon
     };
 
     if !Path::new(&file_path).is_absolute() {
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into_response());
     }
+    let file_path = config
+        .workspace
+        .resolve(&file_path, Access::Read)
+        .map_err(access_denied)?;
 
     let result = fetch_stat(&file_path);
 
     match result {
-        Ok((mtime, path_type)) => {
+        Ok((mtime_ns, path_type)) => {
             let windows_path = if query.is_wsl {
                 wslpath_to_windows(&query.path).await.ok()
             } else {
@@ -844,7 +1819,8 @@ async fn stat_handler(Query(query): Query<StatParams>) -> Result<Json<StatRespon
             };
             Ok(Json(StatResponse::StatResponseOk {
                 success: true,
-                mtime,
+                mtime: mtime_ns / NANOS_PER_SEC,
+                mtime_ns,
                 path_type,
                 windows_path,
             }))
@@ -857,8 +1833,9 @@ async fn stat_handler(Query(query): Query<StatParams>) -> Result<Json<StatRespon
 }
 
 async fn listdir_handler(
+    Extension(config): Extension<ServerConfig>,
     Query(query): Query<ListDirParams>,
-) -> Result<Json<ListDirResponse>, StatusCode> {
+) -> Result<Json<ListDirResponse>, Response<Body>> {
     let dir_path = match normalize_path(&query.path, query.is_wsl).await {
         Ok(path) => path,
         Err(error) => {
@@ -870,26 +1847,157 @@ async fn listdir_handler(
     };
 
     if !Path::new(&dir_path).is_absolute() {
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into_response());
     }
-
-    let result = fetch_dir_entries(&dir_path);
+    let dir_path = config
+        .workspace
+        .resolve(&dir_path, Access::Read)
+        .map_err(access_denied)?;
+
+    let options = WalkOptions {
+        max_depth: if query.recursive { query.max_depth } else { Some(1) },
+        gitignore: query.gitignore,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_RESULTS);
+    let result =
+        tokio::task::spawn_blocking(move || search::list_dir(&dir_path, &options, limit))
+            .await
+            .unwrap_or_else(|e| Err(std::io::Error::other(e)));
 
     match result {
-        Ok(entries) => Ok(Json(ListDirResponse::ListDirResponseOk {
+        Ok(listing) => Ok(Json(ListDirResponse::ListDirResponseOk {
             success: true,
-            entries,
+            entries: listing.items,
+            truncated: listing.truncated,
         })),
         Err(error) => Ok(Json(ListDirResponse::ListDirResponseErr {
             success: false,
-            error,
+            error: error.kind().to_string(),
+        })),
+    }
+}
+
+async fn glob_handler(
+    Extension(config): Extension<ServerConfig>,
+    Query(query): Query<GlobParams>,
+) -> Result<Json<GlobResponse>, Response<Body>> {
+    let dir_path = match normalize_path(&query.path, query.is_wsl).await {
+        Ok(path) => path,
+        Err(error) => {
+            return Ok(Json(GlobResponse::GlobResponseErr {
+                success: false,
+                error,
+            }));
+        }
+    };
+
+    if !Path::new(&dir_path).is_absolute() {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    let dir_path = config
+        .workspace
+        .resolve(&dir_path, Access::Read)
+        .map_err(access_denied)?;
+
+    let glob = match Glob::new(&query.pattern) {
+        Ok(glob) => glob,
+        Err(e) if e.code == GLOB_TOO_LARGE => return Err(glob_too_large(e)),
+        Err(e) => {
+            return Ok(Json(GlobResponse::GlobResponseErr {
+                success: false,
+                error: e.error,
+            }));
+        }
+    };
+    let options = WalkOptions {
+        max_depth: None,
+        gitignore: query.gitignore.unwrap_or(true),
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_GLOB_LIMIT).min(MAX_RESULTS);
+    let result =
+        tokio::task::spawn_blocking(move || search::glob(&dir_path, &glob, &options, limit))
+            .await
+            .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+
+    match result {
+        Ok(found) => Ok(Json(GlobResponse::GlobResponseOk {
+            success: true,
+            matches: found.items,
+            truncated: found.truncated,
+        })),
+        Err(error) => Ok(Json(GlobResponse::GlobResponseErr {
+            success: false,
+            error: error.kind().to_string(),
+        })),
+    }
+}
+
+async fn grep_handler(
+    Extension(config): Extension<ServerConfig>,
+    Json(payload): Json<GrepParams>,
+) -> Result<Json<GrepResponse>, Response<Body>> {
+    let search_path = match normalize_path(&payload.path, payload.is_wsl).await {
+        Ok(path) => path,
+        Err(error) => {
+            return Ok(Json(GrepResponse::GrepResponseErr {
+                success: false,
+                error,
+            }));
+        }
+    };
+
+    if !Path::new(&search_path).is_absolute() {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    let search_path = config
+        .workspace
+        .resolve(&search_path, Access::Read)
+        .map_err(access_denied)?;
+
+    let regex = search::build_regex(&payload.pattern, payload.literal, payload.case_insensitive);
+    let glob = match payload.glob.as_deref().map(Glob::new).transpose() {
+        Err(e) if e.code == GLOB_TOO_LARGE => return Err(glob_too_large(e)),
+        glob => glob.map_err(|e| e.error),
+    };
+    let (regex, glob) = match (regex, glob) {
+        (Ok(regex), Ok(glob)) => (regex, glob),
+        (Err(error), _) | (_, Err(error)) => {
+            return Ok(Json(GrepResponse::GrepResponseErr {
+                success: false,
+                error,
+            }));
+        }
+    };
+    let options = GrepOptions {
+        walk: WalkOptions {
+            max_depth: None,
+            gitignore: payload.gitignore.unwrap_or(true),
+        },
+        glob,
+        context: payload.context,
+        limit: payload.limit.unwrap_or(DEFAULT_GREP_LIMIT).min(MAX_RESULTS),
+    };
+    let result = tokio::task::spawn_blocking(move || search::grep(&search_path, &regex, &options))
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+
+    match result {
+        Ok(found) => Ok(Json(GrepResponse::GrepResponseOk {
+            success: true,
+            matches: found.items,
+            truncated: found.truncated,
+        })),
+        Err(error) => Ok(Json(GrepResponse::GrepResponseErr {
+            success: false,
+            error: error.kind().to_string(),
         })),
     }
 }
 
 async fn mkdir_handler(
+    Extension(config): Extension<ServerConfig>,
     Json(payload): Json<MkdirParams>,
-) -> Result<Json<MkdirResponse>, StatusCode> {
+) -> Result<Json<MkdirResponse>, Response<Body>> {
     let dir_path = match normalize_path(&payload.path, payload.is_wsl).await {
         Ok(path) => path,
         Err(error) => {
@@ -901,8 +2009,12 @@ async fn mkdir_handler(
     };
 
     if !Path::new(&dir_path).is_absolute() {
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into_response());
     }
+    let dir_path = config
+        .workspace
+        .resolve(&dir_path, Access::Write)
+        .map_err(access_denied)?;
 
     let result = tokio::fs::create_dir_all(&dir_path).await;
 
@@ -915,56 +2027,16 @@ async fn mkdir_handler(
     }
 }
 
-fn fetch_dir_entries(path: &str) -> Result<Vec<DirEntry>, String> {
-    let entries = std::fs::read_dir(path).map_err(|e| e.kind().to_string())?;
-
-    let mut result = Vec::new();
-    for entry in entries {
-        let entry = entry.map_err(|e| e.kind().to_string())?;
-
-        // Skip entries with invalid UTF-8 names
-        let Some(name) = entry.file_name().to_str().map(|s| s.to_string()) else {
-            continue;
-        };
-
-        let file_type = entry.file_type().map_err(|e| e.kind().to_string())?;
-        let entry_type = if file_type.is_dir() {
-            "directory"
-        } else if file_type.is_file() {
-            "file"
-        } else if file_type.is_symlink() {
-            "symlink"
-        } else {
-            "other"
-        };
-
-        result.push(DirEntry {
-            name,
-            entry_type: entry_type.to_string(),
-        });
-    }
-
-    Ok(result)
-}
+const NANOS_PER_SEC: u64 = 1_000_000_000;
 
-fn fetch_mtime(path: String) -> Result<u64, String> {
+fn fetch_mtime_ns(path: &Path) -> Result<u64, String> {
     let metadata = std::fs::metadata(path).map_err(|e| e.kind().to_string())?;
-    let mtime = metadata.modified().map_err(|e| e.kind().to_string())?;
-
-    return mtime
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .map_err(|_| "system time error".to_string());
+    mtime_ns(&metadata).map_err(|e| e.to_string())
 }
 
-fn fetch_stat(path: &str) -> Result<(u64, String), String> {
+fn fetch_stat(path: &Path) -> Result<(u64, String), String> {
     let metadata = std::fs::metadata(path).map_err(|e| e.kind().to_string())?;
-    let mtime = metadata
-        .modified()
-        .map_err(|e| e.kind().to_string())?
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .map_err(|_| "system time error".to_string())?;
+    let mtime_ns = mtime_ns(&metadata).map_err(|e| e.to_string())?;
 
     let path_type = if metadata.is_dir() {
         "directory"
@@ -976,7 +2048,7 @@ fn fetch_stat(path: &str) -> Result<(u64, String), String> {
         "other"
     };
 
-    Ok((mtime, path_type.to_string()))
+    Ok((mtime_ns, path_type.to_string()))
 }
 
 async fn which_handler(Json(params): Json<WhichParams>) -> Result<Json<WhichResponse>, StatusCode> {
@@ -1130,4 +2202,124 @@ async fn root(_req: Request) -> Html<String> {
     );
 
     Html(html)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // reqwest has its own copy of the `http` types
+    use reqwest::header::{
+        CONNECTION, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_PROTOCOL, SEC_WEBSOCKET_VERSION, UPGRADE,
+        WWW_AUTHENTICATE,
+    };
+    use reqwest::StatusCode;
+
+    /// Serve the public pages, a file route and a WebSocket behind the real
+    /// middleware on a loopback port; returns the base URL and the token.
+    async fn serve() -> (String, String) {
+        let token = AuthToken::generate();
+        let secret = token.as_str().to_string();
+        let config = ServerConfig {
+            allowed_origins: Vec::new(),
+            port: 0,
+            https_port: None,
+            workspace: Workspace::default(),
+            token,
+            shell_policy: Arc::new(ShellPolicy::default()),
+            write_locks: PathLocks::default(),
+        };
+        let app = Router::new()
+            .route("/", get(root))
+            .route("/about", get(about))
+            .route("/read", post(read_file_handler))
+            .route(
+                "/socket/websocket",
+                get(|ws: WebSocketUpgrade| async move { ws.on_upgrade(|_| async {}) }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, with_auth(app, config)).await });
+        (format!("http://{}", addr), secret)
+    }
+
+    fn websocket(client: &Client, url: &str, protocols: &str) -> reqwest::RequestBuilder {
+        client
+            .get(url)
+            .header(CONNECTION, "Upgrade")
+            .header(UPGRADE, "websocket")
+            .header(SEC_WEBSOCKET_VERSION, "13")
+            .header(SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+            .header(SEC_WEBSOCKET_PROTOCOL, protocols)
+    }
+
+    async fn assert_unauthorized(response: reqwest::Response, error: &str) {
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["code"], crate::auth::UNAUTHORIZED);
+        assert_eq!(body["error"], error);
+    }
+
+    #[tokio::test]
+    async fn test_public_pages_need_no_token() {
+        let (base, _) = serve().await;
+        let client = Client::new();
+        for path in ["/", "/about"] {
+            let response = client.get(format!("{}{}", base, path)).send().await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_wrong_token_is_401() {
+        let (base, secret) = serve().await;
+        let client = Client::new();
+        let read = format!("{}/read", base);
+
+        let response = client.post(&read).json(&serde_json::json!({})).send().await.unwrap();
+        assert_unauthorized(response, "missing token").await;
+
+        let response = client
+            .post(&read)
+            .bearer_auth("wrong")
+            .json(&serde_json::json!({}))
+            .send()
+            .await
+            .unwrap();
+        assert_unauthorized(response, "invalid token").await;
+
+        // The right token gets past the middleware to the handler
+        let response = client
+            .post(&read)
+            .bearer_auth(&secret)
+            .json(&serde_json::json!({}))
+            .send()
+            .await
+            .unwrap();
+        assert_ne!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_websocket_token_subprotocol() {
+        let (base, secret) = serve().await;
+        let client = Client::new();
+        let socket = format!("{}/socket/websocket", base);
+
+        let response = websocket(&client, &socket, "chat, bearer.wrong").send().await.unwrap();
+        assert_unauthorized(response, "invalid token").await;
+        // Only the `bearer.` prefix carries a token
+        let response = websocket(&client, &socket, &format!("token.{}", secret))
+            .send()
+            .await
+            .unwrap();
+        assert_unauthorized(response, "missing token").await;
+
+        let protocol = format!("bearer.{}", secret);
+        let response = websocket(&client, &socket, &format!("chat, {}", protocol))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+        assert_eq!(response.headers()[SEC_WEBSOCKET_PROTOCOL], protocol.as_str());
+    }
+}
@@ -0,0 +1,924 @@
+//! Allow/deny policy for commands run through `/shell`.
+//!
+//! A policy file (TOML) looks like:
+//!
+//! ```toml
+//! allow = ["git status", "git diff", "cargo *", "npm run *"]
+//! deny = ["sudo", "rm -rf /*"]
+//! env_allow = ["GITHUB_TOKEN"]
+//! timeout_secs = 300
+//! interactive_shell = false
+//!
+//! [[timeouts]]
+//! command = "cargo test*"
+//! secs = 1800
+//! ```
+//!
+//! A rule containing `*` or `?` is a glob over the whole command; any other
+//! rule matches the command itself or the command followed by arguments
+//! (`git status` matches `git status -s`, not `git statusx`). Commands chained
+//! with `;`, `&&`, `||`, `|` or `&` are checked part by part. Deny rules win
+//! over allow rules; a command no rule matches is allowed only when the
+//! policy has no allow list, unless `default` says otherwise.
+//!
+//! Rules see each part the way the shell runs it: quotes and escapes removed,
+//! leading `NAME=value` assignments, reserved words (`if`, `then`, `!`, `{`,
+//! ...) and wrappers such as `env`, `exec` or `nohup` peeled off, and the
+//! scripts of `sh -c` and `eval` checked in turn. Deny rules also match the
+//! program's basename, so `deny = ["sudo"]` covers `/usr/bin/sudo`; allow rules
+//! match the program as written. A deny list is still advisory on its own:
+//! `python -c`, `xargs` or `find -exec` can run anything, so only an allow
+//! list actually confines what runs.
+//!
+//! Under any policy with rules, redirections (`>`, `>>`, `<`, `&>`, `2>`, ...)
+//! must name a file inside the workspace: reading for `<`, writing otherwise.
+//! A target the policy cannot resolve up front (one with `$`, a glob, or a
+//! relative path after a `cd`) is refused.
+//!
+//! A chained command gets the longest timeout among its parts. A part no
+//! `[[timeouts]]` rule matches gets `timeout_secs`, or no limit when that is
+//! unset.
+//!
+//! A PTY opened without a command runs an interactive `$SHELL`, which no rule
+//! can see into. It is refused under an allow list and otherwise needs
+//! `interactive_shell = true`.
+
+use crate::workspace::{Access, Workspace};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Variables never passed to a command unless `env_allow` names them.
+const DEFAULT_ENV_DENY: &[&str] = &["*_TOKEN", "AWS_*"];
+
+/// Reserved words and grouping that open or close a command rather than run one.
+const KEYWORDS: &[&str] = &[
+    "!", "{", "}", "if", "then", "elif", "else", "fi", "while", "until", "do", "done",
+];
+
+/// Commands that run the rest of their arguments as a command: name, options
+/// that take a value, and operands before the command.
+const WRAPPERS: &[(&str, &[&str], usize)] = &[
+    ("builtin", &[], 0),
+    ("command", &[], 0),
+    ("env", &["-u", "--unset", "-C", "--chdir"], 0),
+    ("exec", &["-a"], 0),
+    ("nice", &["-n", "--adjustment"], 0),
+    ("nohup", &[], 0),
+    ("time", &["-f", "--format", "-o", "--output"], 0),
+    ("timeout", &["-s", "--signal", "-k", "--kill-after"], 1),
+];
+
+/// Shells whose `-c` argument is a script of its own.
+const SHELLS: &[&str] = &["sh", "bash", "dash", "zsh", "ksh"];
+
+/// Builtins after which relative redirection targets no longer resolve against `cwd`.
+const DIRECTORY_CHANGES: &[&str] = &["cd", "pushd", "popd"];
+
+/// Redirection targets that are not files in any workspace.
+const SPECIAL_FILES: &[&str] = &["/dev/null", "/dev/stdin", "/dev/stdout", "/dev/stderr"];
+
+pub const COMMAND_DENIED: &str = "command_denied";
+pub const COMMAND_NOT_ALLOWED: &str = "command_not_allowed";
+pub const REDIRECT_NOT_ALLOWED: &str = "redirect_not_allowed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandTimeout {
+    pub command: String,
+    pub secs: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShellPolicy {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// What to do with a command no rule matches
+    #[serde(default)]
+    pub default: Option<Decision>,
+    /// Patterns of variables to drop from the environment, on top of the defaults
+    #[serde(default)]
+    pub env_deny: Vec<String>,
+    /// Patterns of variables to keep even though a deny pattern matches
+    #[serde(default)]
+    pub env_allow: Vec<String>,
+    /// Timeout for commands without a more specific one
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub timeouts: Vec<CommandTimeout>,
+    /// Let a PTY start an interactive shell when the policy has no allow list
+    #[serde(default)]
+    pub interactive_shell: bool,
+}
+
+/// A refused `/shell` request, as sent to the client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PolicyDenial {
+    pub code: &'static str,
+    /// The part of a chained command that was refused
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// The deny rule that matched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// The redirection target that was refused
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect: Option<String>,
+}
+
+impl PolicyDenial {
+    pub fn message(&self) -> String {
+        if let Some(redirect) = &self.redirect {
+            return match &self.command {
+                Some(command) => format!(
+                    "`{}` redirects to `{}`, which is not a file in the workspace",
+                    command, redirect
+                ),
+                None => format!("`{}` is not a file in the workspace", redirect),
+            };
+        }
+        match (&self.command, &self.rule) {
+            (Some(command), Some(rule)) => {
+                format!("`{}` is denied by the shell policy rule `{}`", command, rule)
+            }
+            (Some(command), None) => format!("`{}` is not in the shell policy allow list", command),
+            _ => "an interactive shell is not allowed by the shell policy".to_string(),
+        }
+    }
+}
+
+impl ShellPolicy {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read shell policy {}: {}", path.display(), e))?;
+        toml::from_str(&content)
+            .map_err(|e| format!("invalid shell policy {}: {}", path.display(), e))
+    }
+
+    /// Check every part of `command` against the rules, and its redirections
+    /// against `workspace` as if it ran in `cwd`.
+    pub fn check(
+        &self,
+        command: &str,
+        cwd: &Path,
+        workspace: &Workspace,
+    ) -> Result<(), PolicyDenial> {
+        let Some(parts) = parts(command) else {
+            // Substitutions hide what actually runs, so only an open policy
+            // lets them through
+            if self.is_open() {
+                return Ok(());
+            }
+            return Err(PolicyDenial {
+                code: COMMAND_NOT_ALLOWED,
+                command: Some(command.trim().to_string()),
+                rule: None,
+                redirect: None,
+            });
+        };
+
+        let mut cwd = Some(cwd);
+        for part in parts {
+            if !self.is_open() {
+                if let Some(redirect) = part
+                    .redirects
+                    .iter()
+                    .find(|redirect| !redirect_allowed(redirect, cwd, workspace))
+                {
+                    return Err(PolicyDenial {
+                        code: REDIRECT_NOT_ALLOWED,
+                        command: Some(part.command).filter(|command| !command.is_empty()),
+                        rule: None,
+                        redirect: Some(redirect.target.clone()),
+                    });
+                }
+            }
+            if part.command.is_empty() {
+                continue;
+            }
+            if DIRECTORY_CHANGES.contains(&part.program()) {
+                cwd = None;
+            }
+
+            if let Some(rule) = self.deny.iter().find(|rule| {
+                rule_matches(rule, &part.command) || rule_matches(rule, &part.basename)
+            }) {
+                return Err(PolicyDenial {
+                    code: COMMAND_DENIED,
+                    command: Some(part.command),
+                    rule: Some(rule.clone()),
+                    redirect: None,
+                });
+            }
+            let allowed = self.allow.iter().any(|rule| rule_matches(rule, &part.command));
+            if !allowed && self.default_decision() == Decision::Deny {
+                return Err(PolicyDenial {
+                    code: COMMAND_NOT_ALLOWED,
+                    command: Some(part.command),
+                    rule: None,
+                    redirect: None,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check a PTY that runs an interactive shell rather than a command.
+    pub fn check_interactive(&self) -> Result<(), PolicyDenial> {
+        if self.interactive_shell && self.default_decision() == Decision::Allow {
+            return Ok(());
+        }
+        Err(PolicyDenial {
+            code: COMMAND_NOT_ALLOWED,
+            command: None,
+            rule: None,
+            redirect: None,
+        })
+    }
+
+    /// No rules at all: every command runs as the user typed it.
+    fn is_open(&self) -> bool {
+        self.deny.is_empty() && self.default_decision() == Decision::Allow
+    }
+
+    fn default_decision(&self) -> Decision {
+        self.default.unwrap_or(if self.allow.is_empty() {
+            Decision::Allow
+        } else {
+            Decision::Deny
+        })
+    }
+
+    /// Drop the variables the policy keeps from commands.
+    pub fn scrub_env(&self, env: HashMap<String, String>) -> HashMap<String, String> {
+        env.into_iter()
+            .filter(|(name, _)| {
+                let denied = DEFAULT_ENV_DENY
+                    .iter()
+                    .copied()
+                    .chain(self.env_deny.iter().map(String::as_str))
+                    .any(|pattern| glob_match(pattern, name));
+                !denied || self.env_allow.iter().any(|pattern| glob_match(pattern, name))
+            })
+            .collect()
+    }
+
+    /// The longest timeout among the parts of `command`; `None` is no limit.
+    pub fn timeout_for(&self, command: &str) -> Option<Duration> {
+        let part_timeout = |command: &str| {
+            self.timeouts
+                .iter()
+                .find(|timeout| rule_matches(&timeout.command, command))
+                .map(|timeout| timeout.secs)
+                .or(self.timeout_secs)
+        };
+        let Some(parts) = parts(command) else {
+            return part_timeout(command.trim()).map(Duration::from_secs);
+        };
+
+        let mut longest = None;
+        for part in parts.iter().filter(|part| !part.command.is_empty()) {
+            // A part without a limit leaves the whole command without one
+            let secs = part_timeout(&part.command)?;
+            longest = longest.max(Some(secs));
+        }
+        longest.or(self.timeout_secs).map(Duration::from_secs)
+    }
+}
+
+fn rule_matches(rule: &str, command: &str) -> bool {
+    let rule = rule.trim();
+    if rule.contains(['*', '?']) {
+        return glob_match(rule, command);
+    }
+    match command.strip_prefix(rule) {
+        Some(rest) => rest.is_empty() || rest.starts_with(char::is_whitespace),
+        None => false,
+    }
+}
+
+/// Match `text` against a pattern where `*` is any run of characters and `?`
+/// any one character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// A file a command reads or writes through a redirection.
+#[derive(Debug, Clone, PartialEq)]
+struct Redirect {
+    target: String,
+    access: Access,
+}
+
+/// Whether `redirect` stays inside the workspace; `cwd` is `None` once the
+/// command line has changed directory.
+fn redirect_allowed(redirect: &Redirect, cwd: Option<&Path>, workspace: &Workspace) -> bool {
+    let target = redirect.target.as_str();
+    if SPECIAL_FILES.contains(&target) {
+        return true;
+    }
+    if target.contains(['$', '*', '?', '[', '{']) {
+        return false;
+    }
+    let path = if let Some(rest) = target.strip_prefix("~/") {
+        match std::env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(rest),
+            None => return false,
+        }
+    } else if target.starts_with('~') {
+        return false;
+    } else if Path::new(target).is_absolute() {
+        PathBuf::from(target)
+    } else {
+        match cwd {
+            Some(cwd) => cwd.join(target),
+            None => return false,
+        }
+    };
+    workspace.resolve(&path.to_string_lossy(), redirect.access).is_ok()
+}
+
+/// One command a command line runs; the scripts of `sh -c` and `eval` are
+/// flattened into the parts around them.
+#[derive(Debug, PartialEq)]
+struct Part {
+    /// Program and arguments, quotes removed; empty when the part only has
+    /// keywords, assignments or redirections
+    command: String,
+    /// `command` with the program's directory stripped
+    basename: String,
+    redirects: Vec<Redirect>,
+}
+
+impl Part {
+    fn program(&self) -> &str {
+        self.basename.split(' ').next().unwrap_or_default()
+    }
+}
+
+/// Every part of `command`, in order. `None` when a substitution hides some.
+fn parts(command: &str) -> Option<Vec<Part>> {
+    let mut parts = Vec::new();
+    for segment in command_segments(command)? {
+        let (words, redirects) = shell_words(&segment);
+        let (command, basename) = match invocation(&words) {
+            Invocation::Nothing => (String::new(), String::new()),
+            Invocation::Script(script) => {
+                // Redirections of `sh -c '...' > file` still apply
+                parts.push(Part {
+                    command: String::new(),
+                    basename: String::new(),
+                    redirects,
+                });
+                parts.extend(self::parts(&script)?);
+                continue;
+            }
+            Invocation::Command { command, basename } => (command, basename),
+        };
+        parts.push(Part {
+            command,
+            basename,
+            redirects,
+        });
+    }
+    Some(parts)
+}
+
+/// Split a command line at `;`, `&`, `|`, newlines and parentheses outside of
+/// quotes. `None` when it contains a `$(...)` or backtick substitution.
+fn command_segments(command: &str) -> Option<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut chars = command.chars().peekable();
+    let (mut single, mut double) = (false, false);
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if !single => {
+                current.push(c);
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            '\'' if !double => {
+                single = !single;
+                current.push(c);
+            }
+            '"' if !single => {
+                double = !double;
+                current.push(c);
+            }
+            '`' if !single => return None,
+            '$' if !single && chars.peek() == Some(&'(') => return None,
+            // `2>&1`, `>|` and `&>` are redirections, not separators
+            '&' | '|' if !single && !double && current.ends_with(['<', '>']) => current.push(c),
+            '&' if !single && !double && chars.peek() == Some(&'>') => current.push(c),
+            ';' | '&' | '|' | '\n' | '(' | ')' if !single && !double => {
+                segments.push(std::mem::take(&mut current));
+            }
+            _ => current.push(c),
+        }
+    }
+    segments.push(current);
+
+    Some(
+        segments
+            .into_iter()
+            .map(|segment| segment.trim().to_string())
+            .filter(|segment| !segment.is_empty())
+            .collect(),
+    )
+}
+
+/// What one part of a command line runs.
+#[derive(Debug, PartialEq)]
+enum Invocation {
+    /// Only keywords or assignments
+    Nothing,
+    /// A script run by `sh -c` or `eval`, to be checked on its own
+    Script(String),
+    /// A program and its arguments; `basename` has the program's directory stripped
+    Command { command: String, basename: String },
+}
+
+fn invocation(words: &[String]) -> Invocation {
+    let mut rest = words;
+    loop {
+        let Some((first, args)) = rest.split_first() else {
+            return Invocation::Nothing;
+        };
+        if is_assignment(first) || KEYWORDS.contains(&first.as_str()) {
+            rest = args;
+            continue;
+        }
+
+        let program = first.rsplit('/').next().unwrap_or(first);
+        if let Some((_, valued, operands)) = WRAPPERS.iter().find(|(name, ..)| *name == program) {
+            rest = skip_options(args, valued, *operands);
+            continue;
+        }
+        if program == "eval" {
+            return Invocation::Script(args.join(" "));
+        }
+        if SHELLS.contains(&program) {
+            // `-c` may be bundled with other flags, as in `bash -lc`
+            let script = args
+                .iter()
+                .position(|arg| arg.starts_with('-') && !arg.starts_with("--") && arg.contains('c'))
+                .and_then(|i| args.get(i + 1));
+            if let Some(script) = script {
+                return Invocation::Script(script.clone());
+            }
+        }
+
+        let join = |program: &str| {
+            std::iter::once(program)
+                .chain(args.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        return Invocation::Command {
+            command: join(first),
+            basename: join(program),
+        };
+    }
+}
+
+/// The arguments of a wrapper after its options and leading operands.
+fn skip_options<'a>(args: &'a [String], valued: &[&str], operands: usize) -> &'a [String] {
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        if arg == "--" {
+            i += 1;
+            break;
+        }
+        if !arg.starts_with('-') {
+            break;
+        }
+        i += if valued.contains(&arg.as_str()) { 2 } else { 1 };
+    }
+    &args[(i + operands).min(args.len())..]
+}
+
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Split one part of a command line into words as the shell would, removing
+/// quotes and backslash escapes. Redirections are returned apart from the words.
+fn shell_words(segment: &str) -> (Vec<String>, Vec<Redirect>) {
+    let mut words = Vec::new();
+    let mut redirects = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    // Whether the word being read is the target of a redirection, and how it is used
+    let mut target: Option<Option<Access>> = None;
+    let mut chars = segment.chars().peekable();
+    let (mut single, mut double) = (false, false);
+
+    let mut finish = |word: &mut String, target: &mut Option<Option<Access>>| {
+        let word = std::mem::take(word);
+        match target.take() {
+            Some(Some(access)) => redirects.push(Redirect {
+                target: word,
+                access,
+            }),
+            // A here-document delimiter or a duplicated descriptor
+            Some(None) => {}
+            None => words.push(word),
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if !double => {
+                single = !single;
+                in_word = true;
+            }
+            '"' if !single => {
+                double = !double;
+                in_word = true;
+            }
+            '\\' if !single => {
+                in_word = true;
+                match chars.next() {
+                    // A line continuation
+                    Some('\n') => {}
+                    // Inside double quotes only these are escapes
+                    Some(next) if double && !matches!(next, '$' | '`' | '"' | '\\') => {
+                        word.push('\\');
+                        word.push(next);
+                    }
+                    Some(next) => word.push(next),
+                    None => {}
+                }
+            }
+            '<' | '>' if !single && !double => {
+                // A descriptor number or `&` right before the operator belongs to it
+                let number = word == "&" || word.chars().all(|d| d.is_ascii_digit());
+                if in_word && !word.is_empty() && number {
+                    word.clear();
+                    in_word = false;
+                }
+                if in_word {
+                    finish(&mut word, &mut target);
+                    in_word = false;
+                }
+                let mut access = Some(if c == '<' { Access::Read } else { Access::Write });
+                match chars.peek() {
+                    Some('>') | Some('|') => {
+                        // `>>`, `>|` and the read-write `<>`
+                        chars.next();
+                        access = Some(Access::Write);
+                    }
+                    Some('<') if c == '<' => {
+                        // A here-document or here-string carries its own text
+                        chars.next();
+                        chars.next_if_eq(&'<');
+                        chars.next_if_eq(&'-');
+                        access = None;
+                    }
+                    _ => {}
+                }
+                if chars.next_if_eq(&'&').is_some() {
+                    // `>&2` duplicates a descriptor; `>& file` is bash for `&>`
+                    let descriptor = chars.peek().is_some_and(|d| d.is_ascii_digit() || *d == '-');
+                    if descriptor {
+                        access = None;
+                    }
+                }
+                target = Some(access);
+            }
+            c if c.is_whitespace() && !single && !double => {
+                if in_word {
+                    finish(&mut word, &mut target);
+                    in_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word || target.is_some() {
+        finish(&mut word, &mut target);
+    }
+    (words, redirects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(toml: &str) -> ShellPolicy {
+        toml::from_str(toml).unwrap()
+    }
+
+    /// Check with the whole filesystem as the workspace, so only the rules decide.
+    fn check(policy: &ShellPolicy, command: &str) -> Result<(), PolicyDenial> {
+        let anywhere = Workspace::from_specs(&["/".to_string()]).unwrap();
+        policy.check(command, Path::new("/"), &anywhere)
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*_TOKEN", "GITHUB_TOKEN"));
+        assert!(!glob_match("*_TOKEN", "GITHUB_TOKENS"));
+        assert!(glob_match("AWS_*", "AWS_SECRET_ACCESS_KEY"));
+        assert!(glob_match("cargo * --release", "cargo build --release"));
+        assert!(glob_match("ls ?", "ls a"));
+        assert!(!glob_match("ls ?", "ls ab"));
+    }
+
+    #[test]
+    fn test_prefix_rules_match_whole_words() {
+        let policy = policy(r#"allow = ["git status", "cargo *"]"#);
+        assert!(check(&policy, "git status -s").is_ok());
+        assert!(check(&policy, "cargo test").is_ok());
+        assert_eq!(check(&policy, "git statusx").unwrap_err().code, COMMAND_NOT_ALLOWED);
+        assert_eq!(check(&policy, "git push").unwrap_err().code, COMMAND_NOT_ALLOWED);
+    }
+
+    #[test]
+    fn test_every_part_of_a_chain_is_checked() {
+        let policy = policy(r#"allow = ["git status", "echo"]"#);
+        assert!(check(&policy, "git status && echo done").is_ok());
+        assert!(check(&policy, "echo 'a; rm -rf ~'").is_ok());
+
+        let denied = check(&policy, "git status; rm -rf ~").unwrap_err();
+        assert_eq!(denied.command.as_deref(), Some("rm -rf ~"));
+        assert!(check(&policy, "git status | sh").is_err());
+        assert!(check(&policy, "echo $(rm -rf ~)").is_err());
+        assert!(check(&policy, "echo `id`").is_err());
+    }
+
+    #[test]
+    fn test_deny_rules_win() {
+        let policy = policy(
+            r#"
+            deny = ["sudo", "rm -rf /*"]
+            "#,
+        );
+        assert!(check(&policy, "ls -la").is_ok());
+        assert!(check(&policy, "echo $(date)").is_err());
+
+        let denied = check(&policy, "FOO=1 sudo ls").unwrap_err();
+        assert_eq!(denied.code, COMMAND_DENIED);
+        assert_eq!(denied.rule.as_deref(), Some("sudo"));
+        assert_eq!(denied.message(), "`sudo ls` is denied by the shell policy rule `sudo`");
+
+        let both = ShellPolicy {
+            allow: vec!["rm *".to_string()],
+            ..policy
+        };
+        assert_eq!(check(&both, "rm -rf /etc").unwrap_err().code, COMMAND_DENIED);
+        assert!(check(&both, "rm -rf build").is_ok());
+    }
+
+    #[test]
+    fn test_deny_rules_see_through_shell_syntax() {
+        let policy = policy(r#"deny = ["sudo"]"#);
+        for command in [
+            "{ sudo x; }",
+            "if true; then sudo x; fi",
+            "while true; do sudo x; done",
+            "! sudo x",
+            "exec sudo x",
+            "env -u HOME FOO=1 sudo x",
+            "command sudo x",
+            "nohup sudo x &",
+            "nice -n 5 sudo x",
+            "time -p sudo x",
+            "/usr/bin/time -f %e sudo x",
+            "timeout -s KILL 10 sudo x",
+            "bash -c 'sudo x'",
+            "sh -lc \"echo hi; sudo x\"",
+            "eval 'sudo x'",
+            "\"sudo\" x",
+            "'sudo' x",
+            "\\sudo x",
+            "s'u'do x",
+            "/usr/bin/sudo x",
+            "FOO=1 /usr/bin/env sudo x",
+        ] {
+            let denied = check(&policy, command).unwrap_err();
+            assert_eq!(denied.code, COMMAND_DENIED, "{}", command);
+        }
+        assert!(check(&policy, "echo sudo").is_ok());
+        assert!(check(&policy, "sudoku --solve").is_ok());
+        assert!(check(&policy, "if true; then echo x; fi").is_ok());
+
+        let denied = check(&policy, "/usr/bin/sudo ls").unwrap_err();
+        assert_eq!(denied.command.as_deref(), Some("/usr/bin/sudo ls"));
+    }
+
+    #[test]
+    fn test_allow_rules_match_the_program_as_written() {
+        let policy = policy(r#"allow = ["git status", "echo"]"#);
+        assert!(check(&policy, "{ git status; }").is_ok());
+        assert!(check(&policy, "env GIT_PAGER=cat git status").is_ok());
+        assert!(check(&policy, "bash -c 'git status && echo ok'").is_ok());
+        assert!(check(&policy, "\"git\" status").is_ok());
+        assert_eq!(check(&policy, "./git status").unwrap_err().code, COMMAND_NOT_ALLOWED);
+        assert_eq!(check(&policy, "bash -c 'rm x'").unwrap_err().command.as_deref(), Some("rm x"));
+        assert_eq!(check(&policy, "bash script.sh").unwrap_err().code, COMMAND_NOT_ALLOWED);
+    }
+
+    #[test]
+    fn test_shell_words() {
+        assert_eq!(
+            shell_words(r#"a "b c" 'd "e"' f\ g "h\"i" "j\k" ''"#).0,
+            vec!["a", "b c", "d \"e\"", "f g", "h\"i", "j\\k", ""]
+        );
+    }
+
+    #[test]
+    fn test_open_policy_allows_everything() {
+        let policy = ShellPolicy::default();
+        assert!(check(&policy, "anything at all | sh").is_ok());
+        assert!(check(&policy, "echo $(date)").is_ok());
+    }
+
+    #[test]
+    fn test_interactive_shell_needs_opt_in() {
+        let denied = ShellPolicy::default().check_interactive().unwrap_err();
+        assert_eq!(denied.code, COMMAND_NOT_ALLOWED);
+        assert_eq!(denied.message(), "an interactive shell is not allowed by the shell policy");
+
+        assert!(policy("interactive_shell = true").check_interactive().is_ok());
+        assert!(policy("interactive_shell = true\ndeny = [\"sudo\"]").check_interactive().is_ok());
+
+        // An allow list cannot be enforced inside a shell the user drives
+        let restricted = policy("interactive_shell = true\nallow = [\"git status\"]");
+        assert_eq!(restricted.check_interactive().unwrap_err().code, COMMAND_NOT_ALLOWED);
+        let closed = policy("interactive_shell = true\ndefault = \"deny\"");
+        assert!(closed.check_interactive().is_err());
+    }
+
+    #[test]
+    fn test_env_is_scrubbed() {
+        let policy = policy(
+            r#"
+            env_allow = ["GITHUB_TOKEN"]
+            env_deny = ["*_PASSWORD"]
+            "#,
+        );
+        let env: HashMap<String, String> = [
+            "PATH",
+            "HOME",
+            "GITHUB_TOKEN",
+            "NPM_TOKEN",
+            "AWS_ACCESS_KEY_ID",
+            "DB_PASSWORD",
+        ]
+        .iter()
+        .map(|name| (name.to_string(), "x".to_string()))
+        .collect();
+
+        let mut kept: Vec<String> = policy.scrub_env(env).into_keys().collect();
+        kept.sort();
+        assert_eq!(kept, vec!["GITHUB_TOKEN", "HOME", "PATH"]);
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let policy = policy(
+            r#"
+            timeout_secs = 60
+
+            [[timeouts]]
+            command = "cargo test*"
+            secs = 900
+            "#,
+        );
+        assert_eq!(policy.timeout_for("cargo test --all"), Some(Duration::from_secs(900)));
+        assert_eq!(policy.timeout_for("ls"), Some(Duration::from_secs(60)));
+        assert_eq!(ShellPolicy::default().timeout_for("ls"), None);
+
+        // Rules match each part as it runs, and the longest part wins
+        assert_eq!(policy.timeout_for("true; sleep 600"), Some(Duration::from_secs(60)));
+        assert_eq!(policy.timeout_for("cd x && cargo test"), Some(Duration::from_secs(900)));
+        assert_eq!(policy.timeout_for("env CI=1 cargo test"), Some(Duration::from_secs(900)));
+        assert_eq!(policy.timeout_for("bash -c 'cargo test'"), Some(Duration::from_secs(900)));
+
+        let quick: ShellPolicy = toml::from_str(
+            r#"
+            [[timeouts]]
+            command = "true"
+            secs = 5
+            "#,
+        )
+        .unwrap();
+        assert_eq!(quick.timeout_for("true"), Some(Duration::from_secs(5)));
+        assert_eq!(quick.timeout_for("true; sleep 600"), None);
+    }
+
+    #[test]
+    fn test_redirections_stay_in_the_workspace() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        let workspace = Workspace::from_specs(&[root.display().to_string()]).unwrap();
+        let policy = policy(r#"deny = ["sudo"]"#);
+        let check = |command: &str| policy.check(command, &root, &workspace);
+
+        for command in [
+            "echo x > out.txt",
+            "echo x >> logs/new.txt",
+            "sort < out.txt > sorted.txt",
+            "cargo build 2>&1 | tee build.log",
+            "ls > /dev/null 2>/dev/null",
+            "echo x &> both.log",
+            "cat <<EOF",
+            "echo '>' ~/.bashrc",
+            "exec 3>&-",
+        ] {
+            assert!(check(command).is_ok(), "{}", command);
+        }
+        let outside = format!("{}/../outside.txt", root.display());
+        for command in [
+            "echo x > ~/.bashrc",
+            "git status >> ~/.ssh/authorized_keys",
+            "cat < /etc/shadow",
+            "echo x >/etc/motd",
+            "ls 2> /etc/motd",
+            "echo x &> /etc/motd",
+            "echo x >& /etc/motd",
+            "echo x > $HOME/.bashrc",
+            "echo x > *.txt",
+            "cd / && echo x > out.txt",
+            "bash -c 'echo x' > /etc/motd",
+            "bash -c 'echo x > /etc/motd'",
+            &format!("echo x > {}", outside),
+        ] {
+            let denied = check(command).unwrap_err();
+            assert_eq!(denied.code, REDIRECT_NOT_ALLOWED, "{}", command);
+        }
+        let denied = check("git status >> ~/.ssh/authorized_keys").unwrap_err();
+        assert_eq!(denied.command.as_deref(), Some("git status"));
+        assert_eq!(denied.redirect.as_deref(), Some("~/.ssh/authorized_keys"));
+
+        // Redirections are only confined when the policy confines anything
+        let open = ShellPolicy::default();
+        assert!(open.check("echo x > ~/.bashrc", &root, &workspace).is_ok());
+    }
+
+    #[test]
+    fn test_descriptor_duplication_is_not_a_command() {
+        let policy = policy(r#"allow = ["echo", "cargo build"]"#);
+        assert!(check(&policy, "cargo build 2>&1").is_ok());
+        assert!(check(&policy, "echo x >&2").is_ok());
+        assert!(check(&policy, "echo x >| out.txt").is_ok());
+        assert_eq!(
+            shell_words("cmd 2>&1 >out 3<in <<EOF").1,
+            vec![
+                Redirect {
+                    target: "out".to_string(),
+                    access: Access::Write,
+                },
+                Redirect {
+                    target: "in".to_string(),
+                    access: Access::Read,
+                },
+            ]
+        );
+    }
+}
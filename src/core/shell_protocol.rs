@@ -0,0 +1,270 @@
+//! Frames streamed back by `/shell`, and the limits a request can set.
+//!
+//! Every frame is a one-byte type, a four-byte big-endian length and the
+//! payload. Protocol 1 (the default) sends all output as type 0 and ends with
+//! a status frame. Protocol 2, requested with `"protocol": 2`, keeps stdout
+//! (type 0) and stderr (type 2) apart and follows the status frame with a
+//! stats frame (type 3):
+//!
+//! ```json
+//! {"wall_time_ms":12,"exit_code":0,"exit_signal":null,"stdout_bytes":6,
+//!  "stderr_bytes":0,"truncated":false,"timed_out":false}
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::io;
+
+pub const FRAME_STDOUT: u8 = 0;
+pub const FRAME_STATUS: u8 = 1;
+pub const FRAME_STDERR: u8 = 2;
+pub const FRAME_STATS: u8 = 3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "u8")]
+pub enum ShellProtocol {
+    #[default]
+    V1,
+    V2,
+}
+
+impl TryFrom<u8> for ShellProtocol {
+    type Error = String;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        match version {
+            1 => Ok(ShellProtocol::V1),
+            2 => Ok(ShellProtocol::V2),
+            _ => Err(format!("unsupported shell protocol {}", version)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Summary sent in the final frame of protocol 2.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ShellStats {
+    pub wall_time_ms: u64,
+    pub exit_code: Option<i32>,
+    pub exit_signal: Option<i32>,
+    pub stdout_bytes: u64,
+    pub stderr_bytes: u64,
+    /// Output went past `max_output_bytes` and the command was stopped
+    pub truncated: bool,
+    pub timed_out: bool,
+}
+
+impl ShellProtocol {
+    pub fn data_frame(self, stream: OutputStream, data: &[u8]) -> Vec<u8> {
+        let frame_type = match (self, stream) {
+            (ShellProtocol::V2, OutputStream::Stderr) => FRAME_STDERR,
+            _ => FRAME_STDOUT,
+        };
+        frame(frame_type, data)
+    }
+
+    pub fn status_frame(self, status: i32) -> Vec<u8> {
+        frame(FRAME_STATUS, format!(r#"{{"status":{}}}"#, status).as_bytes())
+    }
+
+    /// The closing stats frame; protocol 1 clients get none.
+    pub fn stats_frame(self, stats: &ShellStats) -> Option<Vec<u8>> {
+        match self {
+            ShellProtocol::V1 => None,
+            ShellProtocol::V2 => {
+                let json = serde_json::to_vec(stats).unwrap_or_default();
+                Some(frame(FRAME_STATS, &json))
+            }
+        }
+    }
+}
+
+fn frame(frame_type: u8, data: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(5 + data.len());
+    frame.push(frame_type);
+    frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    frame.extend_from_slice(data);
+    frame
+}
+
+/// Counts output per stream against an optional cap on stdout and stderr
+/// together.
+#[derive(Debug, Clone, Default)]
+pub struct OutputBudget {
+    limit: Option<u64>,
+    stdout_bytes: u64,
+    stderr_bytes: u64,
+    truncated: bool,
+}
+
+impl OutputBudget {
+    pub fn new(limit: Option<u64>) -> Self {
+        Self {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    /// Account for `len` bytes read from `stream` and return how many of them
+    /// may still be sent.
+    pub fn take(&mut self, stream: OutputStream, len: usize) -> usize {
+        let used = self.stdout_bytes + self.stderr_bytes;
+        let allowed = match self.limit {
+            Some(limit) => (limit.saturating_sub(used)).min(len as u64),
+            None => len as u64,
+        };
+        if allowed < len as u64 {
+            self.truncated = true;
+        }
+        match stream {
+            OutputStream::Stdout => self.stdout_bytes += allowed,
+            OutputStream::Stderr => self.stderr_bytes += allowed,
+        }
+        allowed as usize
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    pub fn stats(&self) -> ShellStats {
+        ShellStats {
+            stdout_bytes: self.stdout_bytes,
+            stderr_bytes: self.stderr_bytes,
+            truncated: self.truncated,
+            ..Default::default()
+        }
+    }
+}
+
+/// Resource limits applied to the command before it starts (Linux only).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceLimits {
+    /// CPU time in seconds (`RLIMIT_CPU`)
+    pub cpu_secs: Option<u64>,
+    /// Address space in bytes (`RLIMIT_AS`)
+    pub memory_bytes: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        self.cpu_secs.is_none() && self.memory_bytes.is_none()
+    }
+
+    /// Set the limits on the calling process. Meant for `pre_exec`, so it
+    /// only makes async-signal-safe calls.
+    #[cfg(target_os = "linux")]
+    pub fn apply(&self) -> io::Result<()> {
+        let limits = [
+            (libc::RLIMIT_CPU, self.cpu_secs),
+            (libc::RLIMIT_AS, self.memory_bytes),
+        ];
+        for (resource, limit) in limits {
+            let Some(limit) = limit else {
+                continue;
+            };
+            let rlimit = libc::rlimit {
+                rlim_cur: limit as libc::rlim_t,
+                rlim_max: limit as libc::rlim_t,
+            };
+            // SAFETY: setrlimit only reads the struct we pass
+            if unsafe { libc::setrlimit(resource, &rlimit) } == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn apply(&self) -> io::Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "resource limits are only supported on Linux",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_versions() {
+        let parse = |json: &str| serde_json::from_str::<ShellProtocol>(json);
+        assert_eq!(parse("1").unwrap(), ShellProtocol::V1);
+        assert_eq!(parse("2").unwrap(), ShellProtocol::V2);
+        assert!(parse("3").is_err());
+    }
+
+    #[test]
+    fn test_frames() {
+        let v1 = ShellProtocol::V1;
+        let v2 = ShellProtocol::V2;
+        assert_eq!(v1.data_frame(OutputStream::Stderr, b"e"), vec![FRAME_STDOUT, 0, 0, 0, 1, b'e']);
+        assert_eq!(v2.data_frame(OutputStream::Stdout, b"o"), vec![FRAME_STDOUT, 0, 0, 0, 1, b'o']);
+        assert_eq!(v2.data_frame(OutputStream::Stderr, b"e"), vec![FRAME_STDERR, 0, 0, 0, 1, b'e']);
+
+        let status = v2.status_frame(3);
+        assert_eq!(status[0], FRAME_STATUS);
+        assert_eq!(&status[5..], br#"{"status":3}"#);
+
+        let stats = ShellStats {
+            exit_code: Some(0),
+            stdout_bytes: 6,
+            ..Default::default()
+        };
+        assert_eq!(v1.stats_frame(&stats), None);
+        let frame = v2.stats_frame(&stats).unwrap();
+        assert_eq!(frame[0], FRAME_STATS);
+        let len = u32::from_be_bytes(frame[1..5].try_into().unwrap()) as usize;
+        assert_eq!(len, frame.len() - 5);
+        let json: serde_json::Value = serde_json::from_slice(&frame[5..]).unwrap();
+        assert_eq!(json["stdout_bytes"], 6);
+        assert_eq!(json["exit_signal"], serde_json::Value::Null);
+        assert_eq!(json["truncated"], false);
+    }
+
+    #[test]
+    fn test_output_budget() {
+        let mut unlimited = OutputBudget::new(None);
+        assert_eq!(unlimited.take(OutputStream::Stdout, 4096), 4096);
+        assert!(!unlimited.is_truncated());
+
+        let mut budget = OutputBudget::new(Some(10));
+        assert_eq!(budget.take(OutputStream::Stdout, 6), 6);
+        assert_eq!(budget.take(OutputStream::Stderr, 6), 4);
+        assert!(budget.is_truncated());
+        assert_eq!(budget.take(OutputStream::Stdout, 1), 0);
+
+        let stats = budget.stats();
+        assert_eq!((stats.stdout_bytes, stats.stderr_bytes), (6, 4));
+        assert!(stats.truncated);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_limits_apply_to_the_child() {
+        use std::os::unix::process::CommandExt;
+
+        let limits = ResourceLimits {
+            cpu_secs: Some(7),
+            memory_bytes: Some(512 * 1024 * 1024),
+        };
+        let mut command = std::process::Command::new("/bin/sh");
+        command.arg("-c").arg("ulimit -t; ulimit -v");
+        // SAFETY: `apply` only calls setrlimit
+        unsafe {
+            command.pre_exec(move || limits.apply());
+        }
+        let output = command.output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "7\n524288\n");
+    }
+}
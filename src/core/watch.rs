@@ -0,0 +1,1049 @@
+//! Filesystem change events for subscribers on `/socket/watch`.
+//!
+//! Each connection is one channel. It subscribes to a directory inside a
+//! workspace root and a set of globs relative to it:
+//!
+//! ```json
+//! {"type":"subscribe","path":"/repo","globs":["src/**/*.rs"],"debounce_ms":100}
+//! {"type":"unsubscribe","subscription":"…"}
+//! ```
+//!
+//! and is answered with `subscribed`, `unsubscribed` or `error` in order. A
+//! channel holds at most `MAX_SUBSCRIPTIONS_PER_CHANNEL` subscriptions, as each
+//! one has its own inotify instance and thread.
+//!
+//! The directory tree is watched with inotify, skipping `.git`, directories
+//! excluded by `.gitignore` and directories no glob can match. Changes are
+//! debounced: events for one path within the window are merged (a file
+//! created and deleted again is not reported at all) and sent as one batch:
+//!
+//! ```json
+//! {"type":"events","subscription":"…","events":[
+//!  {"kind":"create","path":"src/new.rs","is_dir":false},
+//!  {"kind":"rename","from":"a.txt","path":"b.txt","is_dir":false}]}
+//! ```
+//!
+//! An `overflow` event means the kernel dropped events and the client should
+//! rescan. Subscriptions end when the channel closes, i.e. when its
+//! `WatchSession` is dropped.
+#![cfg(target_os = "linux")]
+
+use crate::path_match::{join, strip_dir, GitIgnore, Glob};
+use crate::workspace::{Access, AccessDenied, Workspace};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::{CString, OsStr};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+pub type ChannelId = Uuid;
+pub type SubscriptionId = String;
+
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(100);
+pub const MAX_SUBSCRIPTIONS_PER_CHANNEL: usize = 16;
+const MAX_DEBOUNCE: Duration = Duration::from_secs(10);
+/// A steady stream of changes is still flushed at least this often.
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(1);
+const EVENT_BUFFER_SIZE: usize = 64 * 1024;
+
+const WATCH_MASK: u32 = libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_MODIFY
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_ONLYDIR
+    | libc::IN_DONT_FOLLOW
+    | libc::IN_EXCL_UNLINK;
+
+pub const INVALID_WATCH: &str = "invalid_watch";
+pub const WATCH_FAILED: &str = "watch_failed";
+pub const TOO_MANY_WATCHES: &str = "too_many_watches";
+pub const UNKNOWN_WATCH: &str = "unknown_watch";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchParams {
+    /// Directory to watch
+    pub path: String,
+    /// Globs relative to `path`; everything below it by default
+    #[serde(default = "default_globs")]
+    pub globs: Vec<String>,
+    /// Leave out files excluded by `.gitignore`
+    #[serde(default = "default_gitignore")]
+    pub gitignore: bool,
+    pub debounce_ms: Option<u64>,
+}
+
+fn default_globs() -> Vec<String> {
+    vec!["**".to_string()]
+}
+
+fn default_gitignore() -> bool {
+    true
+}
+
+/// A message from the client.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WatchRequest {
+    Subscribe(WatchParams),
+    Unsubscribe { subscription: SubscriptionId },
+}
+
+/// A message to the client.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WatchReply {
+    Subscribed(Subscribed),
+    Unsubscribed { subscription: SubscriptionId },
+    Events(WatchBatch),
+    Error(WatchError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WatchEvent {
+    Create { path: String, is_dir: bool },
+    Modify { path: String, is_dir: bool },
+    Delete { path: String, is_dir: bool },
+    Rename { from: String, path: String, is_dir: bool },
+    /// Events were lost; the client should rescan
+    Overflow,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchBatch {
+    pub subscription: SubscriptionId,
+    pub events: Vec<WatchEvent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Subscribed {
+    pub subscription: SubscriptionId,
+    /// Canonical path of the watched directory; event paths are relative to it
+    pub root: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WatchError {
+    pub success: bool,
+    pub code: &'static str,
+    pub error: String,
+}
+
+impl WatchError {
+    fn new(code: &'static str, error: String) -> Self {
+        Self {
+            success: false,
+            code,
+            error,
+        }
+    }
+}
+
+impl From<AccessDenied> for WatchError {
+    fn from(denied: AccessDenied) -> Self {
+        Self::new(denied.code, denied.error)
+    }
+}
+
+/// Subscriptions of every channel, so they can be dropped with the channel.
+#[derive(Clone)]
+pub struct WatchChannelState {
+    workspace: Workspace,
+    channels: Arc<DashMap<ChannelId, HashMap<SubscriptionId, Subscription>>>,
+}
+
+impl WatchChannelState {
+    pub fn new(workspace: Workspace) -> Self {
+        Self {
+            workspace,
+            channels: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Start watching and send batches to `tx` until the subscription is
+    /// dropped or `tx` is closed. Blocks while the tree is walked.
+    pub fn subscribe(
+        &self,
+        channel_id: ChannelId,
+        params: WatchParams,
+        tx: mpsc::UnboundedSender<WatchBatch>,
+    ) -> Result<Subscribed, WatchError> {
+        let root = self.workspace.resolve(&params.path, Access::Read)?;
+        if !root.is_dir() {
+            return Err(WatchError::new(
+                INVALID_WATCH,
+                format!("{} is not a directory", root.display()),
+            ));
+        }
+        let too_many = || {
+            WatchError::new(
+                TOO_MANY_WATCHES,
+                format!(
+                    "a channel can have at most {} subscriptions",
+                    MAX_SUBSCRIPTIONS_PER_CHANNEL
+                ),
+            )
+        };
+        let count = self.channels.get(&channel_id).map_or(0, |subscriptions| subscriptions.len());
+        if count >= MAX_SUBSCRIPTIONS_PER_CHANNEL {
+            return Err(too_many());
+        }
+        if params.globs.is_empty() {
+            return Err(WatchError::new(INVALID_WATCH, "no globs given".to_string()));
+        }
+        let globs = params
+            .globs
+            .iter()
+            .map(|glob| Glob::new(glob))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| WatchError::new(INVALID_WATCH, e.error))?;
+        let debounce = params
+            .debounce_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_DEBOUNCE);
+        if debounce > MAX_DEBOUNCE {
+            return Err(WatchError::new(
+                INVALID_WATCH,
+                format!("debounce_ms is limited to {}", MAX_DEBOUNCE.as_millis()),
+            ));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let ignore = params.gitignore.then(GitIgnore::default);
+        let subscription =
+            Subscription::start(id.clone(), root.clone(), globs, ignore, debounce, tx).map_err(|e| {
+                let error = format!("cannot watch {}: {}", root.display(), e);
+                WatchError::new(WATCH_FAILED, error)
+            })?;
+        {
+            // Checked again: the channel may have subscribed while the tree was walked
+            let mut subscriptions = self.channels.entry(channel_id).or_default();
+            if subscriptions.len() >= MAX_SUBSCRIPTIONS_PER_CHANNEL {
+                return Err(too_many());
+            }
+            subscriptions.insert(id.clone(), subscription);
+        }
+        debug!("Channel {} watching {} ({})", channel_id, root.display(), id);
+        Ok(Subscribed {
+            subscription: id,
+            root: root.to_string_lossy().into_owned(),
+        })
+    }
+
+    pub fn unsubscribe(&self, channel_id: ChannelId, subscription: &str) -> bool {
+        let removed = self
+            .channels
+            .get_mut(&channel_id)
+            .and_then(|mut subscriptions| subscriptions.remove(subscription))
+            .is_some();
+        self.channels
+            .remove_if(&channel_id, |_, subscriptions| subscriptions.is_empty());
+        removed
+    }
+
+    /// Drop every subscription of a channel that went away.
+    pub fn close_channel(&self, channel_id: ChannelId) {
+        if let Some((_, subscriptions)) = self.channels.remove(&channel_id) {
+            debug!("Dropping {} watches of channel {}", subscriptions.len(), channel_id);
+        }
+    }
+
+    pub fn clear(&self) {
+        self.channels.clear();
+    }
+}
+
+/// One client connection, i.e. one channel. Its subscriptions end when it is
+/// dropped.
+pub struct WatchSession {
+    state: WatchChannelState,
+    channel_id: ChannelId,
+    tx: mpsc::UnboundedSender<WatchBatch>,
+    batches: mpsc::UnboundedReceiver<WatchBatch>,
+}
+
+impl WatchSession {
+    pub fn new(state: WatchChannelState) -> Self {
+        let (tx, batches) = mpsc::unbounded_channel();
+        Self {
+            state,
+            channel_id: Uuid::new_v4(),
+            tx,
+            batches,
+        }
+    }
+
+    /// Answer one message from the client.
+    pub async fn handle(&self, text: &str) -> WatchReply {
+        let request = match serde_json::from_str::<WatchRequest>(text) {
+            Ok(request) => request,
+            Err(e) => return WatchReply::Error(WatchError::new(INVALID_WATCH, e.to_string())),
+        };
+        match request {
+            WatchRequest::Subscribe(params) => {
+                let state = self.state.clone();
+                let (channel_id, tx) = (self.channel_id, self.tx.clone());
+                // Walking the tree blocks
+                let subscribed = tokio::task::spawn_blocking(move || {
+                    let subscribed = state.subscribe(channel_id, params, tx.clone())?;
+                    // The session went away meanwhile, after dropping the channel
+                    if tx.is_closed() {
+                        state.unsubscribe(channel_id, &subscribed.subscription);
+                    }
+                    Ok(subscribed)
+                })
+                .await
+                .unwrap_or_else(|e| Err(WatchError::new(WATCH_FAILED, e.to_string())));
+                match subscribed {
+                    Ok(subscribed) => WatchReply::Subscribed(subscribed),
+                    Err(e) => WatchReply::Error(e),
+                }
+            }
+            WatchRequest::Unsubscribe { subscription } => {
+                if self.state.unsubscribe(self.channel_id, &subscription) {
+                    WatchReply::Unsubscribed { subscription }
+                } else {
+                    let error = format!("no subscription {}", subscription);
+                    WatchReply::Error(WatchError::new(UNKNOWN_WATCH, error))
+                }
+            }
+        }
+    }
+
+    /// The next batch of events, from any of the channel's subscriptions.
+    pub async fn next_batch(&mut self) -> Option<WatchBatch> {
+        self.batches.recv().await
+    }
+}
+
+impl Drop for WatchSession {
+    fn drop(&mut self) {
+        // Closed first, so a subscription still being set up sees it and
+        // removes itself if it lands after `close_channel`
+        self.batches.close();
+        self.state.close_channel(self.channel_id);
+    }
+}
+
+/// Handle to a watcher thread; dropping it stops the thread.
+struct Subscription {
+    wake: Arc<OwnedFd>,
+}
+
+impl Subscription {
+    fn start(
+        id: SubscriptionId,
+        root: PathBuf,
+        globs: Vec<Glob>,
+        ignore: Option<GitIgnore>,
+        debounce: Duration,
+        tx: mpsc::UnboundedSender<WatchBatch>,
+    ) -> io::Result<Self> {
+        // SAFETY: plain syscalls; the fds are owned right after
+        let inotify = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+        if inotify == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let inotify = unsafe { OwnedFd::from_raw_fd(inotify) };
+        let wake = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if wake == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let wake = Arc::new(unsafe { OwnedFd::from_raw_fd(wake) });
+
+        let mut watcher = Watcher {
+            id,
+            root,
+            inotify,
+            wake: wake.clone(),
+            dirs: HashMap::new(),
+            globs,
+            ignore,
+            debounce,
+            pending: Pending::default(),
+            moves: HashMap::new(),
+            tx,
+        };
+        watcher.add_watch("")?;
+        watcher.watch_tree("", &mut Vec::new());
+        std::thread::Builder::new()
+            .name("fs-watch".to_string())
+            .spawn(move || watcher.run())?;
+        Ok(Self { wake })
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let one = 1u64.to_ne_bytes();
+        // SAFETY: writes 8 bytes from a live buffer to the eventfd
+        unsafe { libc::write(self.wake.as_raw_fd(), one.as_ptr().cast(), one.len()) };
+    }
+}
+
+/// Events waiting for the debounce window to close.
+#[derive(Debug, Default)]
+struct Pending {
+    events: Vec<Option<WatchEvent>>,
+    /// Index of the create/modify/delete event of each path
+    by_path: HashMap<String, usize>,
+    first_at: Option<Instant>,
+    last_at: Option<Instant>,
+}
+
+impl Pending {
+    fn touch(&mut self, now: Instant) {
+        self.first_at.get_or_insert(now);
+        self.last_at = Some(now);
+    }
+
+    fn record(&mut self, event: WatchEvent, now: Instant) {
+        self.touch(now);
+        match event {
+            WatchEvent::Rename { from, path, is_dir } => {
+                self.take(&path);
+                match self.take(&from) {
+                    // Created and renamed in the same window
+                    Some(WatchEvent::Create { .. }) => {
+                        self.merge(WatchEvent::Create { path, is_dir });
+                    }
+                    _ => self.events.push(Some(WatchEvent::Rename { from, path, is_dir })),
+                }
+            }
+            WatchEvent::Overflow => {
+                if !self.events.contains(&Some(WatchEvent::Overflow)) {
+                    self.events.push(Some(WatchEvent::Overflow));
+                }
+            }
+            event => self.merge(event),
+        }
+    }
+
+    fn merge(&mut self, event: WatchEvent) {
+        let Some(path) = event_path(&event).map(str::to_string) else {
+            return;
+        };
+        match self.by_path.get(&path) {
+            Some(&index) => {
+                let merged = match self.events[index].take() {
+                    Some(old) => combine(old, event),
+                    None => Some(event),
+                };
+                if merged.is_none() {
+                    self.by_path.remove(&path);
+                }
+                self.events[index] = merged;
+            }
+            None => {
+                self.by_path.insert(path, self.events.len());
+                self.events.push(Some(event));
+            }
+        }
+    }
+
+    fn take(&mut self, path: &str) -> Option<WatchEvent> {
+        let index = self.by_path.remove(path)?;
+        self.events[index].take()
+    }
+
+    /// When the batch should be sent: after a quiet `debounce`, but no later
+    /// than `MAX_BATCH_DELAY` after its first event.
+    fn deadline(&self, debounce: Duration) -> Option<Instant> {
+        let (first_at, last_at) = (self.first_at?, self.last_at?);
+        Some((last_at + debounce).min(first_at + debounce.max(MAX_BATCH_DELAY)))
+    }
+
+    fn drain(&mut self) -> Vec<WatchEvent> {
+        self.by_path.clear();
+        self.first_at = None;
+        self.last_at = None;
+        self.events.drain(..).flatten().collect()
+    }
+}
+
+fn event_path(event: &WatchEvent) -> Option<&str> {
+    match event {
+        WatchEvent::Create { path, .. }
+        | WatchEvent::Modify { path, .. }
+        | WatchEvent::Delete { path, .. }
+        | WatchEvent::Rename { path, .. } => Some(path),
+        WatchEvent::Overflow => None,
+    }
+}
+
+/// Merge two events for the same path; `None` if they cancel out.
+fn combine(old: WatchEvent, new: WatchEvent) -> Option<WatchEvent> {
+    match (old, new) {
+        (WatchEvent::Create { .. }, WatchEvent::Delete { .. }) => None,
+        (
+            WatchEvent::Create { .. },
+            WatchEvent::Create { path, is_dir } | WatchEvent::Modify { path, is_dir },
+        ) => Some(WatchEvent::Create { path, is_dir }),
+        // Deleted and created again, or modified and recreated: the path
+        // still exists with new contents
+        (
+            WatchEvent::Delete { .. } | WatchEvent::Modify { .. },
+            WatchEvent::Create { path, is_dir },
+        ) => Some(WatchEvent::Modify { path, is_dir }),
+        (_, new) => Some(new),
+    }
+}
+
+struct Watcher {
+    id: SubscriptionId,
+    root: PathBuf,
+    inotify: OwnedFd,
+    wake: Arc<OwnedFd>,
+    /// Watch descriptor to the directory it watches, relative to `root`
+    dirs: HashMap<i32, String>,
+    globs: Vec<Glob>,
+    ignore: Option<GitIgnore>,
+    debounce: Duration,
+    pending: Pending,
+    /// `IN_MOVED_FROM` halves waiting for their `IN_MOVED_TO`, by cookie
+    moves: HashMap<u32, (String, bool)>,
+    tx: mpsc::UnboundedSender<WatchBatch>,
+}
+
+impl Watcher {
+    fn run(mut self) {
+        let mut buffer = vec![0u8; EVENT_BUFFER_SIZE];
+        loop {
+            let timeout = match self.pending.deadline(self.debounce) {
+                Some(deadline) => {
+                    let wait = deadline.saturating_duration_since(Instant::now());
+                    wait.as_micros().div_ceil(1000) as i32
+                }
+                None => -1,
+            };
+            let mut fds = [
+                libc::pollfd {
+                    fd: self.inotify.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                },
+                libc::pollfd {
+                    fd: self.wake.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                },
+            ];
+            // SAFETY: polls two fds we own for the duration of the call
+            if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) } == -1 {
+                let error = io::Error::last_os_error();
+                if error.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                warn!("Watch {} failed: {}", self.id, error);
+                break;
+            }
+            if fds[1].revents != 0 {
+                break;
+            }
+            if fds[0].revents & libc::POLLIN != 0 {
+                // SAFETY: reads into a live buffer of the given length
+                let read = unsafe {
+                    libc::read(self.inotify.as_raw_fd(), buffer.as_mut_ptr().cast(), buffer.len())
+                };
+                if read > 0 {
+                    self.handle_events(&buffer[..read as usize]);
+                }
+            }
+            let due = self
+                .pending
+                .deadline(self.debounce)
+                .is_some_and(|deadline| deadline <= Instant::now());
+            if due && !self.flush() {
+                break;
+            }
+        }
+        debug!("Stopped watch {} of {}", self.id, self.root.display());
+    }
+
+    fn handle_events(&mut self, buffer: &[u8]) {
+        let header = std::mem::size_of::<libc::inotify_event>();
+        let mut offset = 0;
+        while offset + header <= buffer.len() {
+            // SAFETY: the kernel wrote a whole event header at `offset`
+            let event: libc::inotify_event =
+                unsafe { std::ptr::read_unaligned(buffer[offset..].as_ptr().cast()) };
+            let name_end = (offset + header + event.len as usize).min(buffer.len());
+            let name = &buffer[offset + header..name_end];
+            // The name is padded with NULs
+            let name = name.split(|&b| b == 0).next().unwrap_or_default();
+            let name = OsStr::from_bytes(name).to_string_lossy().into_owned();
+            self.handle_event(event.wd, event.mask, event.cookie, &name);
+            offset = name_end;
+        }
+    }
+
+    fn handle_event(&mut self, wd: i32, mask: u32, cookie: u32, name: &str) {
+        let now = Instant::now();
+        if mask & libc::IN_Q_OVERFLOW != 0 {
+            self.pending.record(WatchEvent::Overflow, now);
+            return;
+        }
+        if mask & libc::IN_IGNORED != 0 {
+            self.dirs.remove(&wd);
+            return;
+        }
+        // Events about a watched directory itself are also reported, by
+        // name, to the watch of its parent
+        let Some(dir) = self.dirs.get(&wd).cloned() else {
+            return;
+        };
+        if name.is_empty() {
+            return;
+        }
+        let path = join(&dir, name);
+        let is_dir = mask & libc::IN_ISDIR != 0;
+        if name == ".gitignore" {
+            if let Some(ignore) = &mut self.ignore {
+                ignore.load_dir(&self.root, &dir);
+            }
+        }
+
+        if mask & libc::IN_CREATE != 0 {
+            self.pending.record(WatchEvent::Create { path: path.clone(), is_dir }, now);
+            if is_dir {
+                self.watch_new_dir(&path, now);
+            }
+        } else if mask & libc::IN_MODIFY != 0 {
+            self.pending.record(WatchEvent::Modify { path, is_dir }, now);
+        } else if mask & libc::IN_DELETE != 0 {
+            self.pending.record(WatchEvent::Delete { path, is_dir }, now);
+        } else if mask & libc::IN_MOVED_FROM != 0 {
+            self.moves.insert(cookie, (path, is_dir));
+            self.pending.touch(now);
+        } else if mask & libc::IN_MOVED_TO != 0 {
+            match self.moves.remove(&cookie) {
+                Some((from, _)) => {
+                    if is_dir {
+                        self.rename_tree(&from, &path);
+                    }
+                    self.pending.record(WatchEvent::Rename { from, path, is_dir }, now);
+                }
+                // Moved in from outside the watched tree
+                None => {
+                    self.pending.record(WatchEvent::Create { path: path.clone(), is_dir }, now);
+                    if is_dir {
+                        self.watch_new_dir(&path, now);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Send the pending batch; `false` once nobody is listening.
+    fn flush(&mut self) -> bool {
+        let now = Instant::now();
+        // A move whose other half never came left the watched tree
+        for (_, (path, is_dir)) in std::mem::take(&mut self.moves) {
+            if is_dir {
+                self.unwatch_tree(&path);
+            }
+            self.pending.record(WatchEvent::Delete { path, is_dir }, now);
+        }
+        let events: Vec<WatchEvent> = self
+            .pending
+            .drain()
+            .into_iter()
+            .filter_map(|event| self.visible(event))
+            .collect();
+        if events.is_empty() {
+            return true;
+        }
+        self.tx
+            .send(WatchBatch {
+                subscription: self.id.clone(),
+                events,
+            })
+            .is_ok()
+    }
+
+    /// The event as the client sees it, given its globs and ignore rules. A
+    /// rename across that boundary becomes a create or a delete.
+    fn visible(&self, event: WatchEvent) -> Option<WatchEvent> {
+        match event {
+            WatchEvent::Rename { from, path, is_dir } => {
+                match (self.is_visible(&from, is_dir), self.is_visible(&path, is_dir)) {
+                    (true, true) => Some(WatchEvent::Rename { from, path, is_dir }),
+                    (false, true) => Some(WatchEvent::Create { path, is_dir }),
+                    (true, false) => Some(WatchEvent::Delete { path: from, is_dir }),
+                    (false, false) => None,
+                }
+            }
+            WatchEvent::Create { ref path, is_dir }
+            | WatchEvent::Modify { ref path, is_dir }
+            | WatchEvent::Delete { ref path, is_dir } => {
+                self.is_visible(path, is_dir).then_some(event)
+            }
+            WatchEvent::Overflow => Some(event),
+        }
+    }
+
+    fn is_visible(&self, path: &str, is_dir: bool) -> bool {
+        !self.is_excluded(path, is_dir) && self.globs.iter().any(|glob| glob.is_match(path))
+    }
+
+    fn is_excluded(&self, path: &str, is_dir: bool) -> bool {
+        path.split('/').any(|component| component == ".git")
+            || self
+                .ignore
+                .as_ref()
+                .is_some_and(|ignore| ignore.is_ignored(path, is_dir))
+    }
+
+    fn should_watch(&self, dir: &str) -> bool {
+        dir.is_empty()
+            || (!self.is_excluded(dir, true)
+                && self.globs.iter().any(|glob| glob.may_match_under(dir)))
+    }
+
+    fn add_watch(&mut self, dir: &str) -> io::Result<()> {
+        let path = CString::new(self.root.join(dir).into_os_string().into_vec())?;
+        // SAFETY: `path` is a valid C string for the duration of the call
+        let wd = unsafe {
+            libc::inotify_add_watch(self.inotify.as_raw_fd(), path.as_ptr(), WATCH_MASK)
+        };
+        if wd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        self.dirs.insert(wd, dir.to_string());
+        Ok(())
+    }
+
+    /// Watch `dir` and the directories below it, collecting the entries found.
+    /// Each directory is watched before it is listed so nothing created in
+    /// between is missed.
+    fn watch_tree(&mut self, dir: &str, found: &mut Vec<(String, bool)>) {
+        let mut stack = vec![dir.to_string()];
+        while let Some(dir) = stack.pop() {
+            if !self.should_watch(&dir) {
+                continue;
+            }
+            if let Some(ignore) = &mut self.ignore {
+                ignore.load_dir(&self.root, &dir);
+            }
+            if let Err(e) = self.add_watch(&dir) {
+                warn!("Cannot watch {}: {}", self.root.join(&dir).display(), e);
+                continue;
+            }
+            let Ok(entries) = std::fs::read_dir(self.root.join(&dir)) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                let path = join(&dir, &entry.file_name().to_string_lossy());
+                let is_dir = file_type.is_dir();
+                if self.is_excluded(&path, is_dir) {
+                    continue;
+                }
+                if is_dir {
+                    stack.push(path.clone());
+                }
+                found.push((path, is_dir));
+            }
+        }
+    }
+
+    /// Entries of a new directory may have been created before its watch
+    /// was added, so they are reported as created too.
+    fn watch_new_dir(&mut self, dir: &str, now: Instant) {
+        let mut found = Vec::new();
+        self.watch_tree(dir, &mut found);
+        for (path, is_dir) in found {
+            self.pending.record(WatchEvent::Create { path, is_dir }, now);
+        }
+    }
+
+    fn unwatch_tree(&mut self, dir: &str) {
+        let wds: Vec<i32> = self
+            .dirs
+            .iter()
+            .filter(|(_, path)| strip_dir(path, dir).is_some())
+            .map(|(wd, _)| *wd)
+            .collect();
+        for wd in wds {
+            self.dirs.remove(&wd);
+            // SAFETY: plain syscall on our inotify fd
+            unsafe { libc::inotify_rm_watch(self.inotify.as_raw_fd(), wd) };
+        }
+    }
+
+    /// Inotify keeps watching a renamed directory; only its path changes.
+    fn rename_tree(&mut self, from: &str, to: &str) {
+        let mut watched = false;
+        for path in self.dirs.values_mut() {
+            if let Some(renamed) = strip_dir(path, from).map(|rest| join(to, rest)) {
+                *path = renamed;
+                watched = true;
+            }
+        }
+        if let Some(ignore) = &mut self.ignore {
+            ignore.rename_dir(from, to);
+        }
+        if !watched {
+            self.watch_tree(to, &mut Vec::new());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashSet;
+    use tempfile::TempDir;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+    use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn create(path: &str) -> WatchEvent {
+        WatchEvent::Create {
+            path: path.to_string(),
+            is_dir: false,
+        }
+    }
+
+    fn modify(path: &str) -> WatchEvent {
+        WatchEvent::Modify {
+            path: path.to_string(),
+            is_dir: false,
+        }
+    }
+
+    fn delete(path: &str) -> WatchEvent {
+        WatchEvent::Delete {
+            path: path.to_string(),
+            is_dir: false,
+        }
+    }
+
+    fn rename(from: &str, path: &str) -> WatchEvent {
+        WatchEvent::Rename {
+            from: from.to_string(),
+            path: path.to_string(),
+            is_dir: false,
+        }
+    }
+
+    #[test]
+    fn test_pending_merges_events() {
+        let now = Instant::now();
+        let mut pending = Pending::default();
+        for event in [
+            create("a"),
+            modify("a"),
+            create("gone"),
+            modify("b"),
+            delete("gone"),
+            delete("c"),
+            create("c"),
+            create("tmp"),
+            rename("tmp", "d"),
+            rename("e", "f"),
+            WatchEvent::Overflow,
+            WatchEvent::Overflow,
+        ] {
+            pending.record(event, now);
+        }
+        assert_eq!(
+            pending.deadline(Duration::from_millis(50)),
+            Some(now + Duration::from_millis(50))
+        );
+        assert_eq!(
+            pending.drain(),
+            vec![
+                create("a"),
+                modify("b"),
+                modify("c"),
+                create("d"),
+                rename("e", "f"),
+                WatchEvent::Overflow,
+            ]
+        );
+        assert_eq!(pending.deadline(Duration::from_millis(50)), None);
+    }
+
+    fn collect(rx: &mut mpsc::UnboundedReceiver<WatchBatch>, wait: Duration) -> Vec<WatchEvent> {
+        let deadline = Instant::now() + wait;
+        let mut events = Vec::new();
+        while Instant::now() < deadline {
+            match rx.try_recv() {
+                Ok(batch) => events.extend(batch.events),
+                Err(_) => std::thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        events
+    }
+
+    fn subscribe(
+        dir: &TempDir,
+        globs: &[&str],
+    ) -> (WatchChannelState, ChannelId, mpsc::UnboundedReceiver<WatchBatch>) {
+        let root = dir.path().to_string_lossy().into_owned();
+        let workspace = Workspace::from_specs(std::slice::from_ref(&root)).unwrap();
+        let state = WatchChannelState::new(workspace);
+        let (tx, rx) = mpsc::unbounded_channel();
+        let channel = Uuid::new_v4();
+        let params = WatchParams {
+            path: root,
+            globs: globs.iter().map(|glob| glob.to_string()).collect(),
+            gitignore: true,
+            debounce_ms: Some(20),
+        };
+        state.subscribe(channel, params, tx).unwrap();
+        (state, channel, rx)
+    }
+
+    #[test]
+    fn test_watch_events() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "*.log\nbuild/\n").unwrap();
+        std::fs::write(dir.path().join("old.txt"), "x").unwrap();
+        let (_state, _channel, mut rx) = subscribe(&dir, &["**/*.txt", "**/*.log", "build/**"]);
+
+        std::fs::write(dir.path().join("a.txt"), "hello").unwrap();
+        std::fs::write(dir.path().join("debug.log"), "ignored").unwrap();
+        std::fs::write(dir.path().join("a.rs"), "not matched").unwrap();
+        std::fs::create_dir_all(dir.path().join("build")).unwrap();
+        std::fs::write(dir.path().join("build/out.txt"), "ignored").unwrap();
+        std::fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        std::fs::write(dir.path().join("src/nested/b.txt"), "new dir").unwrap();
+        std::fs::remove_file(dir.path().join("old.txt")).unwrap();
+        let events = collect(&mut rx, Duration::from_millis(300));
+        let events: HashSet<WatchEvent> = events.into_iter().collect();
+        assert_eq!(
+            events,
+            HashSet::from([create("a.txt"), create("src/nested/b.txt"), delete("old.txt")])
+        );
+
+        std::fs::rename(dir.path().join("a.txt"), dir.path().join("c.txt")).unwrap();
+        std::fs::rename(dir.path().join("src"), dir.path().join("lib")).unwrap();
+        std::fs::write(dir.path().join("lib/nested/b.txt"), "moved").unwrap();
+        let events = collect(&mut rx, Duration::from_millis(300));
+        assert!(events.contains(&rename("a.txt", "c.txt")));
+        assert!(events.contains(&modify("lib/nested/b.txt")));
+    }
+
+    #[test]
+    fn test_close_channel_stops_watching() {
+        let dir = TempDir::new().unwrap();
+        let (state, channel, mut rx) = subscribe(&dir, &["**"]);
+        state.close_channel(channel);
+        // The watcher thread exits and drops its sender
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !matches!(rx.try_recv(), Err(mpsc::error::TryRecvError::Disconnected)) {
+            assert!(Instant::now() < deadline, "watcher did not stop");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!state.unsubscribe(channel, "unknown"));
+    }
+
+    /// Serve a session over TCP, one message per line, as the server does
+    /// with one per WebSocket frame.
+    async fn serve(stream: TcpStream, mut session: WatchSession) {
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        loop {
+            let reply = tokio::select! {
+                Some(batch) = session.next_batch() => WatchReply::Events(batch),
+                line = lines.next_line() => match line {
+                    Ok(Some(line)) => session.handle(&line).await,
+                    _ => return,
+                },
+            };
+            let json = format!("{}\n", serde_json::to_string(&reply).unwrap());
+            if write.write_all(json.as_bytes()).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    async fn send(write: &mut OwnedWriteHalf, message: serde_json::Value) {
+        write.write_all(format!("{}\n", message).as_bytes()).await.unwrap();
+    }
+
+    async fn receive(lines: &mut Lines<BufReader<OwnedReadHalf>>) -> serde_json::Value {
+        let line = tokio::time::timeout(Duration::from_secs(5), lines.next_line()).await;
+        serde_json::from_str(&line.unwrap().unwrap().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_over_a_socket() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().to_string_lossy().into_owned();
+        let workspace = Workspace::from_specs(std::slice::from_ref(&root)).unwrap();
+        let state = WatchChannelState::new(workspace);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn({
+            let state = state.clone();
+            async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                serve(stream, WatchSession::new(state)).await;
+            }
+        });
+        let (read, mut write) = TcpStream::connect(address).await.unwrap().into_split();
+        let mut lines = BufReader::new(read).lines();
+
+        let subscribe = json!({
+            "type": "subscribe",
+            "path": root,
+            "globs": ["*.txt"],
+            "debounce_ms": 20,
+        });
+        send(&mut write, subscribe.clone()).await;
+        let reply = receive(&mut lines).await;
+        assert_eq!(reply["type"], "subscribed");
+        let subscription = reply["subscription"].clone();
+
+        std::fs::write(dir.path().join("a.txt"), "hello").unwrap();
+        std::fs::write(dir.path().join("a.rs"), "not matched").unwrap();
+        assert_eq!(
+            receive(&mut lines).await,
+            json!({
+                "type": "events",
+                "subscription": subscription,
+                "events": [{"kind": "create", "path": "a.txt", "is_dir": false}],
+            })
+        );
+
+        send(&mut write, json!({"type": "unsubscribe", "subscription": "unknown"})).await;
+        assert_eq!(receive(&mut lines).await["code"], UNKNOWN_WATCH);
+        send(&mut write, json!({"type": "watch"})).await;
+        assert_eq!(receive(&mut lines).await["code"], INVALID_WATCH);
+
+        // Every subscription is a thread and an inotify instance
+        for _ in 1..MAX_SUBSCRIPTIONS_PER_CHANNEL {
+            send(&mut write, subscribe.clone()).await;
+            assert_eq!(receive(&mut lines).await["type"], "subscribed");
+        }
+        send(&mut write, subscribe.clone()).await;
+        assert_eq!(receive(&mut lines).await["code"], TOO_MANY_WATCHES);
+        let unsubscribe = json!({"type": "unsubscribe", "subscription": subscription});
+        send(&mut write, unsubscribe).await;
+        assert_eq!(receive(&mut lines).await["type"], "unsubscribed");
+        send(&mut write, subscribe).await;
+        assert_eq!(receive(&mut lines).await["type"], "subscribed");
+
+        // Closing the connection drops the channel and its watches
+        drop(write);
+        server.await.unwrap();
+        assert!(state.channels.is_empty());
+    }
+}
@@ -0,0 +1,307 @@
+//! Workspace roots that confine the local file API.
+//!
+//! The server is started with one or more roots, each optionally read-only.
+//! Every path a client sends is canonicalized (symlinks and `..` resolved)
+//! before it is checked against the roots, so a link inside a root cannot be
+//! used to reach a file outside it.
+
+use serde::Serialize;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Suffix of a root spec that makes the root read-only, e.g. `~/notes:ro`.
+const READ_ONLY_SUFFIX: &str = ":ro";
+const READ_WRITE_SUFFIX: &str = ":rw";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceRoot {
+    /// Canonical path of the root
+    pub path: PathBuf,
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Workspace {
+    roots: Arc<Vec<WorkspaceRoot>>,
+}
+
+/// Why a path was refused; sent to the client as the `code` of a 403 response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AccessDenied {
+    pub success: bool,
+    pub code: &'static str,
+    pub error: String,
+}
+
+pub const OUTSIDE_WORKSPACE: &str = "outside_workspace";
+pub const READ_ONLY_ROOT: &str = "read_only_root";
+
+impl AccessDenied {
+    fn new(code: &'static str, error: String) -> Self {
+        Self {
+            success: false,
+            code,
+            error,
+        }
+    }
+}
+
+impl Workspace {
+    /// Parse root specs of the form `PATH`, `PATH:rw` or `PATH:ro`. Without
+    /// any spec the current directory is the only (writable) root.
+    pub fn from_specs(specs: &[String]) -> io::Result<Self> {
+        let mut roots = Vec::new();
+        for spec in specs {
+            let (path, read_only) = if let Some(path) = spec.strip_suffix(READ_ONLY_SUFFIX) {
+                (path, true)
+            } else if let Some(path) = spec.strip_suffix(READ_WRITE_SUFFIX) {
+                (path, false)
+            } else {
+                (spec.as_str(), false)
+            };
+            let path = std::fs::canonicalize(path).map_err(|e| {
+                io::Error::new(e.kind(), format!("workspace root {}: {}", path, e))
+            })?;
+            if !path.is_dir() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("workspace root {} is not a directory", path.display()),
+                ));
+            }
+            roots.push(WorkspaceRoot { path, read_only });
+        }
+
+        if roots.is_empty() {
+            roots.push(WorkspaceRoot {
+                path: std::env::current_dir()?.canonicalize()?,
+                read_only: false,
+            });
+        }
+
+        // Most specific root first, so a read-only root nested in a writable
+        // one takes precedence
+        roots.sort_by_key(|root| std::cmp::Reverse(root.path.components().count()));
+        Ok(Self {
+            roots: Arc::new(roots),
+        })
+    }
+
+    pub fn roots(&self) -> &[WorkspaceRoot] {
+        &self.roots
+    }
+
+    /// Canonicalize `path` and check it against the roots. The path does not
+    /// have to exist; its nearest existing ancestor is resolved instead.
+    pub fn resolve(&self, path: &str, access: Access) -> Result<PathBuf, AccessDenied> {
+        let resolved = canonicalize_lenient(Path::new(path)).map_err(|e| {
+            AccessDenied::new(OUTSIDE_WORKSPACE, format!("cannot resolve {}: {}", path, e))
+        })?;
+        self.check(path, resolved, access)
+    }
+
+    /// Like `resolve`, but a symlink as the last component is the entry
+    /// itself rather than its target, e.g. for deleting the link.
+    pub fn resolve_entry(&self, path: &str, access: Access) -> Result<PathBuf, AccessDenied> {
+        let requested = Path::new(path);
+        let (Some(parent), Some(name)) = (requested.parent(), requested.file_name()) else {
+            return self.resolve(path, access);
+        };
+        let parent = canonicalize_lenient(parent).map_err(|e| {
+            AccessDenied::new(OUTSIDE_WORKSPACE, format!("cannot resolve {}: {}", path, e))
+        })?;
+        self.check(path, parent.join(name), access)
+    }
+
+    fn check(&self, requested: &str, resolved: PathBuf, access: Access) -> Result<PathBuf, AccessDenied> {
+        let Some(root) = self.roots.iter().find(|root| resolved.starts_with(&root.path)) else {
+            return Err(AccessDenied::new(
+                OUTSIDE_WORKSPACE,
+                format!("{} is outside the workspace roots", requested),
+            ));
+        };
+        if access == Access::Write && root.read_only {
+            return Err(AccessDenied::new(
+                READ_ONLY_ROOT,
+                format!(
+                    "{} is in the read-only workspace root {}",
+                    requested,
+                    root.path.display()
+                ),
+            ));
+        }
+        Ok(resolved)
+    }
+}
+
+/// Canonicalize the longest existing ancestor of `path`, then walk the rest
+/// one component at a time. A component that exists by then (e.g. `link` in
+/// `missing/../link/key`) is canonicalized again, so `..` cannot step over a
+/// symlink. A dangling symlink is an error: writing through it would create
+/// its target, wherever that is.
+fn canonicalize_lenient(path: &Path) -> io::Result<PathBuf> {
+    let mut existing = path;
+    let mut base = loop {
+        match existing.canonicalize() {
+            Ok(base) => break base,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => match existing.parent() {
+                Some(parent) => existing = parent,
+                None => return Err(e),
+            },
+            Err(e) => return Err(e),
+        }
+    };
+
+    let missing = path.strip_prefix(existing).unwrap_or(Path::new(""));
+    for component in missing.components() {
+        match component {
+            Component::ParentDir => {
+                base.pop();
+            }
+            Component::Normal(name) => {
+                base.push(name);
+                if base.symlink_metadata().is_ok() {
+                    base = base.canonicalize().map_err(|e| match e.kind() {
+                        io::ErrorKind::NotFound => io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("{} is a dangling symlink", base.display()),
+                        ),
+                        _ => e,
+                    })?;
+                }
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    Ok(base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn workspace(specs: &[&Path]) -> Workspace {
+        let specs: Vec<String> = specs.iter().map(|p| p.display().to_string()).collect();
+        Workspace::from_specs(&specs).unwrap()
+    }
+
+    #[test]
+    fn test_paths_inside_the_root_resolve() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "a").unwrap();
+        let ws = workspace(&[dir.path()]);
+        let root = dir.path().canonicalize().unwrap();
+
+        let path = format!("{}/a.txt", dir.path().display());
+        assert_eq!(ws.resolve(&path, Access::Read).unwrap(), root.join("a.txt"));
+
+        let missing = format!("{}/new/./deep/../file.txt", dir.path().display());
+        assert_eq!(
+            ws.resolve(&missing, Access::Write).unwrap(),
+            root.join("new").join("file.txt")
+        );
+    }
+
+    #[test]
+    fn test_dot_dot_cannot_leave_the_root() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        let ws = workspace(&[&root]);
+
+        let escape = format!("{}/../secret", root.display());
+        assert_eq!(ws.resolve(&escape, Access::Read).unwrap_err().code, OUTSIDE_WORKSPACE);
+
+        let missing_escape = format!("{}/new/../../secret", root.display());
+        let denied = ws.resolve(&missing_escape, Access::Write).unwrap_err();
+        assert_eq!(denied.code, OUTSIDE_WORKSPACE);
+        assert!(!denied.success);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_are_followed_before_checking() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("root");
+        let outside = dir.path().join("outside");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::create_dir(&outside).unwrap();
+        std::fs::write(outside.join("key"), "secret").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        let ws = workspace(&[&root]);
+
+        let through_link = format!("{}/link/key", root.display());
+        assert_eq!(ws.resolve(&through_link, Access::Read).unwrap_err().code, OUTSIDE_WORKSPACE);
+
+        // The link itself lives in the root and may be removed
+        let link = format!("{}/link", root.display());
+        assert_eq!(
+            ws.resolve_entry(&link, Access::Write).unwrap(),
+            root.canonicalize().unwrap().join("link")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dot_dot_in_a_missing_tail_still_follows_symlinks() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("root");
+        let outside = dir.path().join("outside");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::create_dir(&outside).unwrap();
+        std::fs::write(outside.join("key"), "secret").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        let ws = workspace(&[&root]);
+
+        let folded = format!("{}/nope/../link/key", root.display());
+        assert_eq!(ws.resolve(&folded, Access::Read).unwrap_err().code, OUTSIDE_WORKSPACE);
+        let folded_new = format!("{}/nope/../link/new.txt", root.display());
+        assert_eq!(ws.resolve(&folded_new, Access::Write).unwrap_err().code, OUTSIDE_WORKSPACE);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dangling_symlinks_are_refused() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("planted"), root.join("dangling")).unwrap();
+        let ws = workspace(&[&root]);
+
+        let leaf = format!("{}/dangling", root.display());
+        let denied = ws.resolve(&leaf, Access::Write).unwrap_err();
+        assert_eq!(denied.code, OUTSIDE_WORKSPACE);
+        assert!(denied.error.contains("dangling symlink"));
+        let folded = format!("{}/nope/../dangling", root.display());
+        assert_eq!(ws.resolve(&folded, Access::Write).unwrap_err().code, OUTSIDE_WORKSPACE);
+
+        // The link itself can still be removed
+        assert!(ws.resolve_entry(&leaf, Access::Write).is_ok());
+    }
+
+    #[test]
+    fn test_read_only_roots_refuse_writes() {
+        let dir = TempDir::new().unwrap();
+        let docs = dir.path().join("docs");
+        std::fs::create_dir(&docs).unwrap();
+        let specs = vec![
+            dir.path().display().to_string(),
+            format!("{}{}", docs.display(), READ_ONLY_SUFFIX),
+        ];
+        let ws = Workspace::from_specs(&specs).unwrap();
+
+        let inside = format!("{}/guide.md", docs.display());
+        assert!(ws.resolve(&inside, Access::Read).is_ok());
+        assert_eq!(ws.resolve(&inside, Access::Write).unwrap_err().code, READ_ONLY_ROOT);
+
+        let writable = format!("{}/notes.md", dir.path().display());
+        assert!(ws.resolve(&writable, Access::Write).is_ok());
+    }
+}
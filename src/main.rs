@@ -1,12 +1,14 @@
 use anyhow::{bail, Context, Result};
 use clap::{Parser, Subcommand};
 use console::{style, Emoji};
-use dialoguer::{theme::ColorfulTheme, Select};
+use dialoguer::{theme::ColorfulTheme, MultiSelect, Select};
 use dirs::home_dir;
 use indicatif::{ProgressBar, ProgressStyle};
 use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
 use std::env;
 use std::fs::{self, File};
 use std::io::{Cursor, Read, Seek, Write};
@@ -18,6 +20,25 @@ use zip::ZipArchive;
 #[cfg(unix)]
 use std::os::unix::fs::symlink;
 
+mod agents;
+mod converter;
+mod hygiene;
+mod project;
+mod tools;
+
+#[cfg(feature = "server-core")]
+#[path = "core/mod.rs"]
+mod server_core;
+// The core modules import each other as `crate::<module>`
+#[cfg(feature = "server-core")]
+use server_core::{file_io, path_match, workspace};
+
+use agents::{AgentDefinition, AgentRegistry};
+use converter::Format;
+use hygiene::HookOutcome;
+use project::{ensure_context_file, generate_agent_commands, merge_template_dir, ProjectManifest};
+use tools::Version;
+
 static CHECKMARK: Emoji<'_, '_> = Emoji("✓  ", "✓ ");
 static CROSS: Emoji<'_, '_> = Emoji("✗  ", "✗ ");
 static WARN: Emoji<'_, '_> = Emoji("⚠  ", "!");
@@ -38,55 +59,6 @@ const TAGLINE: &str = "Bl1nk Team Kit - Spec-Driven Development Toolkit";
 
 const ONBOARDING_TEMPLATE: &str = include_str!("templates/onboarding-template.md");
 
-/* ================= AGENT CONFIG (for init) ================= */
-
-#[derive(Debug, Clone)]
-struct AgentConfig {
-    name: &'static str,
-    folder: &'static str,
-    install_url: Option<&'static str>,
-    requires_cli: bool,
-}
-
-macro_rules! agent_map {
-    { $($key:expr => $name:expr, $folder:expr, $install_url:expr, $requires_cli:expr),* $(,)? } => {
-        {
-            let mut m = HashMap::new();
-            $(
-                m.insert($key, AgentConfig {
-                    name: $name,
-                    folder: $folder,
-                    install_url: $install_url,
-                    requires_cli: $requires_cli,
-                });
-            )*
-            m
-        }
-    };
-}
-
-lazy_static::lazy_static! {
-    static ref AGENT_CONFIG: HashMap<&'static str, AgentConfig> = agent_map! {
-        "copilot" => "GitHub Copilot", ".github/", None, false,
-        "claude" => "Claude Code", ".claude/", Some("https://docs.anthropic.com/en/docs/claude-code/setup"), true,
-        "gemini" => "Gemini CLI", ".gemini/", Some("https://github.com/google-gemini/gemini-cli"), true,
-        "cursor-agent" => "Cursor", ".cursor/", None, false,
-        "qwen" => "Qwen Code", ".qwen/", Some("https://github.com/QwenLM/qwen-code"), true,
-        "opencode" => "opencode", ".opencode/", Some("https://opencode.ai"), true,
-        "codex" => "Codex CLI", ".codex/", Some("https://github.com/openai/codex"), true,
-        "windsurf" => "Windsurf", ".windsurf/", None, false,
-        "kilocode" => "Kilo Code", ".kilocode/", None, false,
-        "auggie" => "Auggie CLI", ".augment/", Some("https://docs.augmentcode.com/cli/setup-auggie/install-auggie-cli"), true,
-        "codebuddy" => "CodeBuddy", ".codebuddy/", Some("https://www.codebuddy.ai/cli"), true,
-        "qoder" => "Qoder CLI", ".qoder/", Some("https://qoder.com/cli"), true,
-        "roo" => "Roo Code", ".roo/", None, false,
-        "q" => "Amazon Q Developer CLI", ".amazonq/", Some("https://aws.amazon.com/developer/learning/q-developer-cli/"), true,
-        "amp" => "Amp", ".agents/", Some("https://ampcode.com/manual#install"), true,
-        "shai" => "SHAI", ".shai/", Some("https://github.com/ovh/shai"), true,
-        "bob" => "IBM Bob", ".bob/", None, false,
-    };
-}
-
 const SCRIPT_TYPE_CHOICES: &[(&str, &str)] = &[
     ("sh", "POSIX Shell (bash/zsh)"),
     ("ps", "PowerShell"),
@@ -109,7 +81,7 @@ enum Commands {
         /// Name for your new project directory (optional if using --here)
         project_name: Option<String>,
 
-        /// AI assistant to use (claude, gemini, copilot, etc.)
+        /// AI assistants to use, comma-separated (claude, gemini, copilot, etc.)
         #[arg(long)]
         ai: Option<String>,
 
@@ -144,13 +116,59 @@ enum Commands {
         /// GitHub token to use for API requests (or set GH_TOKEN or GITHUB_TOKEN env var)
         #[arg(long)]
         github_token: Option<String>,
+
+        /// Install a git pre-commit hook that blocks committing secrets-like files
+        #[arg(long)]
+        pre_commit_hook: bool,
+
+        /// Never prompt; fail instead when a choice or confirmation would be required
+        #[arg(long = "non-interactive", short = 'y', visible_alias = "yes")]
+        non_interactive: bool,
+
+        /// Emit one JSON event per line on stdout instead of human-readable output (implies --non-interactive)
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Add another AI agent to an already-initialized project
+    AddAgent {
+        /// Agent(s) to add, comma-separated (claude, gemini, copilot, etc.)
+        agent: String,
+
+        /// Script type to use when the project does not record one: sh or ps
+        #[arg(long)]
+        script: Option<String>,
+
+        /// Skip checks for AI agent tools like Claude Code
+        #[arg(long)]
+        ignore_agent_tools: bool,
+
+        /// Skip SSL/TLS verification (not recommended)
+        #[arg(long)]
+        skip_tls: bool,
+
+        /// Show verbose diagnostic output for network and extraction failures
+        #[arg(long)]
+        debug: bool,
+
+        /// GitHub token to use for API requests (or set GH_TOKEN or GITHUB_TOKEN env var)
+        #[arg(long)]
+        github_token: Option<String>,
+
+        /// Emit one JSON event per line on stdout instead of human-readable output
+        #[arg(long)]
+        json: bool,
     },
 
     /// Generate an analysis template for an existing project
     Onboard,
 
     /// Check that all required tools are installed
-    Check,
+    Check {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
     /// Display version and system information
     Version,
@@ -183,11 +201,52 @@ enum Commands {
 
     /// List all agents that have a skills directory
     Agents,
+
+    /// Convert a skill between Claude and Gemini formats
+    Convert {
+        /// Skill or extension directory to convert (with --recursive, a directory of them)
+        source: PathBuf,
+
+        /// Source format: claude, claude-plugin or gemini (detected from the directory if omitted)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Target format: claude, claude-plugin, gemini, codex, cursor, copilot or opencode
+        /// (gemini for Claude sources, claude otherwise)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Output directory (defaults to the source directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Print the conversion result as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Convert every skill below the source directory and write an index for the target
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Skills converted at once with --recursive (defaults to the number of CPUs)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
 }
 
 /* ================= MAIN ================= */
 
-fn main() -> Result<()> {
+fn main() {
+    if let Err(err) = run() {
+        let code = err
+            .downcast_ref::<CliFailure>()
+            .map_or(1, |failure| failure.kind.exit_code());
+        eprintln!("Error: {:?}", err);
+        std::process::exit(code);
+    }
+}
+
+fn run() -> Result<()> {
     let cli = Cli::parse();
     match cli.command {
         Commands::Init {
@@ -201,7 +260,10 @@ fn main() -> Result<()> {
             skip_tls,
             debug,
             github_token,
-        } => cmd_init(
+            pre_commit_hook,
+            non_interactive,
+            json,
+        } => cmd_init(InitOptions {
             project_name,
             ai,
             script,
@@ -212,9 +274,21 @@ fn main() -> Result<()> {
             skip_tls,
             debug,
             github_token,
-        ),
+            pre_commit_hook,
+            non_interactive: non_interactive || json,
+            json,
+        }),
+        Commands::AddAgent {
+            agent,
+            script,
+            ignore_agent_tools,
+            skip_tls,
+            debug,
+            github_token,
+            json,
+        } => cmd_add_agent(agent, script, ignore_agent_tools, skip_tls, debug, github_token, json),
         Commands::Onboard => cmd_onboard(),
-        Commands::Check => cmd_check(),
+        Commands::Check { json } => cmd_check(json),
         Commands::Version => cmd_version(),
         Commands::Install {
             agent,
@@ -226,6 +300,24 @@ fn main() -> Result<()> {
         Commands::Uninstall { agent, skill } => cmd_uninstall(agent, skill),
         Commands::List => cmd_list(),
         Commands::Agents => cmd_agents(),
+        Commands::Convert {
+            source,
+            from,
+            to,
+            output,
+            json,
+            recursive: false,
+            ..
+        } => cmd_convert(source, from, to, output, json),
+        Commands::Convert {
+            source,
+            from,
+            to,
+            output,
+            json,
+            recursive: true,
+            jobs,
+        } => cmd_convert_recursive(source, from, to, output, json, jobs),
     }
 }
 
@@ -255,7 +347,7 @@ fn show_banner() {
 
 /* ================= INIT COMMAND ================= */
 
-fn cmd_init(
+struct InitOptions {
     project_name: Option<String>,
     ai: Option<String>,
     script: Option<String>,
@@ -266,33 +358,61 @@ fn cmd_init(
     skip_tls: bool,
     debug: bool,
     github_token: Option<String>,
-) -> Result<()> {
-    show_banner();
+    pre_commit_hook: bool,
+    non_interactive: bool,
+    json: bool,
+}
+
+fn cmd_init(opts: InitOptions) -> Result<()> {
+    let reporter = InitReporter { json: opts.json };
+    let result = init_project(opts, &reporter);
+    if let Err(err) = &result {
+        reporter.failed(err);
+    }
+    result
+}
+
+fn init_project(opts: InitOptions, reporter: &InitReporter) -> Result<()> {
+    if !reporter.json {
+        show_banner();
+    }
 
     // Handle project path
-    let (project_path, is_current_dir) = if here || project_name.as_deref() == Some(".") {
+    let (project_path, is_current_dir) = if opts.here || opts.project_name.as_deref() == Some(".") {
         (std::env::current_dir()?, true)
-    } else if let Some(name) = project_name {
+    } else if let Some(name) = &opts.project_name {
         (std::env::current_dir()?.join(name), false)
     } else {
-        anyhow::bail!("Must specify either a project name, use '.' for current directory, or use --here flag");
+        return Err(cli_failure(
+            FailureKind::Usage,
+            "Must specify either a project name, use '.' for current directory, or use --here flag",
+        ));
     };
 
     if !is_current_dir && project_path.exists() {
-        anyhow::bail!(
-            "Directory '{}' already exists. Please choose a different name or remove it.",
-            project_path.display()
-        );
+        return Err(cli_failure(
+            FailureKind::Conflict,
+            format!(
+                "Directory '{}' already exists. Please choose a different name or remove it.",
+                project_path.display()
+            ),
+        ));
     }
 
     if is_current_dir {
         let entries = fs::read_dir(&project_path)?.count();
         if entries > 0 {
-            println!(
-                "{} Current directory is not empty ({} items). Template files will be merged.",
-                WARN, entries
+            reporter.warn(
+                "target",
+                &format!("Current directory is not empty ({} items). Template files will be merged.", entries),
             );
-            if !force {
+            if !opts.force {
+                if opts.non_interactive {
+                    return Err(cli_failure(
+                        FailureKind::Conflict,
+                        "Current directory is not empty. Pass --force to merge template files non-interactively.",
+                    ));
+                }
                 if !dialoguer::Confirm::new()
                     .with_prompt("Do you want to continue?")
                     .interact()?
@@ -305,49 +425,67 @@ fn cmd_init(
     }
 
     // Print setup info
-    println!("{} Bl1nk Project Setup", style("▶").magenta());
-    println!("  Project:      {}", style(project_path.file_name().unwrap_or_default().to_string_lossy()).green());
-    println!("  Working path: {}", style(std::env::current_dir()?.display()).dim());
-    if !is_current_dir {
-        println!("  Target path:  {}", style(project_path.display()).dim());
+    if !reporter.json {
+        println!("{} Bl1nk Project Setup", style("▶").magenta());
+        println!("  Project:      {}", style(project_path.file_name().unwrap_or_default().to_string_lossy()).green());
+        println!("  Working path: {}", style(std::env::current_dir()?.display()).dim());
+        if !is_current_dir {
+            println!("  Target path:  {}", style(project_path.display()).dim());
+        }
+        println!();
     }
-    println!();
 
     // Check git if needed
-    let git_available = if !no_git { check_tool("git") } else { false };
-    if !no_git && !git_available {
-        println!("{} Git not found - will skip repository initialization", WARN);
+    let git_available = if !opts.no_git { check_tool("git") } else { false };
+    if !opts.no_git && !git_available {
+        reporter.warn("git", "Git not found - will skip repository initialization");
     }
 
     // AI assistant selection
-    let selected_ai = match ai {
-        Some(a) if AGENT_CONFIG.contains_key(a.as_str()) => a,
-        Some(a) => anyhow::bail!("Invalid AI assistant '{}'. Choose from: {}", a, AGENT_CONFIG.keys().cloned().collect::<Vec<_>>().join(", ")),
+    let registry = AgentRegistry::load()?;
+    let selected_agents = match opts.ai.as_deref() {
+        Some(list) => parse_agent_list(&registry, list)?,
+        None if opts.non_interactive => {
+            return Err(cli_failure(
+                FailureKind::Usage,
+                "--ai is required in non-interactive mode",
+            ))
+        }
         None => {
-            let agents: Vec<&str> = AGENT_CONFIG.keys().copied().collect();
-            let selection = Select::with_theme(&ColorfulTheme::default())
-                .with_prompt("Choose your AI assistant")
-                .default(0)
+            let agents = registry.keys();
+            let selection = MultiSelect::with_theme(&ColorfulTheme::default())
+                .with_prompt("Choose your AI assistants (space to select)")
                 .items(&agents)
                 .interact()?;
-            agents[selection].to_string()
+            if selection.is_empty() {
+                return Err(cli_failure(FailureKind::Usage, "Select at least one AI assistant"));
+            }
+            selection.into_iter().map(|i| agents[i].to_string()).collect()
         }
     };
-    let agent_cfg = AGENT_CONFIG.get(selected_ai.as_str()).unwrap();
-
-    // Check agent CLI if required
-    if !ignore_agent_tools && agent_cfg.requires_cli && !check_tool(&selected_ai) {
-        let install_url = agent_cfg.install_url.unwrap_or("(no URL)");
-        anyhow::bail!(
-            "{} not found. Install from: {}\n{} is required for this project type.\nTip: Use --ignore-agent-tools to skip this check.",
-            agent_cfg.name, install_url, agent_cfg.name
-        );
+
+    // Check agent CLIs if required
+    if !opts.ignore_agent_tools {
+        for agent in &selected_agents {
+            ensure_agent_tool(&registry, agent)?;
+        }
     }
 
     // Script type selection
-    let selected_script = match script {
+    let selected_script = match opts.script {
         Some(s) if SCRIPT_TYPE_CHOICES.iter().any(|(k, _)| *k == s) => s,
-        Some(s) => anyhow::bail!("Invalid script type '{}'. Choose from: sh, ps", s),
+        Some(s) => {
+            return Err(cli_failure(
+                FailureKind::Usage,
+                format!("Invalid script type '{}'. Choose from: sh, ps", s),
+            ))
+        }
+        None if opts.non_interactive => {
+            return Err(cli_failure(
+                FailureKind::Usage,
+                "--script is required in non-interactive mode",
+            ))
+        }
         None => {
             let default = if cfg!(windows) { "ps" } else { "sh" };
             let items: Vec<&str> = SCRIPT_TYPE_CHOICES.iter().map(|(k, _)| *k).collect();
@@ -360,24 +498,76 @@ fn cmd_init(
         }
     };
 
-    println!("{} AI assistant: {}", CHECKMARK, style(&selected_ai).magenta());
-    println!("{} Script type:  {}", CHECKMARK, style(&selected_script).magenta());
-    println!();
+    reporter.done(
+        "select",
+        &format!("AI assistant: {}", style(selected_agents.join(", ")).magenta()),
+        json!({ "ai": selected_agents }),
+    );
+    reporter.done("select", &format!("Script type:  {}", style(&selected_script).magenta()), json!({ "script": selected_script }));
+    if !reporter.json {
+        println!();
+    }
 
     // Download and extract template
-    let github_token = github_token.or_else(|| env::var("GH_TOKEN").ok()).or_else(|| env::var("GITHUB_TOKEN").ok());
+    let github_token = opts
+        .github_token
+        .or_else(|| env::var("GH_TOKEN").ok())
+        .or_else(|| env::var("GITHUB_TOKEN").ok());
     let client = reqwest::blocking::ClientBuilder::new()
-        .danger_accept_invalid_certs(skip_tls)
+        .danger_accept_invalid_certs(opts.skip_tls)
         .build()?;
 
-    // Show progress steps
-    println!("{} Fetch latest release...", INFO);
-    let (zip_path, release_tag, asset_name) = download_template(&client, &selected_ai, &selected_script, &github_token, debug)?;
-    println!("{} Downloaded: {}", CHECKMARK, asset_name);
+    // The first agent's template lays down the project; the others are layered on top
+    let (first_agent, other_agents) = selected_agents.split_first().unwrap();
+
+    reporter.start("download", "Fetch latest release...");
+    let template = download_template(&client, first_agent, &selected_script, &github_token, opts.debug, reporter)?;
+    reporter.done(
+        "download",
+        &format!("Downloaded: {}", template.asset_name),
+        json!({
+            "agent": first_agent,
+            "asset": template.asset_name,
+            "template_version": template.release_tag,
+            "checksum": template.checksum,
+        }),
+    );
+
+    reporter.start("extract", "Extracting template...");
+    let mut files_written = extract_template(&template.zip_path, &project_path, is_current_dir)
+        .map_err(|e| cli_failure(FailureKind::Template, format!("Failed to extract template: {:#}", e)))?;
+    reporter.done("extract", "Extracted", json!({ "agent": first_agent, "files": files_written }));
+
+    let mut installed = vec![first_agent.clone()];
+    for agent in other_agents {
+        let written = add_agent_to_project(
+            &project_path,
+            &registry,
+            &installed,
+            agent,
+            &TemplateSource {
+                client: &client,
+                script: &selected_script,
+                github_token: &github_token,
+                debug: opts.debug,
+            },
+            reporter,
+        )?;
+        files_written.extend(written);
+        installed.push(agent.clone());
+    }
+    files_written.sort();
+    files_written.dedup();
 
-    println!("{} Extracting template...", INFO);
-    extract_template(&zip_path, &project_path, is_current_dir)?;
-    println!("{} Extracted", CHECKMARK);
+    ProjectManifest {
+        agents: installed,
+        script: selected_script.clone(),
+        template_version: Some(template.release_tag.clone()),
+    }
+    .save(&project_path)?;
+
+    // Keep credentials out of the initial commit
+    apply_secrets_hygiene(&project_path, &registry, &selected_agents, &files_written, reporter)?;
 
     // Set executable permissions on .sh scripts (Unix only)
     if !cfg!(windows) {
@@ -385,31 +575,40 @@ fn cmd_init(
     }
 
     // Git init
-    if !no_git && git_available && !is_git_repo(&project_path)? {
-        println!("{} Initializing git repository...", INFO);
+    if !opts.no_git && git_available && !is_git_repo(&project_path)? {
+        reporter.start("git", "Initializing git repository...");
         init_git_repo(&project_path)?;
-        println!("{} Git repository initialized", CHECKMARK);
-    } else if !no_git && git_available {
-        println!("{} Git repository already exists", INFO);
-    } else if !no_git {
-        println!("{} Git not available, skipping", WARN);
+        reporter.done("git", "Git repository initialized", json!({}));
+    } else if !opts.no_git && git_available {
+        reporter.skip("git", "Git repository already exists");
+    } else if !opts.no_git {
+        reporter.warn("git", "Git not available, skipping");
     } else {
-        println!("{} Git init skipped (--no-git)", INFO);
+        reporter.skip("git", "Git init skipped (--no-git)");
+    }
+
+    if opts.pre_commit_hook {
+        install_secrets_hook(&project_path, reporter)?;
     }
 
     // Clean up zip
-    let _ = fs::remove_file(&zip_path);
+    let _ = fs::remove_file(&template.zip_path);
+
+    if reporter.json {
+        reporter.emit(json!({
+            "step": "complete",
+            "status": "ok",
+            "project_path": project_path.display().to_string(),
+            "ai": selected_agents,
+            "script": selected_script,
+            "template_version": template.release_tag,
+            "files_written": files_written,
+        }));
+        return Ok(());
+    }
 
     println!("\n{} Project ready.", style("✔").green());
 
-    // Security notice for agent folder
-    if let Some(cfg) = AGENT_CONFIG.get(selected_ai.as_str()) {
-        println!();
-        println!("{} Agent Folder Security", style("⚠").yellow());
-        println!("   Some agents may store credentials in {}.", style(cfg.folder).cyan());
-        println!("   Consider adding it to .gitignore to prevent leakage.");
-    }
-
     // Next steps – updated to use /bl: prefix
     println!();
     println!("{} Next Steps", style("▶").magenta());
@@ -436,6 +635,448 @@ fn cmd_init(
     Ok(())
 }
 
+/* ================= ADD-AGENT COMMAND ================= */
+
+fn cmd_add_agent(
+    agent: String,
+    script: Option<String>,
+    ignore_agent_tools: bool,
+    skip_tls: bool,
+    debug: bool,
+    github_token: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let reporter = InitReporter { json };
+    let result = add_agents(&agent, script, ignore_agent_tools, skip_tls, debug, github_token, &reporter);
+    if let Err(err) = &result {
+        reporter.failed(err);
+    }
+    result
+}
+
+fn add_agents(
+    agent_list: &str,
+    script: Option<String>,
+    ignore_agent_tools: bool,
+    skip_tls: bool,
+    debug: bool,
+    github_token: Option<String>,
+    reporter: &InitReporter,
+) -> Result<()> {
+    let project_path = std::env::current_dir()?;
+    let registry = AgentRegistry::load()?;
+    let requested = parse_agent_list(&registry, agent_list)?;
+
+    let mut manifest = match ProjectManifest::load(&project_path)? {
+        Some(manifest) => manifest,
+        None if project_path.join(".bl1nk").is_dir() => ProjectManifest {
+            // Projects initialized before the manifest existed: infer agents from their folders
+            agents: registry
+                .iter()
+                .filter(|(_, cfg)| project_path.join(&cfg.commands_dir).is_dir())
+                .map(|(key, _)| key.to_string())
+                .collect(),
+            ..Default::default()
+        },
+        None => {
+            return Err(cli_failure(
+                FailureKind::Usage,
+                "Not a Bl1nk project (no .bl1nk directory). Run `bl init` first.",
+            ))
+        }
+    };
+
+    let script = match script.or_else(|| Some(manifest.script.clone()).filter(|s| !s.is_empty())) {
+        Some(s) if SCRIPT_TYPE_CHOICES.iter().any(|(k, _)| *k == s) => s,
+        Some(s) => {
+            return Err(cli_failure(
+                FailureKind::Usage,
+                format!("Invalid script type '{}'. Choose from: sh, ps", s),
+            ))
+        }
+        None => {
+            return Err(cli_failure(
+                FailureKind::Usage,
+                "Project does not record a script type; pass --script sh or --script ps",
+            ))
+        }
+    };
+    manifest.script = script.clone();
+
+    let github_token = github_token
+        .or_else(|| env::var("GH_TOKEN").ok())
+        .or_else(|| env::var("GITHUB_TOKEN").ok());
+    let client = reqwest::blocking::ClientBuilder::new()
+        .danger_accept_invalid_certs(skip_tls)
+        .build()?;
+    let source = TemplateSource {
+        client: &client,
+        script: &script,
+        github_token: &github_token,
+        debug,
+    };
+
+    manifest.save(&project_path)?;
+    let mut files_written = Vec::new();
+    let mut added = Vec::new();
+    for agent in &requested {
+        if manifest.agents.contains(agent) {
+            reporter.skip("select", &format!("{} is already part of this project", agent));
+            continue;
+        }
+        if !ignore_agent_tools {
+            ensure_agent_tool(&registry, agent)?;
+        }
+        files_written.extend(add_agent_to_project(&project_path, &registry, &manifest.agents, agent, &source, reporter)?);
+        manifest.agents.push(agent.clone());
+        added.push(agent.clone());
+        // Record each agent as soon as its files are in, so a later failure does not orphan them
+        manifest.save(&project_path)?;
+    }
+
+    apply_secrets_hygiene(&project_path, &registry, &manifest.agents, &files_written, reporter)?;
+    if !cfg!(windows) {
+        set_executable_permissions(&project_path)?;
+    }
+
+    if reporter.json {
+        reporter.emit(json!({
+            "step": "complete",
+            "status": "ok",
+            "project_path": project_path.display().to_string(),
+            "added": added,
+            "agents": manifest.agents,
+            "files_written": files_written,
+        }));
+    } else if added.is_empty() {
+        println!("{} Nothing to add", INFO);
+    } else {
+        println!("\n{} Added {} ({} files)", style("✔").green(), added.join(", "), files_written.len());
+    }
+    Ok(())
+}
+
+/// Where to fetch agent templates from, shared by `init` and `add-agent`.
+struct TemplateSource<'a> {
+    client: &'a Client,
+    script: &'a str,
+    github_token: &'a Option<String>,
+    debug: bool,
+}
+
+fn parse_agent_list(registry: &AgentRegistry, list: &str) -> Result<Vec<String>> {
+    let mut agents: Vec<String> = Vec::new();
+    for agent in list.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+        if !registry.contains(agent) {
+            return Err(cli_failure(
+                FailureKind::Usage,
+                format!(
+                    "Invalid AI assistant '{}'. Choose from: {}",
+                    agent,
+                    registry.keys().join(", ")
+                ),
+            ));
+        }
+        if !agents.iter().any(|a| a == agent) {
+            agents.push(agent.to_string());
+        }
+    }
+    if agents.is_empty() {
+        return Err(cli_failure(FailureKind::Usage, "Specify at least one AI assistant"));
+    }
+    Ok(agents)
+}
+
+fn ensure_agent_tool(registry: &AgentRegistry, agent: &str) -> Result<()> {
+    let Some(agent_cfg) = registry.get(agent) else {
+        return Ok(());
+    };
+    let cli_missing = agent_cfg.cli_binary().is_some_and(|cli| !check_tool(cli));
+    if agent_cfg.requires_cli && cli_missing {
+        let install_url = agent_cfg.install_url.as_deref().unwrap_or("(no URL)");
+        return Err(cli_failure(
+            FailureKind::MissingTool,
+            format!(
+                "{} not found. Install from: {}\n{} is required for this project type.\nTip: Use --ignore-agent-tools to skip this check.",
+                agent_cfg.name, install_url, agent_cfg.name
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Layer `agent` onto a project that already has `installed` agents. Shared files
+/// the project already has are kept; command files and the context file the
+/// template does not provide are generated from the agents already present.
+fn add_agent_to_project(
+    project: &Path,
+    registry: &AgentRegistry,
+    installed: &[String],
+    agent: &str,
+    source: &TemplateSource,
+    reporter: &InitReporter,
+) -> Result<Vec<String>> {
+    let agent_cfg = registry
+        .get(agent)
+        .ok_or_else(|| cli_failure(FailureKind::Usage, format!("Unknown AI assistant '{}'", agent)))?;
+    let mut written = Vec::new();
+
+    reporter.start("download", &format!("Fetch template for {}...", agent));
+    match download_template(source.client, agent, source.script, source.github_token, source.debug, reporter) {
+        Ok(template) => {
+            reporter.done(
+                "download",
+                &format!("Downloaded: {}", template.asset_name),
+                json!({
+                    "agent": agent,
+                    "asset": template.asset_name,
+                    "template_version": template.release_tag,
+                    "checksum": template.checksum,
+                }),
+            );
+
+            reporter.start("extract", &format!("Merging {} template...", agent));
+            let staging = tempdir()?;
+            let staged = staging.path().join("template");
+            extract_template(&template.zip_path, &staged, false)
+                .map_err(|e| cli_failure(FailureKind::Template, format!("Failed to extract template: {:#}", e)))?;
+            let report = merge_template_dir(&staged, project)?;
+            for conflict in &report.conflicts {
+                reporter.warn("extract", &format!("Kept existing {} (differs in the {} template)", conflict, agent));
+            }
+            reporter.done(
+                "extract",
+                &format!(
+                    "Merged {} template ({} added, {} shared)",
+                    agent,
+                    report.added.len(),
+                    report.deduplicated.len()
+                ),
+                json!({
+                    "agent": agent,
+                    "files": report.added,
+                    "deduplicated": report.deduplicated,
+                    "conflicts": report.conflicts,
+                }),
+            );
+            written.extend(report.added);
+        }
+        Err(err) if is_failure(&err, FailureKind::Template) && !installed.is_empty() => {
+            reporter.warn(
+                "download",
+                &format!("No template published for {}; generating its files from {}", agent, installed.join(", ")),
+            );
+        }
+        Err(err) => return Err(err),
+    }
+
+    let existing: Vec<&AgentDefinition> = installed.iter().filter_map(|a| registry.get(a)).collect();
+    if !project.join(&agent_cfg.commands_dir).exists() {
+        if let Some(from) = existing.iter().find(|cfg| project.join(&cfg.commands_dir).is_dir()) {
+            let generated = generate_agent_commands(project, from, agent_cfg)?;
+            for warning in &generated.warnings {
+                reporter.warn("generate", warning);
+            }
+            reporter.done(
+                "generate",
+                &format!("Generated {} command files for {}", generated.written.len(), agent),
+                json!({ "agent": agent, "files": generated.written, "warnings": generated.warnings }),
+            );
+            written.extend(generated.written);
+        }
+    }
+    if let Some(context_file) = ensure_context_file(project, &existing, agent_cfg)? {
+        written.push(context_file);
+    }
+
+    Ok(written)
+}
+
+/* ================= SECRETS HYGIENE ================= */
+
+/// Keep the agents' credential paths out of git and flag anything secrets-like
+/// among the files just written or already in an agent folder.
+fn apply_secrets_hygiene(
+    project: &Path,
+    registry: &AgentRegistry,
+    agents: &[String],
+    files_written: &[String],
+    reporter: &InitReporter,
+) -> Result<()> {
+    let credential_paths: Vec<String> = agents
+        .iter()
+        .filter_map(|agent| registry.get(agent))
+        .flat_map(|cfg| cfg.credential_paths.iter().cloned())
+        .collect();
+    let changed = hygiene::update_gitignore(project, &credential_paths)?;
+    let message = if changed {
+        format!("Added {} agent credential paths to .gitignore", credential_paths.len())
+    } else {
+        ".gitignore already covers agent credential paths".to_string()
+    };
+    reporter.done("gitignore", &message, json!({ "paths": credential_paths, "changed": changed }));
+
+    let mut roots: Vec<PathBuf> = files_written.iter().map(PathBuf::from).collect();
+    roots.extend(
+        registry
+            .iter()
+            .map(|(_, cfg)| PathBuf::from(&cfg.folder))
+            .filter(|folder| project.join(folder).is_dir()),
+    );
+    let findings = hygiene::scan_secrets(project, &roots)?;
+    for finding in &findings {
+        reporter.warn("secrets", &format!("{} looks like a secret ({})", finding.path, finding.reason));
+    }
+    let message = match findings.len() {
+        0 => "No secrets-like files found".to_string(),
+        n => format!("{} secrets-like files found; keep them out of git", n),
+    };
+    reporter.done("secrets", &message, json!({ "findings": findings }));
+    Ok(())
+}
+
+fn install_secrets_hook(project: &Path, reporter: &InitReporter) -> Result<()> {
+    match hygiene::install_pre_commit_hook(project)? {
+        HookOutcome::Installed => reporter.done("hook", "Pre-commit secrets hook installed", json!({ "changed": true })),
+        HookOutcome::Updated => reporter.done("hook", "Pre-commit secrets hook updated", json!({ "changed": true })),
+        HookOutcome::ForeignHookPresent => {
+            reporter.warn("hook", "An existing pre-commit hook was left untouched; secrets hook not installed")
+        }
+        HookOutcome::NotAGitRepo => reporter.warn("hook", "Not a git repository; pre-commit hook skipped"),
+    }
+    Ok(())
+}
+
+/* ================= INIT REPORTING ================= */
+
+/// Failure classes for `bl init` and `bl check`, each mapped to its own process
+/// exit code so scripts can tell a flaky network apart from a real conflict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FailureKind {
+    Usage,
+    Network,
+    Checksum,
+    Conflict,
+    MissingTool,
+    Template,
+    Conversion,
+}
+
+impl FailureKind {
+    fn exit_code(self) -> i32 {
+        match self {
+            FailureKind::Usage => 2,
+            FailureKind::Network => 3,
+            FailureKind::Checksum => 4,
+            FailureKind::Conflict => 5,
+            FailureKind::MissingTool => 6,
+            FailureKind::Template => 7,
+            FailureKind::Conversion => 8,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            FailureKind::Usage => "usage",
+            FailureKind::Network => "network",
+            FailureKind::Checksum => "checksum",
+            FailureKind::Conflict => "conflict",
+            FailureKind::MissingTool => "missing_tool",
+            FailureKind::Template => "template",
+            FailureKind::Conversion => "conversion",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CliFailure {
+    kind: FailureKind,
+    message: String,
+}
+
+impl std::fmt::Display for CliFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CliFailure {}
+
+fn is_failure(err: &anyhow::Error, kind: FailureKind) -> bool {
+    err.downcast_ref::<CliFailure>().is_some_and(|failure| failure.kind == kind)
+}
+
+fn cli_failure(kind: FailureKind, message: impl Into<String>) -> anyhow::Error {
+    anyhow::Error::new(CliFailure {
+        kind,
+        message: message.into(),
+    })
+}
+
+/// Prints init progress either as styled lines or as one JSON event per line.
+struct InitReporter {
+    json: bool,
+}
+
+impl InitReporter {
+    fn emit(&self, event: serde_json::Value) {
+        println!("{}", event);
+    }
+
+    fn start(&self, step: &str, message: &str) {
+        if self.json {
+            self.emit(json!({ "step": step, "status": "started" }));
+        } else {
+            println!("{} {}", INFO, message);
+        }
+    }
+
+    fn done(&self, step: &str, message: &str, mut details: serde_json::Value) {
+        if self.json {
+            if let Some(obj) = details.as_object_mut() {
+                obj.insert("step".to_string(), json!(step));
+                obj.insert("status".to_string(), json!("ok"));
+            }
+            self.emit(details);
+        } else {
+            println!("{} {}", CHECKMARK, message);
+        }
+    }
+
+    fn skip(&self, step: &str, message: &str) {
+        if self.json {
+            self.emit(json!({ "step": step, "status": "skipped", "message": message }));
+        } else {
+            println!("{} {}", INFO, message);
+        }
+    }
+
+    fn warn(&self, step: &str, message: &str) {
+        if self.json {
+            self.emit(json!({ "step": step, "status": "warning", "message": message }));
+        } else {
+            println!("{} {}", WARN, message);
+        }
+    }
+
+    fn failed(&self, err: &anyhow::Error) {
+        if !self.json {
+            return;
+        }
+        let (class, exit_code) = match err.downcast_ref::<CliFailure>() {
+            Some(failure) => (failure.kind.as_str(), failure.kind.exit_code()),
+            None => ("error", 1),
+        };
+        self.emit(json!({
+            "step": "complete",
+            "status": "error",
+            "class": class,
+            "exit_code": exit_code,
+            "message": format!("{:#}", err),
+        }));
+    }
+}
+
 /* ================= ONBOARD COMMAND ================= */
 
 fn cmd_onboard() -> Result<()> {
@@ -443,14 +1084,13 @@ fn cmd_onboard() -> Result<()> {
     println!("{} Generating onboarding template...", INFO);
 
     let output_path = std::env::current_dir()?.join("000-onboarding-analysis.md");
-    if output_path.exists() {
-        if !dialoguer::Confirm::new()
+    if output_path.exists()
+        && !dialoguer::Confirm::new()
             .with_prompt("File already exists. Overwrite?")
             .interact()?
-        {
-            println!("{} Operation cancelled", INFO);
-            return Ok(());
-        }
+    {
+        println!("{} Operation cancelled", INFO);
+        return Ok(());
     }
 
     fs::write(&output_path, ONBOARDING_TEMPLATE)?;
@@ -463,32 +1103,249 @@ fn cmd_onboard() -> Result<()> {
 
 /* ================= CHECK COMMAND ================= */
 
-fn cmd_check() -> Result<()> {
+/// Minimum tool versions a project template declares in `.bl1nk/requirements.toml`:
+///
+/// ```toml
+/// [tools]
+/// git = "2.30"
+/// claude = "1.0.0"
+/// ```
+///
+/// Every tool listed there is required; without the file only git is.
+#[derive(Debug, Default, Deserialize)]
+struct TemplateRequirements {
+    #[serde(default)]
+    tools: BTreeMap<String, String>,
+}
+
+impl TemplateRequirements {
+    fn load(project: &Path) -> Result<Option<Self>> {
+        let path = project.join(".bl1nk").join("requirements.toml");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)?;
+        let requirements = toml::from_str(&content)
+            .with_context(|| format!("Invalid requirements file {}", path.display()))?;
+        Ok(Some(requirements))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ToolStatus {
+    Ok,
+    Missing,
+    Outdated,
+    UnknownVersion,
+    NoCli,
+}
+
+#[derive(Debug, Serialize)]
+struct ToolReport {
+    key: String,
+    name: String,
+    binary: Option<String>,
+    status: ToolStatus,
+    version: Option<String>,
+    min_version: Option<String>,
+    required: bool,
+    fix: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct GitConfigReport {
+    key: &'static str,
+    value: Option<String>,
+    fix: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct CheckReport {
+    ok: bool,
+    tools: Vec<ToolReport>,
+    git_config: Vec<GitConfigReport>,
+}
+
+fn check_one(
+    key: &str,
+    name: &str,
+    binary: Option<&str>,
+    version_args: &[String],
+    min_version: Option<&str>,
+    required: bool,
+    install_hint: Option<&str>,
+) -> ToolReport {
+    let mut report = ToolReport {
+        key: key.to_string(),
+        name: name.to_string(),
+        binary: binary.map(str::to_string),
+        status: ToolStatus::NoCli,
+        version: None,
+        min_version: min_version.map(str::to_string),
+        required,
+        fix: None,
+    };
+
+    let Some(binary) = binary else {
+        return report;
+    };
+
+    let Some(path) = tools::resolve_tool(binary) else {
+        report.status = ToolStatus::Missing;
+        report.fix = Some(match install_hint {
+            Some(hint) => format!("Install {} from {}", name, hint),
+            None => format!("Install {} and make sure `{}` is on your PATH", name, binary),
+        });
+        return report;
+    };
+
+    let version = tools::probe_version(&path, version_args);
+    report.version = version.as_ref().map(|v| v.to_string());
+
+    report.status = match (version, min_version.and_then(Version::parse)) {
+        (Some(found), Some(min)) if found < min => {
+            report.fix = Some(format!("Upgrade {} to {} or newer", name, min));
+            ToolStatus::Outdated
+        }
+        (None, Some(_)) => ToolStatus::UnknownVersion,
+        _ => ToolStatus::Ok,
+    };
+    report
+}
+
+fn cmd_check(json: bool) -> Result<()> {
+    let registry = AgentRegistry::load()?;
+    let requirements = TemplateRequirements::load(&std::env::current_dir()?)?.unwrap_or_default();
+    let required_min = |key: &str| requirements.tools.get(key).map(String::as_str);
+    let version_args = vec!["--version".to_string()];
+
+    let mut tools = vec![check_one(
+        "git",
+        "Git",
+        Some("git"),
+        &version_args,
+        required_min("git"),
+        true,
+        Some("https://git-scm.com/downloads"),
+    )];
+
+    for (key, cfg) in registry.iter() {
+        // Probe optional CLIs too when the template asks for them
+        let required = requirements.tools.contains_key(key);
+        let binary = cfg.cli_binary().filter(|_| cfg.requires_cli || required);
+        let min_version = required_min(key).or(cfg.min_version.as_deref());
+        tools.push(check_one(
+            key,
+            &cfg.name,
+            binary,
+            &cfg.version_args,
+            min_version,
+            required,
+            cfg.install_url.as_deref(),
+        ));
+    }
+
+    for (key, name) in [("code", "Visual Studio Code"), ("code-insiders", "VS Code Insiders")] {
+        tools.push(check_one(key, name, Some(key), &version_args, required_min(key), requirements.tools.contains_key(key), None));
+    }
+
+    let git_config = ["user.name", "user.email"]
+        .into_iter()
+        .map(|key| {
+            let value = tools::git_config(key);
+            let fix = value
+                .is_none()
+                .then(|| format!("git config --global {} \"<your {}>\"", key, &key[5..]));
+            GitConfigReport { key, value, fix }
+        })
+        .collect::<Vec<_>>();
+
+    let failing: Vec<&ToolReport> = tools
+        .iter()
+        .filter(|t| t.required && matches!(t.status, ToolStatus::Missing | ToolStatus::Outdated))
+        .collect();
+    let report_ok = failing.is_empty();
+    let failing_names = failing.iter().map(|t| t.name.clone()).collect::<Vec<_>>();
+
+    let report = CheckReport {
+        ok: report_ok,
+        tools,
+        git_config,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_check_report(&report);
+    }
+
+    if !report_ok {
+        return Err(cli_failure(
+            FailureKind::MissingTool,
+            format!("Required tools missing or outdated: {}", failing_names.join(", ")),
+        ));
+    }
+    Ok(())
+}
+
+fn print_check_report(report: &CheckReport) {
     show_banner();
     println!("{} Checking for installed tools...", style("▶").magenta());
     println!();
 
-    let git = check_tool("git");
-    println!("  {} Git: {}", if git { CHECKMARK } else { CROSS }, if git { "available" } else { "not found" });
-
-    for (key, cfg) in AGENT_CONFIG.iter() {
-        if cfg.requires_cli {
-            let found = check_tool(key);
-            println!("  {} {}: {}", if found { CHECKMARK } else { CROSS }, cfg.name, if found { "available" } else { "not found" });
-        } else {
-            println!("  {} {}: {} (IDE-based)", INFO, cfg.name, style("no CLI check").dim());
+    for tool in &report.tools {
+        let version = tool
+            .version
+            .as_deref()
+            .map(|v| format!(" ({})", v))
+            .unwrap_or_default();
+        match tool.status {
+            ToolStatus::Ok => println!("  {} {}: available{}", CHECKMARK, tool.name, version),
+            ToolStatus::UnknownVersion => println!(
+                "  {} {}: available{} (could not determine version)",
+                WARN, tool.name, version
+            ),
+            ToolStatus::Outdated => println!(
+                "  {} {}: {} is older than required {}",
+                if tool.required { CROSS } else { WARN },
+                tool.name,
+                tool.version.as_deref().unwrap_or("?"),
+                tool.min_version.as_deref().unwrap_or("?")
+            ),
+            ToolStatus::Missing => println!(
+                "  {} {}: not found{}",
+                if tool.required { CROSS } else { WARN },
+                tool.name,
+                if tool.required { " (required)" } else { "" }
+            ),
+            ToolStatus::NoCli => println!("  {} {}: {} (IDE-based)", INFO, tool.name, style("no CLI check").dim()),
+        }
+        if let Some(fix) = tool.fix.as_deref().filter(|_| tool.required) {
+            println!("      {} {}", style("fix:").dim(), fix);
         }
     }
 
-    let code = check_tool("code");
-    println!("  {} Visual Studio Code: {}", if code { CHECKMARK } else { CROSS }, if code { "available" } else { "not found" });
-
-    let code_insiders = check_tool("code-insiders");
-    println!("  {} VS Code Insiders: {}", if code_insiders { CHECKMARK } else { CROSS }, if code_insiders { "available" } else { "not found" });
+    println!();
+    println!("{} Git configuration", style("▶").magenta());
+    for entry in &report.git_config {
+        match &entry.value {
+            Some(value) => println!("  {} {}: {}", CHECKMARK, entry.key, value),
+            None => {
+                println!("  {} {}: not set", WARN, entry.key);
+                if let Some(fix) = &entry.fix {
+                    println!("      {} {}", style("fix:").dim(), fix);
+                }
+            }
+        }
+    }
 
     println!();
-    println!("{} Bl1nk CLI is ready to use!", style("✔").green());
-    Ok(())
+    if report.ok {
+        println!("{} Bl1nk CLI is ready to use!", style("✔").green());
+    } else {
+        println!("{} Some required tools need attention.", style("✗").red());
+    }
 }
 
 /* ================= VERSION COMMAND ================= */
@@ -547,7 +1404,7 @@ fn cmd_install(
     fs::rename(&skill_src, &global_dest)?;
 
     // Link into agent
-    let agent_root = agent_dir(&agent);
+    let agent_root = agent_dir(&AgentRegistry::load()?, &agent);
     fs::create_dir_all(&agent_root)?;
     let link_path = agent_root.join(&skill_name);
 
@@ -571,7 +1428,7 @@ fn cmd_install(
 /* ================= SKILL UNINSTALL COMMAND ================= */
 
 fn cmd_uninstall(agent: String, skill: String) -> Result<()> {
-    let path = agent_dir(&agent).join(skill);
+    let path = agent_dir(&AgentRegistry::load()?, &agent).join(skill);
     if path.exists() {
         fs::remove_file(path)?;
         println!("Removed");
@@ -597,33 +1454,277 @@ fn cmd_list() -> Result<()> {
 
 fn cmd_agents() -> Result<()> {
     let home = home_dir().unwrap();
-    for entry in fs::read_dir(home)? {
+    let registry = AgentRegistry::load()?;
+
+    // Known agents, wherever their definition puts the skills directory
+    let mut known_dirs = HashSet::new();
+    for (key, _) in registry.iter() {
+        let skills_dir = registry.skills_dir(&home, key);
+        if skills_dir.exists() {
+            println!("{}", key);
+        }
+        known_dirs.insert(skills_dir);
+    }
+
+    // Unknown agents that follow the ~/.<agent>/skills convention
+    for entry in fs::read_dir(&home)? {
         let entry = entry?;
         let name = entry.file_name().to_string_lossy().to_string();
-        if name.starts_with('.') && entry.path().join("skills").exists() {
+        let skills_dir = entry.path().join("skills");
+        if name.starts_with('.')
+            && !registry.contains(&name[1..])
+            && !known_dirs.contains(&skills_dir)
+            && skills_dir.exists()
+        {
             println!("{}", &name[1..]);
         }
     }
     Ok(())
 }
 
-/* ================= HELPER FUNCTIONS (Project Init) ================= */
+/* ================= CONVERT COMMAND ================= */
 
-fn check_tool(tool: &str) -> bool {
-    if tool == "claude" {
-        let claude_local = home_dir()
-            .map(|p| p.join(".claude").join("local").join("claude"))
-            .filter(|p| p.exists() && p.is_file());
-        if claude_local.is_some() {
-            return true;
+fn cmd_convert(
+    source: PathBuf,
+    from: Option<String>,
+    to: Option<String>,
+    output: Option<PathBuf>,
+    json: bool,
+) -> Result<()> {
+    if !source.is_dir() {
+        return Err(cli_failure(
+            FailureKind::Usage,
+            format!("Source directory not found: {}", source.display()),
+        ));
+    }
+
+    let from = match from {
+        Some(name) => {
+            let format = parse_format(&name)?;
+            if !format.readable() {
+                return Err(cli_failure(
+                    FailureKind::Usage,
+                    format!("{} can only be used with --to", format.as_str()),
+                ));
+            }
+            format
+        }
+        None => match converter::detect_formats(&source).as_slice() {
+            [format] => *format,
+            [] => {
+                return Err(cli_failure(
+                    FailureKind::Usage,
+                    format!(
+                        "Cannot detect the format of {}: expected SKILL.md, .claude-plugin/plugin.json or gemini-extension.json. Pass --from.",
+                        source.display()
+                    ),
+                ))
+            }
+            formats => {
+                return Err(cli_failure(
+                    FailureKind::Usage,
+                    format!(
+                        "{} matches several formats ({}). Pass --from.",
+                        source.display(),
+                        formats.iter().map(|f| f.as_str()).collect::<Vec<_>>().join(", ")
+                    ),
+                ))
+            }
+        },
+    };
+    let to = match to {
+        Some(name) => parse_format(&name)?,
+        None if from == Format::Gemini => Format::Claude,
+        None => Format::Gemini,
+    };
+    if from == to {
+        return Err(cli_failure(
+            FailureKind::Usage,
+            format!("--from and --to are both {}", from.as_str()),
+        ));
+    }
+    let output = output.unwrap_or_else(|| source.clone());
+
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    let converter::ConversionResult {
+        success,
+        files,
+        warnings,
+        errors,
+        unmapped,
+    } = runtime.block_on(converter::convert(from, to, &source, &output))?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({
+                "from": from.as_str(),
+                "to": to.as_str(),
+                "source": source.display().to_string(),
+                "output": output.display().to_string(),
+                "success": success,
+                "files": files,
+                "warnings": warnings,
+                "errors": errors,
+                "unmapped": unmapped,
+            }))?
+        );
+    } else {
+        println!(
+            "{} Converted {} → {}: {} files written to {}",
+            if success { CHECKMARK } else { CROSS },
+            from.as_str(),
+            to.as_str(),
+            files.len(),
+            style(output.display()).cyan()
+        );
+        for file in &files {
+            println!("   - {}", file);
+        }
+        if !unmapped.is_empty() {
+            println!("{} Not mapped to {}:", WARN, to.as_str());
+            for item in &unmapped {
+                println!("   - {}", item);
+            }
+        }
+        for warning in &warnings {
+            println!("{} {}", WARN, warning);
+        }
+        for error in &errors {
+            println!("{} {}", CROSS, style(error).red());
+        }
+    }
+
+    if !success {
+        return Err(cli_failure(
+            FailureKind::Conversion,
+            format!("Conversion failed with {} errors", errors.len()),
+        ));
+    }
+    Ok(())
+}
+
+fn cmd_convert_recursive(
+    source: PathBuf,
+    from: Option<String>,
+    to: Option<String>,
+    output: Option<PathBuf>,
+    json: bool,
+    jobs: Option<usize>,
+) -> Result<()> {
+    if !source.is_dir() {
+        return Err(cli_failure(
+            FailureKind::Usage,
+            format!("Source directory not found: {}", source.display()),
+        ));
+    }
+
+    let from = match from {
+        Some(name) => {
+            let format = parse_format(&name)?;
+            if !format.readable() {
+                return Err(cli_failure(
+                    FailureKind::Usage,
+                    format!("{} can only be used with --to", format.as_str()),
+                ));
+            }
+            Some(format)
+        }
+        None => None,
+    };
+    let to = match to {
+        Some(name) => parse_format(&name)?,
+        None if from == Some(Format::Gemini) => Format::Claude,
+        None => Format::Gemini,
+    };
+    let output = output.unwrap_or_else(|| source.clone());
+    let jobs = jobs
+        .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1);
+
+    let skills = converter::batch::find_skills(&source, from, &output)?;
+    if skills.is_empty() {
+        return Err(cli_failure(
+            FailureKind::Usage,
+            format!("No skills found below {}", source.display()),
+        ));
+    }
+    let report = converter::batch::convert_all(&skills, to, &source, &output, jobs)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        let width = report.skills.iter().map(|s| s.name.chars().count()).max().unwrap_or(0).max(5);
+        println!(
+            "   {:<width$}  {:<6}  {:>5}  {:>8}  {:>8}  {:>6}",
+            "SKILL", "STATUS", "FILES", "UNMAPPED", "WARNINGS", "ERRORS"
+        );
+        for skill in &report.skills {
+            let status = if skill.result.success { "ok" } else { "failed" };
+            println!(
+                "{}{:<width$}  {:<6}  {:>5}  {:>8}  {:>8}  {:>6}",
+                if skill.result.success { CHECKMARK } else { CROSS },
+                skill.name,
+                status,
+                skill.result.files.len(),
+                skill.result.unmapped.len(),
+                skill.result.warnings.len(),
+                skill.result.errors.len()
+            );
         }
+        for skill in report.skills.iter().filter(|s| !s.result.success) {
+            for error in &skill.result.errors {
+                println!("{} {}: {}", CROSS, skill.name, style(error).red());
+            }
+        }
+        for warning in &report.warnings {
+            println!("{} {}", WARN, warning);
+        }
+        println!(
+            "{} Converted {} of {} skills to {} in {}",
+            if report.failed == 0 { CHECKMARK } else { CROSS },
+            report.succeeded,
+            report.skills.len(),
+            to.as_str(),
+            style(output.display()).cyan()
+        );
+        if let Some(index) = &report.index {
+            println!("   Index: {}", index);
+        }
+        println!("   Report: {}", output.join(converter::batch::REPORT_FILE).display());
+    }
+
+    if report.failed > 0 {
+        return Err(cli_failure(
+            FailureKind::Conversion,
+            format!("{} of {} skills failed to convert", report.failed, report.skills.len()),
+        ));
     }
-    which::which(tool).is_ok()
+    Ok(())
+}
+
+fn parse_format(name: &str) -> Result<Format> {
+    Format::parse(name).ok_or_else(|| {
+        cli_failure(
+            FailureKind::Usage,
+            format!(
+                "Unknown format '{}'. Choose from: {}",
+                name,
+                Format::ALL.iter().map(|f| f.as_str()).collect::<Vec<_>>().join(", ")
+            ),
+        )
+    })
+}
+
+/* ================= HELPER FUNCTIONS (Project Init) ================= */
+
+fn check_tool(tool: &str) -> bool {
+    tools::resolve_tool(tool).is_some()
 }
 
 fn is_git_repo(path: &Path) -> Result<bool> {
     let output = Command::new("git")
-        .args(&["rev-parse", "--is-inside-work-tree"])
+        .args(["rev-parse", "--is-inside-work-tree"])
         .current_dir(path)
         .output();
     match output {
@@ -645,7 +1746,7 @@ fn init_git_repo(path: &Path) -> Result<()> {
         .status()
         .context("git add failed")?;
     Command::new("git")
-        .args(&["commit", "-m", "Initial commit from Bl1nk template"])
+        .args(["commit", "-m", "Initial commit from Bl1nk template"])
         .current_dir(path)
         .status()
         .context("git commit failed")?;
@@ -661,7 +1762,7 @@ fn set_executable_permissions(path: &Path) -> Result<()> {
     }
     for entry in walkdir::WalkDir::new(&scripts_dir) {
         let entry = entry?;
-        if entry.file_type().is_file() && entry.path().extension().map_or(false, |ext| ext == "sh") {
+        if entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "sh") {
             let mut perms = fs::metadata(entry.path())?.permissions();
             let mode = perms.mode();
             let new_mode = mode
@@ -697,13 +1798,23 @@ struct Asset {
     browser_download_url: String,
 }
 
+struct DownloadedTemplate {
+    zip_path: PathBuf,
+    release_tag: String,
+    asset_name: String,
+    checksum: Option<String>,
+    // Keeps the download directory alive until the template is extracted
+    _dir: TempDir,
+}
+
 fn download_template(
     client: &Client,
     ai: &str,
     script_type: &str,
     github_token: &Option<String>,
     debug: bool,
-) -> Result<(PathBuf, String, String)> {
+    reporter: &InitReporter,
+) -> Result<DownloadedTemplate> {
     // Changed to bl1nk-bot/skill-cli as requested
     let repo_owner = "bl1nk-bot";
     let repo_name = "skill-cli";
@@ -722,7 +1833,7 @@ fn download_template(
         .get(&api_url)
         .headers(headers.clone())
         .send()
-        .context("Failed to fetch latest release")?;
+        .map_err(|e| cli_failure(FailureKind::Network, format!("Failed to fetch latest release: {}", e)))?;
 
     if !response.status().is_success() {
         let status = response.status();
@@ -730,28 +1841,43 @@ fn download_template(
         if debug {
             eprintln!("GitHub API error {}: {}", status, text);
         }
-        anyhow::bail!("GitHub API returned status {}", status);
+        return Err(cli_failure(
+            FailureKind::Network,
+            format!("GitHub API returned status {}", status),
+        ));
     }
 
-    let release: Release = response.json()?;
+    let release: Release = response
+        .json()
+        .map_err(|e| cli_failure(FailureKind::Network, format!("Invalid release response: {}", e)))?;
     let pattern = format!("bl1nk-template-{}-{}", ai, script_type);
     let asset = release
         .assets
         .iter()
         .find(|a| a.name.contains(&pattern) && a.name.ends_with(".zip"))
-        .ok_or_else(|| anyhow!("No matching asset found for pattern '{}'", pattern))?;
-
-    println!("  Found: {} ({} bytes)", asset.name, asset.size);
+        .ok_or_else(|| {
+            cli_failure(
+                FailureKind::Template,
+                format!("No matching asset found for pattern '{}'", pattern),
+            )
+        })?;
+
+    if !reporter.json {
+        println!("  Found: {} ({} bytes)", asset.name, asset.size);
+    }
 
     // Download the zip
     let response = client
         .get(&asset.browser_download_url)
-        .headers(headers)
+        .headers(headers.clone())
         .send()
-        .context("Failed to download template")?;
+        .map_err(|e| cli_failure(FailureKind::Network, format!("Failed to download template: {}", e)))?;
 
     if !response.status().is_success() {
-        anyhow::bail!("Download failed with status {}", response.status());
+        return Err(cli_failure(
+            FailureKind::Network,
+            format!("Download failed with status {}", response.status()),
+        ));
     }
 
     let total_size = response
@@ -761,32 +1887,85 @@ fn download_template(
         .and_then(|s| s.parse::<u64>().ok())
         .unwrap_or(0);
 
-    let pb = ProgressBar::new(total_size);
+    let pb = if reporter.json {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(total_size)
+    };
     pb.set_style(ProgressStyle::default_bar()
         .template("{msg} {bar:40.magenta/blue} {bytes}/{total_bytes} ({eta})")?
         .progress_chars("=>-"));
 
     let mut data = Vec::new();
     let mut stream = response;
+    let mut buf = [0u8; 8192];
     let mut downloaded = 0;
-    while let Some(chunk) = stream.chunk().context("Error reading download stream")? {
-        data.extend_from_slice(&chunk);
-        downloaded += chunk.len() as u64;
+    loop {
+        let n = stream
+            .read(&mut buf)
+            .map_err(|e| cli_failure(FailureKind::Network, format!("Error reading download stream: {}", e)))?;
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+        downloaded += n as u64;
         pb.set_position(downloaded);
     }
     pb.finish_and_clear();
 
-    let zip_path = tempdir()?.path().join(&asset.name);
+    // Verify against a published `<asset>.sha256` when the release provides one
+    let checksum_name = format!("{}.sha256", asset.name);
+    let checksum = match release.assets.iter().find(|a| a.name == checksum_name) {
+        Some(checksum_asset) => {
+            let expected = client
+                .get(&checksum_asset.browser_download_url)
+                .headers(headers)
+                .send()
+                .and_then(|r| r.error_for_status())
+                .and_then(|r| r.text())
+                .map_err(|e| cli_failure(FailureKind::Network, format!("Failed to download checksum: {}", e)))?;
+            let expected = expected.split_whitespace().next().unwrap_or_default().to_lowercase();
+            let actual = sha256_hex(&data);
+            if expected != actual {
+                return Err(cli_failure(
+                    FailureKind::Checksum,
+                    format!("Checksum mismatch for {}: expected {}, got {}", asset.name, expected, actual),
+                ));
+            }
+            Some(actual)
+        }
+        None => {
+            reporter.warn("download", &format!("No checksum published for {}, skipping verification", asset.name));
+            None
+        }
+    };
+
+    let dir = tempdir()?;
+    let zip_path = dir.path().join(&asset.name);
     fs::write(&zip_path, data)?;
 
-    Ok((zip_path, release.tag_name, asset.name.clone()))
+    Ok(DownloadedTemplate {
+        zip_path,
+        release_tag: release.tag_name,
+        asset_name: asset.name.clone(),
+        checksum,
+        _dir: dir,
+    })
 }
 
-fn extract_template(zip_path: &Path, dest: &Path, flatten_nested: bool) -> Result<()> {
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Extracts the template and returns the files it wrote, relative to `dest`.
+fn extract_template(zip_path: &Path, dest: &Path, flatten_nested: bool) -> Result<Vec<String>> {
     let file = File::open(zip_path)?;
     let mut archive = ZipArchive::new(file)?;
 
-    if flatten_nested {
+    let files_written = if flatten_nested {
         // Extract to temp, then flatten
         let temp_dir = tempdir()?;
         archive.extract(temp_dir.path())?;
@@ -802,7 +1981,9 @@ fn extract_template(zip_path: &Path, dest: &Path, flatten_nested: bool) -> Resul
             extracted.to_path_buf()
         };
 
+        let files = list_files(&source)?;
         copy_dir_all(&source, dest)?;
+        files
     } else {
         archive.extract(dest)?;
 
@@ -818,7 +1999,9 @@ fn extract_template(zip_path: &Path, dest: &Path, flatten_nested: bool) -> Resul
             fs::remove_dir(dest)?;
             fs::rename(&temp_move, dest)?;
         }
-    }
+
+        list_files(dest)?
+    };
 
     // Merge .vscode/settings.json if present
     let vscode_settings_src = dest.join(".vscode").join("settings.json");
@@ -830,7 +2013,20 @@ fn extract_template(zip_path: &Path, dest: &Path, flatten_nested: bool) -> Resul
         }
     }
 
-    Ok(())
+    Ok(files_written)
+}
+
+fn list_files(root: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(root) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            files.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    files.sort();
+    Ok(files)
 }
 
 fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
@@ -915,8 +2111,8 @@ fn global_dir() -> PathBuf {
     home_dir().unwrap().join(".agents/skills")
 }
 
-fn agent_dir(agent: &str) -> PathBuf {
-    home_dir().unwrap().join(format!(".{}/skills", agent))
+fn agent_dir(registry: &AgentRegistry, agent: &str) -> PathBuf {
+    registry.skills_dir(&home_dir().unwrap(), agent)
 }
 
 fn download_repo(
@@ -0,0 +1,386 @@
+use crate::agents::{AgentDefinition, CommandFormat};
+use crate::converter::gemini::{self, GeminiCommand};
+use crate::converter::ir::SkillCommand;
+use crate::converter::{claude, copilot, placeholders};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const MANIFEST_PATH: &str = ".bl1nk/project.json";
+
+/// Records what `bl init` set up so `bl add-agent` can extend it later.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectManifest {
+    pub agents: Vec<String>,
+    pub script: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_version: Option<String>,
+}
+
+impl ProjectManifest {
+    pub fn load(project: &Path) -> Result<Option<Self>> {
+        let path = project.join(MANIFEST_PATH);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)?;
+        let manifest = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid project manifest {}", path.display()))?;
+        Ok(Some(manifest))
+    }
+
+    pub fn save(&self, project: &Path) -> Result<()> {
+        let path = project.join(MANIFEST_PATH);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = serde_json::to_string_pretty(self)?;
+        content.push('\n');
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+/// Outcome of layering one agent's template on top of an existing project.
+#[derive(Debug, Default, Serialize)]
+pub struct MergeReport {
+    /// Files copied because the project did not have them yet
+    pub added: Vec<String>,
+    /// Files the project already had with identical content
+    pub deduplicated: Vec<String>,
+    /// Files the project already had with different content; the project copy is kept
+    pub conflicts: Vec<String>,
+}
+
+/// Copy `src` into `dest` without overwriting anything already there.
+pub fn merge_template_dir(src: &Path, dest: &Path) -> Result<MergeReport> {
+    let mut report = MergeReport::default();
+
+    for entry in walkdir::WalkDir::new(src) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(src).unwrap_or(entry.path());
+        let relative_str = relative.to_string_lossy().replace('\\', "/");
+        let target = dest.join(relative);
+
+        if target.exists() {
+            if fs::read(&target)? == fs::read(entry.path())? {
+                report.deduplicated.push(relative_str);
+            } else {
+                report.conflicts.push(relative_str);
+            }
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(entry.path(), &target)?;
+        report.added.push(relative_str);
+    }
+
+    Ok(report)
+}
+
+/// Command files generated for one agent from another agent's commands.
+#[derive(Debug, Default, Serialize)]
+pub struct GeneratedCommands {
+    /// Files written, relative to the project
+    pub written: Vec<String>,
+    /// Placeholders that did not translate exactly, and commands the target format cannot hold
+    pub warnings: Vec<String>,
+}
+
+/// Generate `to`'s command files from the commands another agent already has in
+/// the project, translating between the agents' command formats with the
+/// converter's placeholder translation.
+/// Existing files are left alone.
+pub fn generate_agent_commands(project: &Path, from: &AgentDefinition, to: &AgentDefinition) -> Result<GeneratedCommands> {
+    let src_dir = project.join(&from.commands_dir);
+    let dest_dir = project.join(&to.commands_dir);
+    let mut generated = GeneratedCommands::default();
+
+    if !src_dir.is_dir() {
+        return Ok(generated);
+    }
+
+    for entry in walkdir::WalkDir::new(&src_dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let mut warnings = Vec::new();
+        let Some(command) = read_command(entry.path(), &src_dir, from.command_format, &mut warnings)? else {
+            continue;
+        };
+
+        let target = dest_dir.join(format!("{}.{}", command.name, to.command_format.extension()));
+        if !target.exists() {
+            if let Some(content) = render_command(&command, to.command_format, &mut warnings)? {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&target, content)?;
+
+                let relative = target.strip_prefix(project).unwrap_or(&target);
+                generated.written.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+        generated
+            .warnings
+            .extend(warnings.into_iter().map(|w| format!("Command {}: {}", command.name, w)));
+    }
+
+    generated.written.sort();
+    Ok(generated)
+}
+
+/// Read one command file into the IR, whose prompts use Claude's syntax.
+fn read_command(
+    path: &Path,
+    root: &Path,
+    format: CommandFormat,
+    warnings: &mut Vec<String>,
+) -> Result<Option<SkillCommand>> {
+    let relative = path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/");
+    let Some(name) = relative
+        .strip_suffix(format.extension())
+        .and_then(|name| name.strip_suffix('.'))
+        .filter(|name| !name.is_empty() && !name.ends_with('/'))
+    else {
+        return Ok(None);
+    };
+    let name = name.to_string();
+    let content = fs::read_to_string(path)?;
+
+    Ok(Some(match format {
+        CommandFormat::Md => claude::parse_markdown_command(name, &content),
+        CommandFormat::Prompt => copilot::parse_prompt(name, &content, warnings),
+        CommandFormat::Toml => {
+            let command: GeminiCommand = toml::from_str(&content)
+                .with_context(|| format!("Invalid command file {}", path.display()))?;
+            let (prompt, prompt_warnings) = placeholders::gemini_to_claude(command.prompt.trim());
+            warnings.extend(prompt_warnings);
+            SkillCommand {
+                name,
+                description: command.description,
+                prompt,
+                ..Default::default()
+            }
+        }
+    }))
+}
+
+/// Render one command in `to`'s format; `None` when the format cannot hold it.
+fn render_command(command: &SkillCommand, to: CommandFormat, warnings: &mut Vec<String>) -> Result<Option<String>> {
+    Ok(Some(match to {
+        CommandFormat::Md => claude::render_markdown_command(command),
+        CommandFormat::Prompt => copilot::render_prompt(command, warnings)?,
+        CommandFormat::Toml => {
+            let (prompt, prompt_warnings) = placeholders::claude_to_gemini(&command.prompt);
+            warnings.extend(prompt_warnings);
+            let gemini = GeminiCommand {
+                description: command.description.clone(),
+                prompt: format!("{}\n", prompt.trim()),
+            };
+            match gemini::encode_command("", &gemini) {
+                Ok(content) => content,
+                Err(reason) => {
+                    warnings.push(format!("skipped, {}", reason));
+                    return Ok(None);
+                }
+            }
+        }
+    }))
+}
+
+/// Give `to` its own context file by copying the first one another agent already has.
+pub fn ensure_context_file(project: &Path, existing: &[&AgentDefinition], to: &AgentDefinition) -> Result<Option<String>> {
+    let target = project.join(&to.context_file);
+    if target.exists() {
+        return Ok(None);
+    }
+    let Some(source) = existing
+        .iter()
+        .map(|def| project.join(&def.context_file))
+        .find(|path| path.is_file())
+    else {
+        return Ok(None);
+    };
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(source, &target)?;
+    Ok(Some(to.context_file.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(commands_dir: &str, command_format: CommandFormat, context_file: &str) -> AgentDefinition {
+        AgentDefinition {
+            name: "Test".to_string(),
+            folder: ".test/".to_string(),
+            install_url: None,
+            requires_cli: false,
+            cli: None,
+            version_args: Vec::new(),
+            min_version: None,
+            skills_dir: ".test/skills".to_string(),
+            commands_dir: commands_dir.to_string(),
+            command_format,
+            context_file: context_file.to_string(),
+            credential_paths: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn merge_keeps_existing_files() {
+        let template = tempfile::tempdir().unwrap();
+        let project = tempfile::tempdir().unwrap();
+        fs::create_dir_all(template.path().join("scripts")).unwrap();
+        fs::write(template.path().join("scripts/new.sh"), "echo new\n").unwrap();
+        fs::write(template.path().join("same.md"), "same\n").unwrap();
+        fs::write(template.path().join("changed.md"), "template\n").unwrap();
+        fs::write(project.path().join("same.md"), "same\n").unwrap();
+        fs::write(project.path().join("changed.md"), "mine\n").unwrap();
+
+        let report = merge_template_dir(template.path(), project.path()).unwrap();
+
+        assert_eq!(report.added, vec!["scripts/new.sh"]);
+        assert_eq!(report.deduplicated, vec!["same.md"]);
+        assert_eq!(report.conflicts, vec!["changed.md"]);
+        assert_eq!(fs::read_to_string(project.path().join("scripts/new.sh")).unwrap(), "echo new\n");
+        assert_eq!(fs::read_to_string(project.path().join("changed.md")).unwrap(), "mine\n");
+    }
+
+    #[test]
+    fn commands_are_translated_between_formats() {
+        let project = tempfile::tempdir().unwrap();
+        let claude = agent(".claude/commands", CommandFormat::Md, "CLAUDE.md");
+        let gemini = agent(".gemini/commands", CommandFormat::Toml, "GEMINI.md");
+        fs::create_dir_all(project.path().join(".claude/commands/git")).unwrap();
+        fs::write(
+            project.path().join(".claude/commands/git/commit.md"),
+            "---\ndescription: \"Write a commit\"\n---\n\nCommit $ARGUMENTS touching $1 and !`git diff $1`\n",
+        )
+        .unwrap();
+        fs::write(project.path().join(".claude/commands/notes.txt"), "ignored\n").unwrap();
+
+        let generated = generate_agent_commands(project.path(), &claude, &gemini).unwrap();
+        assert_eq!(generated.written, vec![".gemini/commands/git/commit.toml"]);
+        // $1 inside the shell command can only become {{args}}
+        assert_eq!(generated.warnings.len(), 1);
+        assert!(generated.warnings[0].starts_with("Command git/commit: positional argument $1"));
+
+        let table: toml::Table = fs::read_to_string(project.path().join(&generated.written[0]))
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(table["description"].as_str(), Some("Write a commit"));
+        let prompt = table["prompt"].as_str().unwrap();
+        assert!(prompt.starts_with(placeholders::POSITIONAL_PREAMBLE));
+        assert!(prompt.contains("Commit {{args}} touching [argument 1] and !{git diff {{args}}}"));
+
+        // On to Copilot, without touching a command the agent already has
+        let copilot = agent(".github/prompts", CommandFormat::Prompt, ".github/copilot-instructions.md");
+        fs::create_dir_all(project.path().join(".github/prompts/git")).unwrap();
+        fs::write(project.path().join(".github/prompts/git/commit.prompt.md"), "mine\n").unwrap();
+        fs::write(
+            project.path().join(".gemini/commands/review.toml"),
+            "description = \"Review\"\nprompt = \"Review {{args}} against @{docs/style.md}\"\n",
+        )
+        .unwrap();
+
+        let generated = generate_agent_commands(project.path(), &gemini, &copilot).unwrap();
+        assert_eq!(generated.written, vec![".github/prompts/review.prompt.md"]);
+        let review = fs::read_to_string(project.path().join(".github/prompts/review.prompt.md")).unwrap();
+        assert!(review.starts_with("---\ndescription: Review\nmode: agent\n---\n"));
+        assert!(review.contains("Review ${input:args} against #file:docs/style.md"));
+        assert_eq!(
+            fs::read_to_string(project.path().join(".github/prompts/git/commit.prompt.md")).unwrap(),
+            "mine\n"
+        );
+
+        // And back, reading the command names without the .prompt suffix
+        let claude_only = tempfile::tempdir().unwrap();
+        fs::create_dir_all(claude_only.path().join(".github")).unwrap();
+        fs::rename(project.path().join(".github/prompts"), claude_only.path().join(".github/prompts")).unwrap();
+        let generated = generate_agent_commands(claude_only.path(), &copilot, &claude).unwrap();
+        assert_eq!(generated.written, vec![".claude/commands/git/commit.md", ".claude/commands/review.md"]);
+        let review = fs::read_to_string(claude_only.path().join(".claude/commands/review.md")).unwrap();
+        assert!(review.contains("Review $ARGUMENTS against @docs/style.md"));
+    }
+
+    #[test]
+    fn commands_gemini_cannot_hold_are_reported() {
+        let project = tempfile::tempdir().unwrap();
+        let claude = agent(".claude/commands", CommandFormat::Md, "CLAUDE.md");
+        let gemini = agent(".gemini/commands", CommandFormat::Toml, "GEMINI.md");
+        fs::create_dir_all(project.path().join(".claude/commands")).unwrap();
+        fs::write(project.path().join(".claude/commands/empty.md"), "---\ndescription: Nothing\n---\n").unwrap();
+
+        let generated = generate_agent_commands(project.path(), &claude, &gemini).unwrap();
+        assert!(generated.written.is_empty());
+        assert_eq!(
+            generated.warnings,
+            vec!["Command empty: skipped, Gemini commands need a non-empty prompt"]
+        );
+        assert!(!project.path().join(".gemini/commands/empty.toml").exists());
+    }
+
+    #[test]
+    fn context_file_is_copied_once() {
+        let project = tempfile::tempdir().unwrap();
+        let claude = agent(".claude/commands", CommandFormat::Md, "CLAUDE.md");
+        let copilot = agent(".github/prompts", CommandFormat::Prompt, ".github/copilot-instructions.md");
+        let codex = agent(".codex/prompts", CommandFormat::Md, "AGENTS.md");
+
+        // Nothing to copy from yet
+        assert_eq!(ensure_context_file(project.path(), &[&claude], &copilot).unwrap(), None);
+
+        fs::write(project.path().join("CLAUDE.md"), "# Project\n").unwrap();
+        assert_eq!(
+            ensure_context_file(project.path(), &[&codex, &claude], &copilot).unwrap().as_deref(),
+            Some(".github/copilot-instructions.md")
+        );
+        assert_eq!(
+            fs::read_to_string(project.path().join(".github/copilot-instructions.md")).unwrap(),
+            "# Project\n"
+        );
+
+        // An existing context file is never overwritten
+        fs::write(project.path().join("AGENTS.md"), "mine\n").unwrap();
+        assert_eq!(ensure_context_file(project.path(), &[&claude], &codex).unwrap(), None);
+        assert_eq!(fs::read_to_string(project.path().join("AGENTS.md")).unwrap(), "mine\n");
+    }
+
+    #[test]
+    fn manifest_round_trips_agent_updates() {
+        let project = tempfile::tempdir().unwrap();
+        assert!(ProjectManifest::load(project.path()).unwrap().is_none());
+
+        let mut manifest = ProjectManifest {
+            agents: vec!["claude".to_string()],
+            script: "sh".to_string(),
+            template_version: Some("v0.1.0".to_string()),
+        };
+        manifest.save(project.path()).unwrap();
+
+        manifest.agents.push("gemini".to_string());
+        manifest.save(project.path()).unwrap();
+
+        let loaded = ProjectManifest::load(project.path()).unwrap().unwrap();
+        assert_eq!(loaded.agents, vec!["claude", "gemini"]);
+        assert_eq!(loaded.script, "sh");
+        assert_eq!(loaded.template_version.as_deref(), Some("v0.1.0"));
+
+        fs::write(project.path().join(MANIFEST_PATH), "{").unwrap();
+        assert!(ProjectManifest::load(project.path()).is_err());
+    }
+}
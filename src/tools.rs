@@ -0,0 +1,171 @@
+use dirs::home_dir;
+use std::cmp::Ordering;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Dotted numeric version as printed by `<tool> --version`; missing
+/// components compare as zero so `2.1` == `2.1.0`.
+#[derive(Debug, Clone)]
+pub struct Version {
+    parts: Vec<u64>,
+}
+
+impl Version {
+    /// Parse the first `N.N[.N...]` run found anywhere in `text`, allowing a
+    /// leading `v`/`V` as in `v20.11.1`.
+    pub fn find_in(text: &str) -> Option<Self> {
+        let bytes = text.as_bytes();
+        let is_boundary = |i: usize| {
+            let i = match i.checked_sub(1) {
+                Some(prev) if matches!(bytes[prev], b'v' | b'V') => prev,
+                _ => i,
+            };
+            i == 0 || !bytes[i - 1].is_ascii_alphanumeric()
+        };
+        let mut start = 0;
+        while start < bytes.len() {
+            if bytes[start].is_ascii_digit() && is_boundary(start) {
+                let end = bytes[start..]
+                    .iter()
+                    .position(|b| !(b.is_ascii_digit() || *b == b'.'))
+                    .map_or(bytes.len(), |n| start + n);
+                let candidate = text[start..end].trim_end_matches('.');
+                if candidate.contains('.') {
+                    if let Some(version) = Self::parse(candidate) {
+                        return Some(version);
+                    }
+                }
+                start = end;
+            } else {
+                start += 1;
+            }
+        }
+        None
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let parts = text
+            .strip_prefix(['v', 'V'])
+            .unwrap_or(text)
+            .split('.')
+            .map(|p| p.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        if parts.is_empty() {
+            return None;
+        }
+        Some(Self { parts })
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.parts.len().max(other.parts.len());
+        for i in 0..len {
+            let a = self.parts.get(i).copied().unwrap_or(0);
+            let b = other.parts.get(i).copied().unwrap_or(0);
+            match a.cmp(&b) {
+                Ordering::Equal => continue,
+                ord => return ord,
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.parts.iter().map(|p| p.to_string()).collect();
+        f.write_str(&parts.join("."))
+    }
+}
+
+/// Locate a tool on PATH, including Claude's per-user local install.
+pub fn resolve_tool(tool: &str) -> Option<PathBuf> {
+    if tool == "claude" {
+        let claude_local = home_dir()
+            .map(|p| p.join(".claude").join("local").join("claude"))
+            .filter(|p| p.exists() && p.is_file());
+        if claude_local.is_some() {
+            return claude_local;
+        }
+    }
+    which::which(tool).ok()
+}
+
+/// Run the tool's version command and parse whatever it prints.
+pub fn probe_version(binary: &Path, args: &[String]) -> Option<Version> {
+    let output = Command::new(binary).args(args).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Version::find_in(&stdout).or_else(|| Version::find_in(&stderr))
+}
+
+/// Read a git config value, returning `None` when unset or git is unavailable.
+pub fn git_config(key: &str) -> Option<String> {
+    let output = Command::new("git").args(["config", "--get", key]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(text: &str) -> Version {
+        Version::parse(text).unwrap()
+    }
+
+    #[test]
+    fn finds_version_in_tool_output() {
+        assert_eq!(Version::find_in("v20.11.1").unwrap().to_string(), "20.11.1");
+        assert_eq!(Version::find_in("node V18.2.0\n").unwrap().to_string(), "18.2.0");
+        assert_eq!(Version::find_in("git version 2.43.0").unwrap().to_string(), "2.43.0");
+        assert_eq!(Version::find_in("1.0.30 (Claude Code)").unwrap().to_string(), "1.0.30");
+        assert_eq!(Version::find_in("codex-cli 0.46.0.").unwrap().to_string(), "0.46.0");
+        // Digits glued to a word are part of a name, not a version
+        assert_eq!(Version::find_in("python3.12 tool 1.2").unwrap().to_string(), "1.2");
+        assert!(Version::find_in("build 42").is_none());
+        assert!(Version::find_in("").is_none());
+    }
+
+    #[test]
+    fn parses_dotted_versions() {
+        assert_eq!(v("v1.2.3").to_string(), "1.2.3");
+        assert_eq!(v(" V0.9 ").to_string(), "0.9");
+        assert!(Version::parse("1.x").is_none());
+        assert!(Version::parse("vv1.2").is_none());
+        assert!(Version::parse("").is_none());
+    }
+
+    #[test]
+    fn compares_with_missing_components_as_zero() {
+        assert!(v("2.1") == v("2.1.0"));
+        assert_eq!(v("2.1").cmp(&v("2.1.0")), Ordering::Equal);
+        assert!(v("2.1.1") > v("2.1"));
+        assert!(v("2.10") > v("2.9"));
+        assert!(v("1.99.99") < v("2"));
+    }
+}
@@ -33,3 +33,210 @@ fn install_requires_repo_or_url() {
     .assert()
     .failure();
 }
+
+#[test]
+fn init_non_interactive_requires_ai() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut cmd = Command::cargo_bin("bl1nk-cli").unwrap();
+
+    cmd.current_dir(tmp.path())
+        .args(["init", "demo", "--json", "--script", "sh", "--no-git"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("\"class\":\"usage\""));
+}
+
+#[test]
+fn init_existing_directory_is_a_conflict() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::create_dir(tmp.path().join("demo")).unwrap();
+    let mut cmd = Command::cargo_bin("bl1nk-cli").unwrap();
+
+    cmd.current_dir(tmp.path())
+        .args(["init", "demo", "--non-interactive", "--ai", "claude", "--script", "sh"])
+        .assert()
+        .code(5);
+}
+
+#[test]
+fn agents_uses_registry_overrides() {
+    let tmp = tempfile::tempdir().unwrap();
+    let home = tmp.path().join("home");
+    std::fs::create_dir_all(home.join(".acme-agent").join("skills")).unwrap();
+
+    let overrides = tmp.path().join("agents.toml");
+    std::fs::write(
+        &overrides,
+        r#"
+[agents.acme]
+name = "Acme Agent"
+folder = ".acme/"
+skills_dir = ".acme-agent/skills"
+commands_dir = ".acme/commands"
+command_format = "md"
+context_file = "AGENTS.md"
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("bl1nk-cli").unwrap();
+    cmd.env("HOME", &home)
+        .env("BL1NK_AGENTS_FILE", &overrides)
+        .arg("agents")
+        .assert()
+        .success()
+        // Listed once under its key, not again as `acme-agent` by the
+        // ~/.<agent>/skills fallback
+        .stdout("acme\n");
+}
+
+#[test]
+fn check_fails_when_template_requirement_is_not_met() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(tmp.path().join(".bl1nk")).unwrap();
+    std::fs::write(
+        tmp.path().join(".bl1nk").join("requirements.toml"),
+        "[tools]\ngit = \"999.0\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("bl1nk-cli").unwrap();
+    cmd.current_dir(tmp.path())
+        .args(["check", "--json"])
+        .assert()
+        .code(6)
+        .stdout(predicate::str::contains("\"ok\": false"));
+}
+
+#[test]
+fn init_rejects_unknown_agent_in_list() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut cmd = Command::cargo_bin("bl1nk-cli").unwrap();
+
+    cmd.current_dir(tmp.path())
+        .args(["init", "demo", "--json", "--ai", "claude,nope", "--script", "sh", "--no-git"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("Invalid AI assistant 'nope'"));
+}
+
+#[test]
+fn add_agent_requires_initialized_project() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut cmd = Command::cargo_bin("bl1nk-cli").unwrap();
+
+    cmd.current_dir(tmp.path())
+        .args(["add-agent", "copilot", "--json"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("Not a Bl1nk project"));
+}
+
+#[test]
+fn convert_detects_claude_skill_and_writes_gemini_extension() {
+    let tmp = tempfile::tempdir().unwrap();
+    let skill = tmp.path().join("skill");
+    let out = tmp.path().join("out");
+    std::fs::create_dir_all(&skill).unwrap();
+    std::fs::write(
+        skill.join("SKILL.md"),
+        "---\nname: demo\ndescription: Demo skill\n---\n# Demo\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("bl1nk-cli").unwrap();
+    cmd.arg("convert")
+        .arg(&skill)
+        .arg("-o")
+        .arg(&out)
+        .arg("--json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"to\": \"gemini\""))
+        .stdout(predicate::str::contains("\"success\": true"));
+
+    assert!(out.join("gemini-extension.json").exists());
+}
+
+#[test]
+fn convert_fails_when_conversion_reports_errors() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(tmp.path().join("SKILL.md"), "# no frontmatter\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("bl1nk-cli").unwrap();
+    cmd.arg("convert")
+        .arg(tmp.path())
+        .args(["--to", "gemini", "-o"])
+        .arg(tmp.path().join("out"))
+        .assert()
+        .code(8)
+        .stdout(predicate::str::contains("missing YAML frontmatter"));
+}
+
+#[test]
+fn convert_to_codex_reports_unmapped_parts() {
+    let tmp = tempfile::tempdir().unwrap();
+    let skill = tmp.path().join("skill");
+    let out = tmp.path().join("out");
+    std::fs::create_dir_all(&skill).unwrap();
+    std::fs::write(
+        skill.join("SKILL.md"),
+        "---\nname: demo\ndescription: Demo skill\nallowed-tools: Read, Grep\n---\n# Demo\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("bl1nk-cli").unwrap();
+    cmd.arg("convert")
+        .arg(&skill)
+        .args(["--to", "codex", "-o"])
+        .arg(&out)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Not mapped to codex"))
+        .stdout(predicate::str::contains("Tool permissions"));
+
+    assert!(out.join("AGENTS.md").exists());
+}
+
+#[test]
+fn convert_rejects_write_only_source_format() {
+    let tmp = tempfile::tempdir().unwrap();
+
+    let mut cmd = Command::cargo_bin("bl1nk-cli").unwrap();
+    cmd.arg("convert")
+        .arg(tmp.path())
+        .args(["--from", "cursor", "--to", "claude"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cursor can only be used with --to"));
+}
+
+#[test]
+fn convert_recursive_reports_each_skill() {
+    let tmp = tempfile::tempdir().unwrap();
+    let skills = tmp.path().join("skills");
+    let out = tmp.path().join("dist");
+    for name in ["alpha", "beta"] {
+        std::fs::create_dir_all(skills.join(name)).unwrap();
+        std::fs::write(
+            skills.join(name).join("SKILL.md"),
+            format!("---\nname: {}\ndescription: The {} skill\n---\n# {}\n", name, name, name),
+        )
+        .unwrap();
+    }
+
+    let mut cmd = Command::cargo_bin("bl1nk-cli").unwrap();
+    cmd.arg("convert")
+        .arg(&skills)
+        .args(["--recursive", "--to", "gemini", "-o"])
+        .arg(&out)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Converted 2 of 2 skills to gemini"));
+
+    assert!(out.join("alpha").join("gemini-extension.json").exists());
+    assert!(out.join("gemini-extensions.json").exists());
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(out.join("conversion-report.json")).unwrap()).unwrap();
+    assert_eq!(report["succeeded"], 2);
+}